@@ -7,3 +7,4 @@ npm --prefix "$ROOT_DIR" ci
 npm --prefix "$ROOT_DIR" run check
 cargo fmt --manifest-path "$ROOT_DIR/src-tauri/Cargo.toml" --check
 cargo clippy --manifest-path "$ROOT_DIR/src-tauri/Cargo.toml" --all-targets -- -D warnings
+cargo test --manifest-path "$ROOT_DIR/src-tauri/Cargo.toml"
@@ -0,0 +1,260 @@
+//! What the global shortcuts do. Handlers drive windows through a
+//! [`WindowHost`] and take the selection capture and profile switch as
+//! arguments, so they can run in tests without a desktop.
+
+use serde::Serialize;
+
+use crate::credentials::{self, load_credentials};
+use crate::gateway::abort_chat;
+use crate::notch::{restore_from_notch, NOTCH_LABEL};
+use crate::panel::{dismiss_panel, present_panel, transition, WINDOW_LABEL};
+use crate::panel_state::{PanelEvent, PanelState};
+use crate::selection::{capture_selected_text, SelectionPrefillPayload};
+use crate::shortcuts::ShortcutAction;
+use crate::window::{emit, WindowHost};
+use crate::{redaction, source_app, write_back};
+
+pub fn handle_action(host: &dyn WindowHost, action: &ShortcutAction) {
+    match action {
+        ShortcutAction::TogglePanel => handle_shortcut(host),
+        ShortcutAction::CaptureSelection => {
+            handle_selection_shortcut(host, capture_selection_payload)
+        }
+        ShortcutAction::RestoreNotch => restore_from_notch(host, None),
+        ShortcutAction::OpenProfile { profile } => {
+            handle_open_profile(host, || credentials::activate_profile(profile.to_string()))
+        }
+        ShortcutAction::RunPrompt { prompt } => {
+            handle_run_prompt(host, prompt, capture_selection_payload)
+        }
+        ShortcutAction::AbortStreaming => {
+            if let Err(error) = abort_chat() {
+                eprintln!("abort shortcut failed: {error}");
+            }
+        }
+    }
+}
+
+fn handle_shortcut(host: &dyn WindowHost) {
+    match host.panel().state() {
+        // If a background response is active, restore from notch.
+        PanelState::Background => restore_from_notch(host, None),
+        // The panel is already on its way back.
+        PanelState::Restoring => {}
+        state if state.is_panel_open() => dismiss_panel(host),
+        _ => present_panel(host),
+    }
+}
+
+/// Leave background/notch mode before an action that takes over the panel.
+fn leave_background_mode(host: &dyn WindowHost) {
+    if transition(host, PanelEvent::LeaveBackground) {
+        if let Some(notch) = host.window(NOTCH_LABEL) {
+            notch.hide();
+        }
+    }
+}
+
+fn capture_selection_payload() -> SelectionPrefillPayload {
+    let creds = load_credentials().unwrap_or_default();
+    // Look up the app before capturing; simulated copy can shift focus.
+    let app = source_app::frontmost_app();
+    if let Some(target) = app.as_ref().and_then(|app| app.focus.clone()) {
+        write_back::remember(target);
+    }
+    let app_prompt = app
+        .as_ref()
+        .and_then(|app| source_app::app_prompt(&creds.selection.app_prompts, app))
+        .map(str::to_string);
+    let (text, source, error) = match capture_selected_text(&creds.selection) {
+        Ok(captured) => (captured.text, captured.source, None),
+        Err(error) => (String::new(), None, Some(error)),
+    };
+    // Redact the whole text so secrets straddling the cut are still found.
+    let redacted = redaction::redact(&text, &creds.redaction);
+    let cut = creds.selection.truncate(&redacted.text);
+
+    SelectionPrefillPayload {
+        has_text: !cut.text.is_empty(),
+        text: cut.text,
+        error,
+        source,
+        redacted: redacted.count,
+        truncated: cut.truncated,
+        original_chars: cut.original_chars,
+        app,
+        app_prompt,
+    }
+}
+
+fn handle_selection_shortcut(
+    host: &dyn WindowHost,
+    capture: impl FnOnce() -> SelectionPrefillPayload,
+) {
+    // Any explicit selection capture restores the main panel and disables
+    // background/notch mode to keep interaction deterministic.
+    leave_background_mode(host);
+
+    if let Some(window) = host.window(WINDOW_LABEL) {
+        let payload = capture();
+        present_panel(host);
+        emit(&*window, "panel-prefill-selection", payload);
+    }
+}
+
+fn handle_open_profile<T: Serialize>(
+    host: &dyn WindowHost,
+    activate: impl FnOnce() -> Result<T, String>,
+) {
+    leave_background_mode(host);
+
+    let creds = match activate() {
+        Ok(creds) => creds,
+        Err(error) => {
+            eprintln!("profile shortcut failed: {error}");
+            return;
+        }
+    };
+
+    if let Some(window) = host.window(WINDOW_LABEL) {
+        present_panel(host);
+        // The panel reconnects with the new settings.
+        emit(&*window, "profile-activated", creds);
+    }
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RunPromptPayload {
+    prompt: String,
+    selection: SelectionPrefillPayload,
+}
+
+fn handle_run_prompt(
+    host: &dyn WindowHost,
+    prompt: &str,
+    capture: impl FnOnce() -> SelectionPrefillPayload,
+) {
+    leave_background_mode(host);
+
+    if let Some(window) = host.window(WINDOW_LABEL) {
+        let payload = RunPromptPayload {
+            prompt: prompt.to_string(),
+            selection: capture(),
+        };
+        present_panel(host);
+        emit(&*window, "panel-run-prompt", payload);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::window::testing::FakeHost;
+    use serde_json::json;
+
+    fn host() -> FakeHost {
+        FakeHost::new(&[WINDOW_LABEL, NOTCH_LABEL])
+    }
+
+    fn selection(text: &str) -> SelectionPrefillPayload {
+        SelectionPrefillPayload {
+            text: text.to_string(),
+            has_text: !text.is_empty(),
+            error: None,
+            source: None,
+            redacted: 0,
+            truncated: false,
+            original_chars: text.chars().count(),
+            app: None,
+            app_prompt: None,
+        }
+    }
+
+    fn in_background(host: &FakeHost) {
+        transition(host, PanelEvent::SendToBackground);
+        host.take_calls();
+    }
+
+    #[test]
+    fn toggle_presents_then_dismisses() {
+        let host = host();
+        handle_shortcut(&host);
+        assert_eq!(host.panel().state(), PanelState::Compact);
+        assert!(host.take_calls().contains(&"main show".to_string()));
+
+        handle_shortcut(&host);
+        assert_eq!(host.panel().state(), PanelState::Hidden);
+        assert_eq!(host.take_calls(), ["main emit panel-state", "main hide"]);
+    }
+
+    #[test]
+    fn toggle_restores_a_background_response_once() {
+        let host = host();
+        in_background(&host);
+
+        handle_shortcut(&host);
+        let calls = host.take_calls();
+        assert!(calls.contains(&"notch hide".to_string()));
+        assert!(calls.contains(&"main emit notch-restore".to_string()));
+        assert_eq!(host.panel().state(), PanelState::Expanded);
+    }
+
+    #[test]
+    fn toggle_is_ignored_while_restoring() {
+        let host = host();
+        in_background(&host);
+        transition(&host, PanelEvent::Restore);
+        host.take_calls();
+
+        handle_shortcut(&host);
+        handle_action(&host, &ShortcutAction::RestoreNotch);
+        assert!(host.take_calls().is_empty());
+        assert_eq!(host.panel().state(), PanelState::Restoring);
+    }
+
+    #[test]
+    fn selection_shortcut_drops_the_notch_and_prefills() {
+        let host = host();
+        in_background(&host);
+
+        handle_selection_shortcut(&host, || selection("fn main() {}"));
+        let calls = host.take_calls();
+        assert_eq!(calls[..2], ["main emit panel-state", "notch hide"]);
+        assert_eq!(
+            calls.last().map(String::as_str),
+            Some("main emit panel-prefill-selection")
+        );
+        assert_eq!(host.panel().state(), PanelState::Compact);
+        assert_eq!(
+            host.payload("panel-prefill-selection").unwrap()["text"],
+            "fn main() {}"
+        );
+    }
+
+    #[test]
+    fn run_prompt_sends_prompt_and_selection() {
+        let host = host();
+        handle_run_prompt(&host, "Explain", || selection("x = 1"));
+
+        assert_eq!(host.panel().state(), PanelState::Compact);
+        let payload = host.payload("panel-run-prompt").unwrap();
+        assert_eq!(payload["prompt"], "Explain");
+        assert_eq!(payload["selection"]["hasText"], true);
+    }
+
+    #[test]
+    fn open_profile_presents_only_after_switching() {
+        let host = host();
+        handle_open_profile(&host, || Err::<(), _>("unknown profile".to_string()));
+        assert!(host.take_calls().is_empty());
+        assert_eq!(host.panel().state(), PanelState::Hidden);
+
+        handle_open_profile(&host, || Ok(json!({"activeProfile": "work"})));
+        assert_eq!(host.panel().state(), PanelState::Compact);
+        assert_eq!(
+            host.payload("profile-activated"),
+            Some(json!({"activeProfile": "work"}))
+        );
+    }
+}
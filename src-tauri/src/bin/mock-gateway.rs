@@ -0,0 +1,84 @@
+//! Standalone mock OpenClaw gateway for CI and offline demos.
+//!
+//! ```text
+//! mock-gateway [--port 19819] [--token T] [--password P] [--reply TEXT]...
+//!              [--chunks N] [--chunk-delay-ms MS] [--handshake-delay-ms MS]
+//!              [--reject-send MSG | --chat-error MSG | --abort-after N | --disconnect-after N]
+//! ```
+
+use std::net::SocketAddr;
+use std::process::ExitCode;
+use std::time::Duration;
+
+use macclaw_lib::mock_gateway::{Fault, MockGateway, MockGatewayConfig};
+
+const DEFAULT_PORT: u16 = 19819;
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<MockGatewayConfig, String> {
+    let mut config = MockGatewayConfig {
+        addr: SocketAddr::from(([127, 0, 0, 1], DEFAULT_PORT)),
+        ..MockGatewayConfig::default()
+    };
+
+    while let Some(flag) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{flag} needs a value"));
+        match flag.as_str() {
+            "--port" => config.addr.set_port(parse_number(&flag, value()?)?),
+            "--token" => config.token = Some(value()?),
+            "--password" => config.password = Some(value()?),
+            "--reply" => config.replies.push(value()?),
+            "--chunks" => config.chunks = parse_number(&flag, value()?)?,
+            "--chunk-delay-ms" => {
+                config.chunk_delay = Duration::from_millis(parse_number(&flag, value()?)?)
+            }
+            "--handshake-delay-ms" => {
+                config.handshake_delay = Duration::from_millis(parse_number(&flag, value()?)?)
+            }
+            "--reject-send" => config.fault = Some(Fault::RejectSend(value()?)),
+            "--chat-error" => config.fault = Some(Fault::ChatError(value()?)),
+            "--abort-after" => {
+                config.fault = Some(Fault::AbortAfter(parse_number(&flag, value()?)?))
+            }
+            "--disconnect-after" => {
+                config.fault = Some(Fault::DisconnectAfter(parse_number(&flag, value()?)?))
+            }
+            "-h" | "--help" => return Err(USAGE.into()),
+            other => return Err(format!("unknown argument: {other}\n\n{USAGE}")),
+        }
+    }
+
+    Ok(config)
+}
+
+fn parse_number<T: std::str::FromStr>(flag: &str, value: String) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{flag} expects a number, got {value:?}"))
+}
+
+const USAGE: &str = "usage: mock-gateway [--port 19819] [--token T] [--password P] [--reply TEXT]...
+                    [--chunks N] [--chunk-delay-ms MS] [--handshake-delay-ms MS]
+                    [--reject-send MSG | --chat-error MSG | --abort-after N | --disconnect-after N]";
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> ExitCode {
+    let config = match parse_args(std::env::args().skip(1)) {
+        Ok(config) => config,
+        Err(message) => {
+            eprintln!("{message}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let gateway = match MockGateway::start(config).await {
+        Ok(gateway) => gateway,
+        Err(error) => {
+            eprintln!("failed to start mock gateway: {error}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    println!("mock gateway listening on {}", gateway.url());
+    std::future::pending::<()>().await;
+    ExitCode::SUCCESS
+}
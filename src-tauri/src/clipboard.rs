@@ -0,0 +1,1024 @@
+//! System clipboard access. Besides plain text, the clipboard can be
+//! snapshotted with every representation the owner offered (rich text,
+//! HTML, images, file URLs) and restored afterwards, so features that borrow
+//! the clipboard leave the user's copy intact.
+
+#[cfg(target_os = "macos")]
+use objc2::{rc::Retained, runtime::ProtocolObject};
+#[cfg(target_os = "macos")]
+use objc2_app_kit::{NSPasteboard, NSPasteboardItem, NSPasteboardTypeString, NSPasteboardWriting};
+#[cfg(target_os = "macos")]
+use objc2_foundation::{NSArray, NSData, NSString};
+#[cfg(any(test, all(unix, not(target_os = "macos"))))]
+use std::sync::atomic::{AtomicI64, Ordering};
+#[cfg(test)]
+use std::sync::{Mutex, PoisonError};
+#[cfg(all(unix, not(target_os = "macos")))]
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    process::{Command, Stdio},
+    sync::{atomic::AtomicBool, Once},
+    thread,
+};
+#[cfg(all(unix, not(target_os = "macos")))]
+use x11rb::{
+    connection::Connection,
+    protocol::{
+        xfixes::{ConnectionExt as _, SelectionEventMask},
+        xproto::ConnectionExt as _,
+        Event,
+    },
+};
+
+/// Pasteboard types marking passwords and other contents not to be kept
+/// (nspasteboard.org).
+#[cfg(target_os = "macos")]
+const CONCEALED_TYPES: [&str; 2] = [
+    "org.nspasteboard.ConcealedType",
+    "org.nspasteboard.TransientType",
+];
+/// Type KDE Plasma and KeePassXC offer with password copies (value
+/// `secret`).
+#[cfg(all(unix, not(target_os = "macos")))]
+const PASSWORD_MANAGER_HINT: &str = "x-kde-passwordManagerHint";
+/// Types read per snapshot on Linux, where every type costs a process.
+#[cfg(all(unix, not(target_os = "macos")))]
+const MAX_SNAPSHOT_TYPES: usize = 16;
+/// Preferred types when only one representation can be restored.
+#[cfg(all(unix, not(target_os = "macos")))]
+const RESTORE_PREFERENCE: [&str; 4] = [
+    "text/plain;charset=utf-8",
+    "UTF8_STRING",
+    "text/plain",
+    "image/png",
+];
+/// Names for plain text. The tools offer all of them when writing text.
+#[cfg(all(unix, not(target_os = "macos")))]
+const TEXT_TYPES: [&str; 5] = [
+    "text/plain;charset=utf-8",
+    "UTF8_STRING",
+    "text/plain",
+    "STRING",
+    "TEXT",
+];
+
+/// One clipboard entry with each of its representations. macOS allows
+/// several entries at once (e.g. copied files); Linux has exactly one.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClipboardItem {
+    /// `(type, bytes)` pairs in the owner's order; types are UTIs on macOS
+    /// and MIME types or X11 targets on Linux.
+    pub representations: Vec<(String, Vec<u8>)>,
+}
+
+/// Full clipboard contents at one point in time; empty when nothing was
+/// copied.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClipboardSnapshot {
+    pub items: Vec<ClipboardItem>,
+}
+
+impl ClipboardSnapshot {
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+}
+
+pub trait Clipboard {
+    /// Plain text on the clipboard; `None` when there is no text
+    /// representation.
+    fn read_text(&self) -> Result<Option<String>, String>;
+    fn write_text(&self, text: &str) -> Result<(), String>;
+    fn snapshot(&self) -> Result<ClipboardSnapshot, String>;
+    /// Replaces the clipboard with `snapshot`; an empty snapshot clears it.
+    fn restore(&self, snapshot: &ClipboardSnapshot) -> Result<(), String>;
+
+    /// Counter bumped on every copy, when the platform keeps one. Lets a
+    /// watcher skip reading a clipboard that did not change.
+    fn change_count(&self) -> Option<i64> {
+        None
+    }
+
+    /// The owner asked not to keep the contents, like password managers do.
+    fn is_concealed(&self) -> bool {
+        false
+    }
+}
+
+/// Clipboard of the running session.
+#[cfg(target_os = "macos")]
+pub fn system() -> Box<dyn Clipboard> {
+    Box::new(Pasteboard)
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+pub fn system() -> Box<dyn Clipboard> {
+    Box::new(ToolClipboard::detect())
+}
+
+#[cfg(not(unix))]
+pub fn system() -> Box<dyn Clipboard> {
+    Box::new(Unsupported)
+}
+
+/// The general pasteboard, read and written through AppKit.
+#[cfg(target_os = "macos")]
+struct Pasteboard;
+
+#[cfg(target_os = "macos")]
+impl Clipboard for Pasteboard {
+    fn read_text(&self) -> Result<Option<String>, String> {
+        let pasteboard = NSPasteboard::generalPasteboard();
+        let text = pasteboard.stringForType(unsafe { NSPasteboardTypeString });
+        Ok(text.map(|text| text.to_string()))
+    }
+
+    fn write_text(&self, text: &str) -> Result<(), String> {
+        let pasteboard = NSPasteboard::generalPasteboard();
+        pasteboard.clearContents();
+        if pasteboard
+            .setString_forType(&NSString::from_str(text), unsafe { NSPasteboardTypeString })
+        {
+            Ok(())
+        } else {
+            Err("failed to write clipboard".to_string())
+        }
+    }
+
+    fn snapshot(&self) -> Result<ClipboardSnapshot, String> {
+        let pasteboard = NSPasteboard::generalPasteboard();
+        let Some(items) = pasteboard.pasteboardItems() else {
+            return Ok(ClipboardSnapshot::default());
+        };
+
+        let items = items
+            .iter()
+            .map(|item| ClipboardItem {
+                // Promised data that the owner fails to provide is skipped.
+                representations: item
+                    .types()
+                    .iter()
+                    .filter_map(|kind| {
+                        let data = item.dataForType(&kind)?;
+                        Some((kind.to_string(), data.to_vec()))
+                    })
+                    .collect(),
+            })
+            .filter(|item| !item.representations.is_empty())
+            .collect();
+        Ok(ClipboardSnapshot { items })
+    }
+
+    fn restore(&self, snapshot: &ClipboardSnapshot) -> Result<(), String> {
+        let pasteboard = NSPasteboard::generalPasteboard();
+        pasteboard.clearContents();
+        if snapshot.is_empty() {
+            return Ok(());
+        }
+
+        let objects: Vec<Retained<ProtocolObject<dyn NSPasteboardWriting>>> = snapshot
+            .items
+            .iter()
+            .map(|item| {
+                let pasteboard_item = NSPasteboardItem::new();
+                for (kind, bytes) in &item.representations {
+                    pasteboard_item
+                        .setData_forType(&NSData::with_bytes(bytes), &NSString::from_str(kind));
+                }
+                ProtocolObject::from_retained(pasteboard_item)
+            })
+            .collect();
+        if pasteboard.writeObjects(&NSArray::from_retained_slice(&objects)) {
+            Ok(())
+        } else {
+            Err("failed to restore clipboard".to_string())
+        }
+    }
+
+    fn change_count(&self) -> Option<i64> {
+        Some(NSPasteboard::generalPasteboard().changeCount() as i64)
+    }
+
+    fn is_concealed(&self) -> bool {
+        let Some(types) = NSPasteboard::generalPasteboard().types() else {
+            return false;
+        };
+        types
+            .iter()
+            .any(|kind| CONCEALED_TYPES.contains(&kind.to_string().as_str()))
+    }
+}
+
+#[cfg(not(unix))]
+struct Unsupported;
+
+#[cfg(not(unix))]
+impl Clipboard for Unsupported {
+    fn read_text(&self) -> Result<Option<String>, String> {
+        Err(UNSUPPORTED.to_string())
+    }
+
+    fn write_text(&self, _text: &str) -> Result<(), String> {
+        Err(UNSUPPORTED.to_string())
+    }
+
+    fn snapshot(&self) -> Result<ClipboardSnapshot, String> {
+        Err(UNSUPPORTED.to_string())
+    }
+
+    fn restore(&self, _snapshot: &ClipboardSnapshot) -> Result<(), String> {
+        Err(UNSUPPORTED.to_string())
+    }
+}
+
+#[cfg(not(unix))]
+const UNSUPPORTED: &str = "Clipboard access is available only on macOS and Linux";
+
+/// Clipboard kept in memory, for tests of code that borrows the clipboard.
+#[cfg(test)]
+#[derive(Debug, Default)]
+pub struct MemoryClipboard {
+    contents: Mutex<ClipboardSnapshot>,
+    changes: AtomicI64,
+}
+
+#[cfg(test)]
+impl MemoryClipboard {
+    pub const TEXT: &'static str = "text/plain";
+
+    pub fn new(contents: ClipboardSnapshot) -> Self {
+        Self {
+            contents: Mutex::new(contents),
+            changes: AtomicI64::new(0),
+        }
+    }
+
+    pub fn contents(&self) -> ClipboardSnapshot {
+        self.contents
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+}
+
+#[cfg(test)]
+impl Clipboard for MemoryClipboard {
+    fn read_text(&self) -> Result<Option<String>, String> {
+        Ok(self
+            .contents()
+            .items
+            .iter()
+            .flat_map(|item| &item.representations)
+            .find(|(kind, _)| kind == Self::TEXT)
+            .map(|(_, bytes)| String::from_utf8_lossy(bytes).into_owned()))
+    }
+
+    fn write_text(&self, text: &str) -> Result<(), String> {
+        self.restore(&ClipboardSnapshot {
+            items: vec![ClipboardItem {
+                representations: vec![(Self::TEXT.to_string(), text.as_bytes().to_vec())],
+            }],
+        })
+    }
+
+    fn snapshot(&self) -> Result<ClipboardSnapshot, String> {
+        Ok(self.contents())
+    }
+
+    fn restore(&self, snapshot: &ClipboardSnapshot) -> Result<(), String> {
+        *self.contents.lock().unwrap_or_else(PoisonError::into_inner) = snapshot.clone();
+        self.changes.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    }
+
+    fn change_count(&self) -> Option<i64> {
+        Some(self.changes.load(Ordering::SeqCst))
+    }
+}
+
+/// Contents of the primary selection (whatever is highlighted).
+#[cfg(all(unix, not(target_os = "macos")))]
+pub fn read_primary_selection() -> Result<String, String> {
+    PasteCommand::detect(Buffer::Primary, env_is_set)?.read()
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+pub(crate) fn env_is_set(key: &str) -> bool {
+    std::env::var_os(key).is_some_and(|value| !value.is_empty())
+}
+
+/// X11/Wayland selection buffers.
+#[cfg(all(unix, not(target_os = "macos")))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Buffer {
+    /// Whatever is highlighted, no copy needed.
+    Primary,
+    Clipboard,
+}
+
+/// Command-line tool that prints a selection buffer.
+#[cfg(all(unix, not(target_os = "macos")))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct PasteTool {
+    program: &'static str,
+    args: &'static [&'static str],
+    /// Distribution package to suggest when the program is missing.
+    package: &'static str,
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+impl PasteTool {
+    const fn new(
+        program: &'static str,
+        args: &'static [&'static str],
+        package: &'static str,
+    ) -> Self {
+        Self {
+            program,
+            args,
+            package,
+        }
+    }
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+const WAYLAND_TOOLS: [(Buffer, PasteTool); 2] = [
+    (
+        Buffer::Primary,
+        PasteTool::new("wl-paste", &["--primary", "--no-newline"], "wl-clipboard"),
+    ),
+    (
+        Buffer::Clipboard,
+        PasteTool::new("wl-paste", &["--no-newline"], "wl-clipboard"),
+    ),
+];
+
+#[cfg(all(unix, not(target_os = "macos")))]
+const X11_TOOLS: [(Buffer, PasteTool); 4] = [
+    (
+        Buffer::Primary,
+        PasteTool::new("xclip", &["-o", "-selection", "primary"], "xclip"),
+    ),
+    (
+        Buffer::Primary,
+        PasteTool::new("xsel", &["--primary", "--output"], "xsel"),
+    ),
+    (
+        Buffer::Clipboard,
+        PasteTool::new("xclip", &["-o", "-selection", "clipboard"], "xclip"),
+    ),
+    (
+        Buffer::Clipboard,
+        PasteTool::new("xsel", &["--clipboard", "--output"], "xsel"),
+    ),
+];
+
+/// What a tool run produced.
+#[cfg(all(unix, not(target_os = "macos")))]
+pub(crate) struct ToolOutput {
+    pub(crate) success: bool,
+    pub(crate) stdout: Vec<u8>,
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn run_tool(tool: &PasteTool) -> io::Result<ToolOutput> {
+    let output = Command::new(tool.program)
+        .args(tool.args)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()?;
+    Ok(ToolOutput {
+        success: output.status.success(),
+        stdout: output.stdout,
+    })
+}
+
+/// Reads a buffer through the first installed tool for the session type.
+#[cfg(all(unix, not(target_os = "macos")))]
+struct PasteCommand {
+    tools: Vec<PasteTool>,
+    run: fn(&PasteTool) -> io::Result<ToolOutput>,
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+impl PasteCommand {
+    /// Tools for the running session; `is_set` tells whether an environment
+    /// variable is set and non-empty.
+    fn detect(buffer: Buffer, is_set: impl Fn(&str) -> bool) -> Result<Self, String> {
+        let mut tools = Vec::new();
+        if is_set("WAYLAND_DISPLAY") {
+            tools.extend(
+                WAYLAND_TOOLS
+                    .iter()
+                    .filter(|(b, _)| *b == buffer)
+                    .map(|(_, t)| *t),
+            );
+        }
+        // Also under Wayland: XWayland mirrors both buffers.
+        if is_set("DISPLAY") {
+            tools.extend(
+                X11_TOOLS
+                    .iter()
+                    .filter(|(b, _)| *b == buffer)
+                    .map(|(_, t)| *t),
+            );
+        }
+        if tools.is_empty() {
+            return Err("No X11 or Wayland display to read the selection from".to_string());
+        }
+        Ok(Self {
+            tools,
+            run: run_tool,
+        })
+    }
+
+    fn read(&self) -> Result<String, String> {
+        for tool in &self.tools {
+            match (self.run)(tool) {
+                Ok(output) if output.success => {
+                    return Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+                }
+                // wl-paste and xclip exit non-zero when the buffer is empty.
+                Ok(_) => return Ok(String::new()),
+                Err(error) if error.kind() == io::ErrorKind::NotFound => continue,
+                Err(error) => return Err(format!("failed to run {}: {error}", tool.program)),
+            }
+        }
+
+        let mut packages: Vec<&str> = self.tools.iter().map(|tool| tool.package).collect();
+        packages.dedup();
+        Err(format!(
+            "Install {} to capture selected text",
+            packages.join(" or ")
+        ))
+    }
+}
+
+/// Clipboard tools that can list and transfer individual types.
+#[cfg(all(unix, not(target_os = "macos")))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TypedTool {
+    WlClipboard,
+    Xclip,
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+impl TypedTool {
+    fn package(self) -> &'static str {
+        match self {
+            Self::WlClipboard => "wl-clipboard",
+            Self::Xclip => "xclip",
+        }
+    }
+
+    fn list(self) -> ToolCall {
+        match self {
+            Self::WlClipboard => ToolCall::new("wl-paste", &["--list-types"]),
+            Self::Xclip => {
+                ToolCall::new("xclip", &["-o", "-selection", "clipboard", "-t", "TARGETS"])
+            }
+        }
+    }
+
+    fn read(self, kind: &str) -> ToolCall {
+        match self {
+            Self::WlClipboard => ToolCall::new("wl-paste", &["--no-newline", "--type", kind]),
+            Self::Xclip => ToolCall::new("xclip", &["-o", "-selection", "clipboard", "-t", kind]),
+        }
+    }
+
+    /// Offers `kind` (the tool's text default when `None`) read from stdin.
+    fn write(self, kind: Option<&str>) -> ToolCall {
+        match (self, kind) {
+            (Self::WlClipboard, Some(kind)) => ToolCall::new("wl-copy", &["--type", kind]),
+            (Self::WlClipboard, None) => ToolCall::new("wl-copy", &[]),
+            (Self::Xclip, Some(kind)) => {
+                ToolCall::new("xclip", &["-i", "-selection", "clipboard", "-t", kind])
+            }
+            (Self::Xclip, None) => ToolCall::new("xclip", &["-i", "-selection", "clipboard"]),
+        }
+    }
+
+    fn clear(self) -> ToolCall {
+        match self {
+            Self::WlClipboard => ToolCall::new("wl-copy", &["--clear"]),
+            // xclip cannot drop ownership; offer empty text instead.
+            Self::Xclip => self.write(None),
+        }
+    }
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ToolCall {
+    pub(crate) program: &'static str,
+    pub(crate) args: Vec<String>,
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+impl ToolCall {
+    pub(crate) fn new(program: &'static str, args: &[&str]) -> Self {
+        Self {
+            program,
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+        }
+    }
+}
+
+/// Runs `call`, feeding it `input` when given. Writers fork to keep serving
+/// the clipboard, so their output is discarded rather than waited on.
+#[cfg(all(unix, not(target_os = "macos")))]
+pub(crate) fn run_call(call: &ToolCall, input: Option<&[u8]>) -> io::Result<ToolOutput> {
+    let mut command = Command::new(call.program);
+    command.args(&call.args).stderr(Stdio::null());
+    let Some(input) = input else {
+        let output = command.stdin(Stdio::null()).output()?;
+        return Ok(ToolOutput {
+            success: output.status.success(),
+            stdout: output.stdout,
+        });
+    };
+
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(input)?;
+    }
+    Ok(ToolOutput {
+        success: child.wait()?.success(),
+        stdout: Vec::new(),
+    })
+}
+
+/// Snapshots every offered type through wl-clipboard or xclip. Both tools
+/// serve a single type per process, so restoring keeps one representation:
+/// text (under all its names) when there is any, otherwise an image or the
+/// owner's first type. The other types are reported as an error.
+#[cfg(all(unix, not(target_os = "macos")))]
+struct ToolClipboard {
+    tools: Vec<TypedTool>,
+    run: fn(&ToolCall, Option<&[u8]>) -> io::Result<ToolOutput>,
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+impl ToolClipboard {
+    fn detect() -> Self {
+        Self::for_session(env_is_set)
+    }
+
+    fn for_session(is_set: impl Fn(&str) -> bool) -> Self {
+        let mut tools = Vec::new();
+        if is_set("WAYLAND_DISPLAY") {
+            tools.push(TypedTool::WlClipboard);
+        }
+        if is_set("DISPLAY") {
+            tools.push(TypedTool::Xclip);
+        }
+        Self {
+            tools,
+            run: run_call,
+        }
+    }
+
+    /// Runs the call built by `call` with the first installed tool.
+    fn run_first(
+        &self,
+        call: impl Fn(TypedTool) -> ToolCall,
+        input: Option<&[u8]>,
+    ) -> Result<(TypedTool, ToolOutput), String> {
+        if self.tools.is_empty() {
+            return Err("No X11 or Wayland display to reach the clipboard".to_string());
+        }
+        for tool in &self.tools {
+            let call = call(*tool);
+            match (self.run)(&call, input) {
+                Ok(output) => return Ok((*tool, output)),
+                Err(error) if error.kind() == io::ErrorKind::NotFound => continue,
+                Err(error) => return Err(format!("failed to run {}: {error}", call.program)),
+            }
+        }
+
+        let mut packages: Vec<&str> = self.tools.iter().map(|tool| tool.package()).collect();
+        packages.dedup();
+        Err(format!(
+            "Install {} to save and restore the clipboard",
+            packages.join(" or ")
+        ))
+    }
+
+    fn write(&self, call: impl Fn(TypedTool) -> ToolCall, input: &[u8]) -> Result<(), String> {
+        let (tool, output) = self.run_first(call, Some(input))?;
+        if output.success {
+            Ok(())
+        } else {
+            Err(format!("{} could not write the clipboard", tool.package()))
+        }
+    }
+}
+
+/// Copies counted by [`watch_changes`].
+#[cfg(all(unix, not(target_os = "macos")))]
+static CHANGES: AtomicI64 = AtomicI64::new(0);
+/// A watcher is running, so [`CHANGES`] is current.
+#[cfg(all(unix, not(target_os = "macos")))]
+static COUNTING_CHANGES: AtomicBool = AtomicBool::new(false);
+
+/// Start counting copies without polling: `wl-paste --watch` on Wayland
+/// (compositors with the data-control protocol), XFixes selection events on
+/// X11. Without either, [`COUNTING_CHANGES`] stays false.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn watch_changes(is_set: impl Fn(&str) -> bool) {
+    if is_set("WAYLAND_DISPLAY") && watch_wayland_changes() {
+        return;
+    }
+    if is_set("DISPLAY") {
+        COUNTING_CHANGES.store(true, Ordering::SeqCst);
+        thread::spawn(|| {
+            if let Err(error) = watch_x11_changes() {
+                eprintln!("clipboard notifications unavailable: {error}");
+            }
+            COUNTING_CHANGES.store(false, Ordering::SeqCst);
+        });
+    }
+}
+
+/// `wl-paste --watch` runs its command once per copy; each `echo` is one
+/// line. Returns `false` when wl-paste is not installed.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn watch_wayland_changes() -> bool {
+    let child = Command::new("wl-paste")
+        .args(["--watch", "echo"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn();
+    let Ok(mut child) = child else {
+        return false;
+    };
+    let Some(stdout) = child.stdout.take() else {
+        return false;
+    };
+    COUNTING_CHANGES.store(true, Ordering::SeqCst);
+    thread::spawn(move || {
+        for _ in BufReader::new(stdout).lines().map_while(Result::ok) {
+            CHANGES.fetch_add(1, Ordering::SeqCst);
+        }
+        // The compositor lacks data-control, or wl-paste was killed.
+        COUNTING_CHANGES.store(false, Ordering::SeqCst);
+        let _ = child.wait();
+    });
+    true
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn watch_x11_changes() -> Result<(), String> {
+    let (connection, screen) = x11rb::connect(None).map_err(|error| error.to_string())?;
+    let root = connection.setup().roots[screen].root;
+    let clipboard = connection
+        .intern_atom(false, b"CLIPBOARD")
+        .map_err(|error| error.to_string())?
+        .reply()
+        .map_err(|error| error.to_string())?
+        .atom;
+    connection
+        .xfixes_query_version(5, 0)
+        .map_err(|error| error.to_string())?
+        .reply()
+        .map_err(|error| error.to_string())?;
+    connection
+        .xfixes_select_selection_input(
+            root,
+            clipboard,
+            SelectionEventMask::SET_SELECTION_OWNER
+                | SelectionEventMask::SELECTION_WINDOW_DESTROY
+                | SelectionEventMask::SELECTION_CLIENT_CLOSE,
+        )
+        .map_err(|error| error.to_string())?;
+    connection.flush().map_err(|error| error.to_string())?;
+    loop {
+        match connection.wait_for_event() {
+            Ok(Event::XfixesSelectionNotify(_)) => {
+                CHANGES.fetch_add(1, Ordering::SeqCst);
+            }
+            Ok(_) => {}
+            Err(error) => return Err(error.to_string()),
+        }
+    }
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+impl Clipboard for ToolClipboard {
+    fn read_text(&self) -> Result<Option<String>, String> {
+        let text = PasteCommand::detect(Buffer::Clipboard, env_is_set)?.read()?;
+        Ok((!text.is_empty()).then_some(text))
+    }
+
+    fn write_text(&self, text: &str) -> Result<(), String> {
+        self.write(|tool| tool.write(None), text.as_bytes())
+    }
+
+    fn change_count(&self) -> Option<i64> {
+        static STARTED: Once = Once::new();
+        STARTED.call_once(|| watch_changes(env_is_set));
+        COUNTING_CHANGES
+            .load(Ordering::SeqCst)
+            .then(|| CHANGES.load(Ordering::SeqCst))
+    }
+
+    fn is_concealed(&self) -> bool {
+        match self.run_first(TypedTool::list, None) {
+            Ok((_, listing)) => listing.success && offers_hint(&listing.stdout),
+            Err(_) => false,
+        }
+    }
+
+    fn snapshot(&self) -> Result<ClipboardSnapshot, String> {
+        let (tool, listing) = self.run_first(TypedTool::list, None)?;
+        // Both tools exit non-zero when nothing owns the clipboard.
+        if !listing.success {
+            return Ok(ClipboardSnapshot::default());
+        }
+
+        let mut representations = Vec::new();
+        for kind in snapshot_types(&String::from_utf8_lossy(&listing.stdout)) {
+            let output = (self.run)(&tool.read(&kind), None)
+                .map_err(|error| format!("failed to run {}: {error}", tool.package()))?;
+            if output.success {
+                representations.push((kind, output.stdout));
+            }
+        }
+        if representations.is_empty() {
+            return Ok(ClipboardSnapshot::default());
+        }
+        Ok(ClipboardSnapshot {
+            items: vec![ClipboardItem { representations }],
+        })
+    }
+
+    fn restore(&self, snapshot: &ClipboardSnapshot) -> Result<(), String> {
+        let Some((kind, bytes)) = restore_choice(snapshot) else {
+            return self.write(TypedTool::clear, &[]);
+        };
+        if TEXT_TYPES.contains(&kind) {
+            self.write(|tool| tool.write(None), bytes)?;
+        } else {
+            self.write(|tool| tool.write(Some(kind)), bytes)?;
+        }
+
+        let lost = lost_types(snapshot, kind);
+        if lost.is_empty() {
+            Ok(())
+        } else {
+            Err(format!(
+                "Clipboard restored as {kind} only, without {}",
+                lost.join(", ")
+            ))
+        }
+    }
+}
+
+/// Data types from a `--list-types`/`TARGETS` listing. X11 bookkeeping
+/// targets (`TIMESTAMP`, `MULTIPLE`, ...) are not data and are skipped.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn snapshot_types(listing: &str) -> Vec<String> {
+    let mut types: Vec<String> = Vec::new();
+    for kind in listing.lines().map(str::trim) {
+        let is_data = kind.contains('/') || kind == "UTF8_STRING";
+        if is_data && !types.iter().any(|seen| seen == kind) {
+            types.push(kind.to_string());
+        }
+    }
+    types.truncate(MAX_SNAPSHOT_TYPES);
+    types
+}
+
+/// A type listing includes [`PASSWORD_MANAGER_HINT`].
+#[cfg(all(unix, not(target_os = "macos")))]
+fn offers_hint(listing: &[u8]) -> bool {
+    String::from_utf8_lossy(listing)
+        .lines()
+        .any(|kind| kind.trim() == PASSWORD_MANAGER_HINT)
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn restore_choice(snapshot: &ClipboardSnapshot) -> Option<(&str, &[u8])> {
+    let representations: Vec<&(String, Vec<u8>)> = snapshot
+        .items
+        .iter()
+        .flat_map(|item| &item.representations)
+        .collect();
+    RESTORE_PREFERENCE
+        .iter()
+        .find_map(|preferred| representations.iter().find(|(kind, _)| kind == preferred))
+        .or_else(|| representations.first())
+        .map(|(kind, bytes)| (kind.as_str(), bytes.as_slice()))
+}
+
+/// Types of `snapshot` that restoring only `restored` leaves out.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn lost_types<'a>(snapshot: &'a ClipboardSnapshot, restored: &str) -> Vec<&'a str> {
+    let text = TEXT_TYPES.contains(&restored);
+    snapshot
+        .items
+        .iter()
+        .flat_map(|item| &item.representations)
+        .map(|(kind, _)| kind.as_str())
+        .filter(|kind| *kind != restored && !(text && TEXT_TYPES.contains(kind)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn memory_clipboard_round_trips_every_representation() {
+        let snapshot = ClipboardSnapshot {
+            items: vec![ClipboardItem {
+                representations: vec![
+                    ("public.png".into(), vec![0x89, b'P', b'N', b'G']),
+                    (MemoryClipboard::TEXT.into(), b"caption".to_vec()),
+                ],
+            }],
+        };
+        let clipboard = MemoryClipboard::new(snapshot.clone());
+
+        assert_eq!(clipboard.read_text().unwrap().as_deref(), Some("caption"));
+        clipboard.write_text("copied").unwrap();
+        assert_eq!(clipboard.snapshot().unwrap().items.len(), 1);
+        clipboard.restore(&snapshot).unwrap();
+        assert_eq!(clipboard.contents(), snapshot);
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    mod paste {
+        use super::super::*;
+
+        fn output(success: bool, stdout: &str) -> io::Result<ToolOutput> {
+            Ok(ToolOutput {
+                success,
+                stdout: stdout.as_bytes().to_vec(),
+            })
+        }
+
+        fn programs(buffer: Buffer, vars: &'static [&'static str]) -> Vec<String> {
+            PasteCommand::detect(buffer, |key| vars.contains(&key))
+                .map(|command| {
+                    command
+                        .tools
+                        .iter()
+                        .map(|tool| format!("{} {}", tool.program, tool.args.join(" ")))
+                        .collect()
+                })
+                .unwrap_or_default()
+        }
+
+        #[test]
+        fn picks_tools_for_the_session() {
+            assert_eq!(
+                programs(Buffer::Primary, &["WAYLAND_DISPLAY"]),
+                ["wl-paste --primary --no-newline"]
+            );
+            assert_eq!(
+                programs(Buffer::Clipboard, &["DISPLAY"]),
+                ["xclip -o -selection clipboard", "xsel --clipboard --output"]
+            );
+            assert_eq!(
+                programs(Buffer::Primary, &["WAYLAND_DISPLAY", "DISPLAY"]).len(),
+                3
+            );
+            assert!(PasteCommand::detect(Buffer::Primary, |_| false).is_err());
+        }
+
+        #[test]
+        fn skips_missing_tools() {
+            let mut command = PasteCommand::detect(Buffer::Primary, |_| true).unwrap();
+            command.run = |tool| match tool.program {
+                "wl-paste" => Err(io::ErrorKind::NotFound.into()),
+                _ => output(true, "from xclip"),
+            };
+            assert_eq!(command.read().unwrap(), "from xclip");
+
+            command.run = |_| Err(io::ErrorKind::NotFound.into());
+            assert_eq!(
+                command.read(),
+                Err("Install wl-clipboard or xclip or xsel to capture selected text".to_string())
+            );
+        }
+
+        #[test]
+        fn failed_tool_means_empty_buffer() {
+            let mut command =
+                PasteCommand::detect(Buffer::Primary, |key| key == "DISPLAY").unwrap();
+            command.run = |tool| match tool.program {
+                "xclip" => output(false, ""),
+                _ => output(true, "unreachable"),
+            };
+            assert_eq!(command.read().unwrap(), "");
+        }
+
+        fn xclip(call: &ToolCall, input: Option<&[u8]>) -> io::Result<ToolOutput> {
+            let kind = call.args.last().map(String::as_str).unwrap_or_default();
+            match (call.args[0].as_str(), kind) {
+                ("-o", "TARGETS") => output(
+                    true,
+                    "TIMESTAMP\nTARGETS\ntext/html\nimage/png\nUTF8_STRING\n",
+                ),
+                ("-o", "image/png") => output(false, ""),
+                ("-o", kind) => output(true, &format!("<{kind}>")),
+                // Text is written in xclip's own text mode.
+                ("-i", "clipboard") => {
+                    assert_eq!(input, Some(&b"<UTF8_STRING>"[..]));
+                    output(true, "")
+                }
+                _ => output(false, ""),
+            }
+        }
+
+        #[test]
+        fn snapshots_every_readable_type_and_restores_text() {
+            let mut clipboard = ToolClipboard::for_session(|key| key == "DISPLAY");
+            clipboard.run = xclip;
+
+            let snapshot = clipboard.snapshot().unwrap();
+            let kinds: Vec<&str> = snapshot.items[0]
+                .representations
+                .iter()
+                .map(|(kind, _)| kind.as_str())
+                .collect();
+            assert_eq!(kinds, ["text/html", "UTF8_STRING"]);
+            assert_eq!(
+                clipboard.restore(&snapshot),
+                Err("Clipboard restored as UTF8_STRING only, without text/html".to_string())
+            );
+
+            let mut text = snapshot.clone();
+            text.items[0].representations.remove(0);
+            text.items[0]
+                .representations
+                .push(("text/plain".into(), b"<UTF8_STRING>".to_vec()));
+            assert_eq!(clipboard.restore(&text), Ok(()));
+        }
+
+        #[test]
+        fn password_manager_copies_are_concealed() {
+            let mut clipboard = ToolClipboard::for_session(|key| key == "WAYLAND_DISPLAY");
+            clipboard.run = |_, _| {
+                output(
+                    true,
+                    "text/plain;charset=utf-8\nx-kde-passwordManagerHint\n",
+                )
+            };
+            assert!(clipboard.is_concealed());
+
+            clipboard.run = |_, _| output(true, "text/plain;charset=utf-8\nUTF8_STRING\n");
+            assert!(!clipboard.is_concealed());
+            clipboard.run = |_, _| output(false, "");
+            assert!(!clipboard.is_concealed());
+        }
+
+        #[test]
+        fn restore_prefers_text_then_images() {
+            let snapshot = |kinds: &[&str]| ClipboardSnapshot {
+                items: vec![ClipboardItem {
+                    representations: kinds
+                        .iter()
+                        .map(|kind| (kind.to_string(), Vec::new()))
+                        .collect(),
+                }],
+            };
+
+            let kind =
+                |kinds: &[&str]| restore_choice(&snapshot(kinds)).map(|(kind, _)| kind.to_string());
+            assert_eq!(
+                kind(&["text/html", "text/plain"]).as_deref(),
+                Some("text/plain")
+            );
+            assert_eq!(
+                kind(&["text/html", "image/png"]).as_deref(),
+                Some("image/png")
+            );
+            assert_eq!(kind(&["text/uri-list"]).as_deref(), Some("text/uri-list"));
+            assert_eq!(kind(&[]), None);
+        }
+
+        #[test]
+        fn missing_tools_and_empty_clipboards() {
+            let mut clipboard = ToolClipboard::for_session(|_| true);
+            clipboard.run = |call, _| match call.program {
+                "wl-paste" => Err(io::ErrorKind::NotFound.into()),
+                _ => output(false, ""),
+            };
+            assert_eq!(clipboard.snapshot(), Ok(ClipboardSnapshot::default()));
+
+            clipboard.run = |_, _| Err(io::ErrorKind::NotFound.into());
+            assert_eq!(
+                clipboard.snapshot(),
+                Err("Install wl-clipboard or xclip to save and restore the clipboard".to_string())
+            );
+            assert!(ToolClipboard::for_session(|_| false).snapshot().is_err());
+        }
+    }
+}
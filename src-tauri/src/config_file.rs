@@ -0,0 +1,217 @@
+//! Crash-safe writes for files under the config dir. Contents go to a temp
+//! file in the same directory which is flushed and renamed over the target,
+//! so readers see either the old or the new file, never a partial one.
+//! Files are created owner-only (0600) on Unix since they may hold secrets.
+
+use fs4::fs_std::FileExt;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Exclusive advisory lock on `<path>.lock`, shared by every MacClaw
+/// process. Released on drop.
+pub struct ConfigLock {
+    file: File,
+}
+
+impl Drop for ConfigLock {
+    fn drop(&mut self) {
+        let _ = FileExt::unlock(&self.file);
+    }
+}
+
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+fn create_parent(path: &Path) -> Result<(), String> {
+    match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => {
+            std::fs::create_dir_all(dir).map_err(|e| format!("Create dir error: {e}"))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn private_options() -> OpenOptions {
+    let mut options = OpenOptions::new();
+    options.write(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options
+}
+
+/// Block until no other process or thread holds the lock for `path`.
+pub fn lock(path: &Path) -> Result<ConfigLock, String> {
+    create_parent(path)?;
+    let file = private_options()
+        .create(true)
+        .truncate(false)
+        .open(sibling(path, ".lock"))
+        .map_err(|e| format!("Lock file error: {e}"))?;
+    FileExt::lock_exclusive(&file).map_err(|e| format!("Lock error: {e}"))?;
+    Ok(ConfigLock { file })
+}
+
+/// Create `path` as an empty owner-only file unless it exists, for files
+/// that are written by other libraries (e.g. SQLite databases).
+pub fn create_private(path: &Path) -> Result<(), String> {
+    create_parent(path)?;
+    match private_options().create_new(true).open(path) {
+        Ok(_) => Ok(()),
+        Err(error) if error.kind() == std::io::ErrorKind::AlreadyExists => Ok(()),
+        Err(error) => Err(format!("Create file error: {error}")),
+    }
+}
+
+/// Create `path` with `contents` unless it exists; `false` when it did.
+/// Callers hold [`lock`] so nobody reads the file half-written.
+pub fn create_private_with(path: &Path, contents: &[u8]) -> Result<bool, String> {
+    create_parent(path)?;
+    let result = match private_options().create_new(true).open(path) {
+        Ok(mut file) => file.write_all(contents).and_then(|()| file.sync_all()),
+        Err(error) if error.kind() == std::io::ErrorKind::AlreadyExists => return Ok(false),
+        Err(error) => return Err(format!("Create file error: {error}")),
+    };
+    result.map(|()| true).map_err(|error| {
+        let _ = std::fs::remove_file(path);
+        format!("Write error: {error}")
+    })
+}
+
+/// Atomically replace `path` with `contents`. Callers that read-modify-write
+/// should hold [`lock`] around the whole sequence.
+pub fn write_private(path: &Path, contents: &[u8]) -> Result<(), String> {
+    write_private_with(path, |file| file.write_all(contents))
+}
+
+/// Like [`write_private`], with the body produced by `fill`. If `fill` or any
+/// later step fails the temp file is removed and `path` is left untouched.
+pub fn write_private_with(
+    path: &Path,
+    fill: impl FnOnce(&mut File) -> std::io::Result<()>,
+) -> Result<(), String> {
+    create_parent(path)?;
+    let temp = sibling(
+        path,
+        &format!(
+            ".tmp-{}-{}",
+            std::process::id(),
+            TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ),
+    );
+
+    let result = (|| {
+        let mut file = private_options().create_new(true).open(&temp)?;
+        fill(&mut file)?;
+        file.sync_all()?;
+        drop(file);
+        std::fs::rename(&temp, path)?;
+        sync_parent(path);
+        Ok(())
+    })();
+
+    result.map_err(|error: std::io::Error| {
+        let _ = std::fs::remove_file(&temp);
+        format!("Write error: {error}")
+    })
+}
+
+/// Persist the rename itself; best effort, not supported everywhere.
+fn sync_parent(path: &Path) {
+    #[cfg(unix)]
+    if let Some(dir) = path.parent() {
+        if let Ok(dir) = File::open(dir) {
+            let _ = dir.sync_all();
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io;
+
+    #[test]
+    fn replaces_contents() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("config.json");
+
+        write_private(&path, b"first").unwrap();
+        write_private(&path, b"second").unwrap();
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "second");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn files_are_owner_only() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.json");
+
+        write_private(&path, b"{}").unwrap();
+        let _lock = lock(&path).unwrap();
+
+        for file in [path.clone(), sibling(&path, ".lock")] {
+            let mode = std::fs::metadata(file).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+
+    #[test]
+    fn failed_write_keeps_previous_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.json");
+        write_private(&path, b"{\"ok\":true}").unwrap();
+
+        let result = write_private_with(&path, |file| {
+            file.write_all(b"{\"trunc")?;
+            Err(io::Error::other("disk full"))
+        });
+
+        assert!(result.is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "{\"ok\":true}");
+        let leftovers: Vec<_> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_name().to_string_lossy().contains(".tmp-"))
+            .collect();
+        assert!(leftovers.is_empty());
+    }
+
+    #[test]
+    fn lock_serializes_writers() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("counter");
+        write_private(&path, b"0").unwrap();
+
+        let threads: Vec<_> = (0..8)
+            .map(|_| {
+                let path = path.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..10 {
+                        let _lock = lock(&path).unwrap();
+                        let value: u32 = std::fs::read_to_string(&path).unwrap().parse().unwrap();
+                        write_private(&path, (value + 1).to_string().as_bytes()).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "80");
+    }
+}
@@ -0,0 +1,181 @@
+//! Versioned layout of `credentials.json`. Older files are upgraded one
+//! version at a time on load; every step works on raw JSON so it never
+//! depends on the current Rust structs.
+
+use serde_json::{json, Map, Value};
+
+use crate::shortcuts::DEFAULT_SELECTION_SHORTCUT;
+
+/// Version written by this build.
+pub const CONFIG_VERSION: u64 = 3;
+
+/// `MIGRATIONS[n]` upgrades version `n + 1` to `n + 2`.
+const MIGRATIONS: [fn(Value) -> Result<Value, String>; 2] = [v1_to_v2, v2_to_v3];
+
+/// Version of a parsed file. Files written before the `version` field
+/// existed are recognised by shape.
+pub fn detect_version(value: &Value) -> u64 {
+    match value.get("version").and_then(Value::as_u64) {
+        Some(version) => version,
+        None if value.get("profiles").is_some() => 2,
+        None => 1,
+    }
+}
+
+/// Upgrade `value` to [`CONFIG_VERSION`]. Returns the upgraded JSON and the
+/// version it started from.
+pub fn upgrade(mut value: Value) -> Result<(Value, u64), String> {
+    if !value.is_object() {
+        return Err("config is not a JSON object".into());
+    }
+
+    let from = detect_version(&value);
+    if from == 0 || from > CONFIG_VERSION {
+        return Err(format!(
+            "unsupported config version {from} (this build reads up to {CONFIG_VERSION})"
+        ));
+    }
+
+    for (index, step) in MIGRATIONS.iter().enumerate().skip(from as usize - 1) {
+        value =
+            step(value).map_err(|error| format!("migration to v{} failed: {error}", index + 2))?;
+    }
+    if let Some(object) = value.as_object_mut() {
+        object.insert("version".into(), json!(CONFIG_VERSION));
+    }
+    Ok((value, from))
+}
+
+fn text(object: &Map<String, Value>, key: &str, fallback: &str) -> Value {
+    match object.get(key) {
+        Some(Value::String(value)) => json!(value),
+        _ => json!(fallback),
+    }
+}
+
+/// v1: one flat gateway (`gatewayUrl`, `token`, ...). v2: named profiles
+/// with an active pointer. Missing or mistyped fields fall back to
+/// defaults instead of failing the whole file.
+fn v1_to_v2(value: Value) -> Result<Value, String> {
+    let object = value.as_object().ok_or("v1 config is not an object")?;
+    let profile = json!({
+        "id": uuid::Uuid::new_v4().to_string(),
+        "name": "Default",
+        "gatewayUrl": text(object, "gatewayUrl", "ws://127.0.0.1:19819"),
+        "token": text(object, "token", ""),
+        "password": text(object, "password", ""),
+        "agentId": text(object, "agentId", ""),
+        "sessionKey": text(object, "sessionKey", "main"),
+    });
+    Ok(json!({
+        "version": 2,
+        "activeProfile": "Default",
+        "profiles": [profile],
+        "shortcuts": object.get("shortcuts").cloned().unwrap_or(Value::Null),
+    }))
+}
+
+/// v2: `shortcuts` is a list of toggle accelerators and the selection
+/// shortcut is fixed. v3: `shortcuts` is a list of `{accelerator, action}`
+/// bindings; the old fixed selection shortcut becomes an explicit binding.
+fn v2_to_v3(mut value: Value) -> Result<Value, String> {
+    let object = value.as_object_mut().ok_or("v2 config is not an object")?;
+    let Some(Value::Array(accelerators)) = object.get("shortcuts") else {
+        object.insert("shortcuts".into(), Value::Null);
+        return Ok(value);
+    };
+
+    let accelerators: Vec<&str> = accelerators.iter().filter_map(Value::as_str).collect();
+    let mut bindings: Vec<Value> = accelerators
+        .iter()
+        .map(|accelerator| json!({ "accelerator": accelerator, "action": { "type": "toggle_panel" } }))
+        .collect();
+    if !accelerators.contains(&DEFAULT_SELECTION_SHORTCUT) {
+        bindings.push(json!({
+            "accelerator": DEFAULT_SELECTION_SHORTCUT,
+            "action": { "type": "capture_selection" },
+        }));
+    }
+    object.insert("shortcuts".into(), Value::Array(bindings));
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_version_has_a_migration() {
+        assert_eq!(MIGRATIONS.len() as u64, CONFIG_VERSION - 1);
+    }
+
+    #[test]
+    fn detects_unversioned_layouts() {
+        assert_eq!(detect_version(&json!({ "gatewayUrl": "ws://x" })), 1);
+        assert_eq!(detect_version(&json!({ "profiles": [] })), 2);
+        assert_eq!(detect_version(&json!({ "version": 7 })), 7);
+    }
+
+    #[test]
+    fn upgrades_flat_config_with_missing_fields() {
+        let (value, from) = upgrade(json!({
+            "gatewayUrl": "wss://gw.example/ws",
+            "agentId": 42,
+            "shortcuts": ["Alt+A", "Alt+B", "Alt+C"],
+        }))
+        .unwrap();
+
+        assert_eq!(from, 1);
+        assert_eq!(value["version"], CONFIG_VERSION);
+        assert_eq!(value["activeProfile"], "Default");
+        let profile = &value["profiles"][0];
+        assert_eq!(profile["gatewayUrl"], "wss://gw.example/ws");
+        assert_eq!(profile["agentId"], "");
+        assert_eq!(profile["sessionKey"], "main");
+        assert_eq!(value["shortcuts"][1]["accelerator"], "Alt+B");
+        assert_eq!(value["shortcuts"][1]["action"]["type"], "toggle_panel");
+    }
+
+    #[test]
+    fn v2_shortcuts_become_bindings() {
+        let (value, from) = upgrade(json!({
+            "version": 2,
+            "activeProfile": "Default",
+            "profiles": [],
+            "shortcuts": ["Alt+A", "Alt+B", "Alt+C"],
+        }))
+        .unwrap();
+
+        assert_eq!(from, 2);
+        let bindings = value["shortcuts"].as_array().unwrap();
+        assert_eq!(bindings.len(), 4);
+        assert_eq!(bindings[0]["accelerator"], "Alt+A");
+        assert_eq!(bindings[3]["accelerator"], DEFAULT_SELECTION_SHORTCUT);
+        assert_eq!(bindings[3]["action"]["type"], "capture_selection");
+    }
+
+    #[test]
+    fn v2_without_shortcuts_keeps_defaults() {
+        let (value, _) = upgrade(json!({ "version": 2, "profiles": [] })).unwrap();
+        assert_eq!(value["shortcuts"], Value::Null);
+    }
+
+    #[test]
+    fn current_version_is_unchanged() {
+        let original = json!({
+            "version": CONFIG_VERSION,
+            "activeProfile": "Local",
+            "profiles": [{ "id": "1", "name": "Local", "gatewayUrl": "ws://x" }],
+        });
+        let (value, from) = upgrade(original.clone()).unwrap();
+        assert_eq!(from, CONFIG_VERSION);
+        assert_eq!(value, original);
+    }
+
+    #[test]
+    fn rejects_newer_and_non_object_configs() {
+        assert!(upgrade(json!({ "version": CONFIG_VERSION + 1 })).is_err());
+        assert!(upgrade(json!({ "version": 0 })).is_err());
+        assert!(upgrade(json!(["not", "an", "object"])).is_err());
+    }
+}
@@ -1,10 +1,30 @@
 use serde::{Deserialize, Serialize};
-use std::sync::{Mutex, OnceLock};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard, OnceLock, PoisonError};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config_file;
+use crate::config_schema::{self, CONFIG_VERSION};
+use crate::history::HistorySettings;
+use crate::notch::NotchSettings;
+use crate::placement::{PanelLayout, PanelSettings, PanelSize};
+use crate::redaction::RedactionSettings;
+use crate::secret_store::{open_default_store, SecretStore};
+use crate::selection::{self, SelectionSettings};
+use crate::shortcuts::ShortcutBinding;
 
 const CREDS_FILE: &str = "credentials.json";
+const TOKEN_KEY: &str = "token";
+const PASSWORD_KEY: &str = "password";
+const DEFAULT_PROFILE: &str = "Default";
 
-static CREDS_CACHE: OnceLock<Mutex<Credentials>> = OnceLock::new();
+static CREDS_CACHE: OnceLock<Mutex<StoredCredentials>> = OnceLock::new();
+static SECRET_STORE: OnceLock<Box<dyn SecretStore>> = OnceLock::new();
+static LOAD_ISSUES: Mutex<Vec<ConfigIssue>> = Mutex::new(Vec::new());
 
+/// Settings of the active profile plus app-wide options. This is what the
+/// frontend loads/saves and what the gateway client connects with.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Credentials {
@@ -14,7 +34,17 @@ pub struct Credentials {
     pub agent_id: String,
     pub session_key: String,
     #[serde(default)]
-    pub shortcuts: Option<Vec<String>>,
+    pub shortcuts: Option<Vec<ShortcutBinding>>,
+    #[serde(default)]
+    pub history: HistorySettings,
+    #[serde(default)]
+    pub selection: SelectionSettings,
+    #[serde(default)]
+    pub redaction: RedactionSettings,
+    #[serde(default)]
+    pub panel: PanelSettings,
+    #[serde(default)]
+    pub notch: NotchSettings,
 }
 
 impl Default for Credentials {
@@ -26,63 +56,1206 @@ impl Default for Credentials {
             agent_id: String::new(),
             session_key: "main".into(),
             shortcuts: None,
+            history: HistorySettings::default(),
+            selection: SelectionSettings::default(),
+            redaction: RedactionSettings::default(),
+            panel: PanelSettings::default(),
+            notch: NotchSettings::default(),
+        }
+    }
+}
+
+/// One named gateway connection. Secrets live in the secret store under the
+/// profile `id`, so renaming a profile never touches the keyring.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GatewayProfile {
+    pub id: String,
+    pub name: String,
+    pub gateway_url: String,
+    #[serde(default)]
+    pub token: String,
+    #[serde(default)]
+    pub password: String,
+    #[serde(default)]
+    pub agent_id: String,
+    #[serde(default)]
+    pub session_key: String,
+    /// The secrets stay in `credentials.json` because the secret store
+    /// would not take them.
+    #[serde(skip)]
+    plaintext: bool,
+    /// Secret fields the store failed to read. They look empty here, so
+    /// saving leaves the stored values alone until a new one is typed.
+    #[serde(skip)]
+    unread: Vec<&'static str>,
+}
+
+impl GatewayProfile {
+    fn new(name: &str, settings: &Credentials) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            gateway_url: settings.gateway_url.clone(),
+            token: settings.token.clone(),
+            password: settings.password.clone(),
+            agent_id: settings.agent_id.clone(),
+            session_key: settings.session_key.clone(),
+            plaintext: false,
+            unread: Vec::new(),
+        }
+    }
+
+    fn apply(&mut self, settings: &Credentials) {
+        self.gateway_url = settings.gateway_url.clone();
+        self.token = settings.token.clone();
+        self.password = settings.password.clone();
+        self.agent_id = settings.agent_id.clone();
+        self.session_key = settings.session_key.clone();
+        let (token, password) = (&self.token, &self.password);
+        self.unread.retain(|&field| {
+            let typed = if field == TOKEN_KEY { token } else { password };
+            typed.is_empty()
+        });
+    }
+
+    fn secret_key(&self, field: &str) -> String {
+        format!("{}.{field}", self.id)
+    }
+
+    fn has_secrets(&self) -> bool {
+        !self.token.is_empty() || !self.password.is_empty()
+    }
+
+    fn secret_mut(&mut self, field: &str) -> &mut String {
+        if field == TOKEN_KEY {
+            &mut self.token
+        } else {
+            &mut self.password
+        }
+    }
+}
+
+/// Profile entry returned to the UI (no secrets).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileSummary {
+    pub name: String,
+    pub gateway_url: String,
+    pub agent_id: String,
+    pub active: bool,
+}
+
+/// Contents of `credentials.json`: named profiles plus app-wide options.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StoredCredentials {
+    #[serde(default)]
+    pub version: u64,
+    pub active_profile: String,
+    pub profiles: Vec<GatewayProfile>,
+    #[serde(default)]
+    pub shortcuts: Option<Vec<ShortcutBinding>>,
+    #[serde(default)]
+    pub history: HistorySettings,
+    #[serde(default)]
+    pub selection: SelectionSettings,
+    #[serde(default)]
+    pub redaction: RedactionSettings,
+    #[serde(default)]
+    pub panel: PanelSettings,
+    #[serde(default)]
+    pub notch: NotchSettings,
+    /// Where the panel was dragged on each display. Not part of
+    /// [`Credentials`]: the settings form never edits it.
+    #[serde(default)]
+    pub layout: PanelLayout,
+}
+
+impl Default for StoredCredentials {
+    fn default() -> Self {
+        let creds = Credentials::default();
+        Self {
+            version: CONFIG_VERSION,
+            active_profile: DEFAULT_PROFILE.into(),
+            profiles: vec![GatewayProfile::new(DEFAULT_PROFILE, &creds)],
+            shortcuts: creds.shortcuts,
+            history: creds.history,
+            selection: creds.selection,
+            redaction: creds.redaction,
+            panel: creds.panel,
+            notch: creds.notch,
+            layout: PanelLayout::default(),
+        }
+    }
+}
+
+impl StoredCredentials {
+    fn find(&self, name: &str) -> Option<usize> {
+        let name = name.trim();
+        self.profiles
+            .iter()
+            .position(|profile| profile.name.eq_ignore_ascii_case(name))
+    }
+
+    fn require(&self, name: &str) -> Result<usize, String> {
+        self.find(name)
+            .ok_or_else(|| format!("Unknown profile: {}", name.trim()))
+    }
+
+    fn active_index(&self) -> usize {
+        self.find(&self.active_profile).unwrap_or(0)
+    }
+
+    pub fn active(&self) -> &GatewayProfile {
+        &self.profiles[self.active_index()]
+    }
+
+    pub fn credentials(&self) -> Credentials {
+        let profile = self.active();
+        Credentials {
+            gateway_url: profile.gateway_url.clone(),
+            token: profile.token.clone(),
+            password: profile.password.clone(),
+            agent_id: profile.agent_id.clone(),
+            session_key: profile.session_key.clone(),
+            shortcuts: self.shortcuts.clone(),
+            history: self.history.clone(),
+            selection: self.selection.clone(),
+            redaction: self.redaction.clone(),
+            panel: self.panel.clone(),
+            notch: self.notch.clone(),
+        }
+    }
+
+    pub fn summaries(&self) -> Vec<ProfileSummary> {
+        let active = self.active_index();
+        self.profiles
+            .iter()
+            .enumerate()
+            .map(|(index, profile)| ProfileSummary {
+                name: profile.name.clone(),
+                gateway_url: profile.gateway_url.clone(),
+                agent_id: profile.agent_id.clone(),
+                active: index == active,
+            })
+            .collect()
+    }
+
+    /// Save `creds` into the active profile.
+    pub fn update_active(&mut self, creds: &Credentials) {
+        let index = self.active_index();
+        self.profiles[index].apply(creds);
+        self.shortcuts = creds.shortcuts.clone();
+        self.history = creds.history.clone();
+        self.selection = creds.selection.clone();
+        self.redaction = creds.redaction.clone();
+        self.panel = creds.panel.clone();
+        self.notch = creds.notch.clone();
+    }
+
+    fn validate_name(&self, name: &str, renaming: Option<usize>) -> Result<String, String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("Profile name cannot be empty".into());
+        }
+        match self.find(name) {
+            Some(index) if Some(index) != renaming => {
+                Err(format!("Profile already exists: {name}"))
+            }
+            _ => Ok(name.to_string()),
+        }
+    }
+
+    pub fn create(
+        &mut self,
+        name: &str,
+        settings: &Credentials,
+    ) -> Result<&GatewayProfile, String> {
+        let name = self.validate_name(name, None)?;
+        self.profiles.push(GatewayProfile::new(&name, settings));
+        Ok(&self.profiles[self.profiles.len() - 1])
+    }
+
+    pub fn rename(&mut self, from: &str, to: &str) -> Result<(), String> {
+        let index = self.require(from)?;
+        let to = self.validate_name(to, Some(index))?;
+        if index == self.active_index() {
+            self.active_profile = to.clone();
+        }
+        self.profiles[index].name = to;
+        Ok(())
+    }
+
+    /// Remove a profile. The last profile cannot be deleted; deleting the
+    /// active one activates the first remaining profile.
+    pub fn delete(&mut self, name: &str) -> Result<GatewayProfile, String> {
+        let index = self.require(name)?;
+        if self.profiles.len() == 1 {
+            return Err("Cannot delete the only profile".into());
+        }
+        let was_active = index == self.active_index();
+        let removed = self.profiles.remove(index);
+        if was_active {
+            self.active_profile = self.profiles[0].name.clone();
+        }
+        Ok(removed)
+    }
+
+    pub fn activate(&mut self, name: &str) -> Result<(), String> {
+        let index = self.require(name)?;
+        self.active_profile = self.profiles[index].name.clone();
+        Ok(())
+    }
+
+    /// Copy safe to write to `credentials.json`: tokens and passwords live
+    /// in the secret store, unless it refused them.
+    fn without_secrets(&self) -> Self {
+        let mut stored = self.clone();
+        for profile in &mut stored.profiles {
+            if !profile.plaintext {
+                profile.token.clear();
+                profile.password.clear();
+            }
+        }
+        stored
+    }
+
+    fn has_plaintext_secrets(&self) -> bool {
+        self.profiles.iter().any(GatewayProfile::has_secrets)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfigIssueKind {
+    /// The file could not be parsed; it was moved aside and defaults used.
+    Unreadable,
+    /// The file was upgraded from an older schema version.
+    Migrated,
+    /// A secret could not be read from or moved into the secret store.
+    Secrets,
+}
+
+/// Problem found while loading `credentials.json`, reported to the UI.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigIssue {
+    pub kind: ConfigIssueKind,
+    pub message: String,
+    pub backup_path: Option<String>,
+}
+
+impl ConfigIssue {
+    fn new(kind: ConfigIssueKind, message: String) -> Self {
+        Self {
+            kind,
+            message,
+            backup_path: None,
         }
     }
 }
 
-fn creds_path() -> std::path::PathBuf {
+pub(crate) fn config_dir() -> PathBuf {
     dirs::config_dir()
-        .unwrap_or_else(|| std::path::PathBuf::from("."))
+        .unwrap_or_else(|| PathBuf::from("."))
         .join("ai.macclaw.panel")
-        .join(CREDS_FILE)
 }
 
-fn read_creds_file() -> Credentials {
-    let path = creds_path();
-    match std::fs::read_to_string(&path) {
-        Ok(json) => serde_json::from_str::<Credentials>(&json).unwrap_or_default(),
-        Err(_) => Credentials::default(),
-    }
+fn creds_path() -> PathBuf {
+    config_dir().join(CREDS_FILE)
 }
 
-#[tauri::command]
-pub fn load_credentials() -> Result<Credentials, String> {
-    let cache = CREDS_CACHE.get_or_init(|| Mutex::new(read_creds_file()));
-    let guard = cache.lock().map_err(|e| format!("Lock error: {e}"))?;
-    Ok(guard.clone())
+fn secret_store() -> &'static dyn SecretStore {
+    SECRET_STORE
+        .get_or_init(|| open_default_store(&config_dir()))
+        .as_ref()
 }
 
-#[tauri::command]
-pub fn save_credentials(creds: Credentials) -> Result<(), String> {
-    let json = serde_json::to_string_pretty(&creds)
+fn write_creds_file(path: &Path, stored: &StoredCredentials) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(&stored.without_secrets())
         .map_err(|error| format!("Serialize error: {error}"))?;
-    let path = creds_path();
-    if let Some(dir) = path.parent() {
-        if let Err(e) = std::fs::create_dir_all(dir) {
-            eprintln!("failed to create config dir: {e}");
+    config_file::write_private(path, json.as_bytes())
+}
+
+fn store_profile_secrets(store: &dyn SecretStore, profile: &GatewayProfile) -> Result<(), String> {
+    for (field, value) in [
+        (TOKEN_KEY, &profile.token),
+        (PASSWORD_KEY, &profile.password),
+    ] {
+        let key = profile.secret_key(field);
+        if value.is_empty() && profile.unread.contains(&field) {
+            continue;
+        }
+        if value.is_empty() {
+            store.delete(&key)?;
+        } else {
+            store.set(&key, value)?;
+        }
+    }
+    Ok(())
+}
+
+fn delete_profile_secrets(store: &dyn SecretStore, profile: &GatewayProfile) {
+    for field in [TOKEN_KEY, PASSWORD_KEY] {
+        if let Err(e) = store.delete(&profile.secret_key(field)) {
+            eprintln!("failed to remove {field} from {}: {e}", store.name());
+        }
+    }
+}
+
+/// Parse `credentials.json`, upgrading older schema versions. Returns the
+/// version the file was written with.
+fn parse_creds_file(json: &str) -> Result<(StoredCredentials, u64), String> {
+    let value = serde_json::from_str::<Value>(json).map_err(|e| format!("invalid JSON: {e}"))?;
+    let (value, from) = config_schema::upgrade(value)?;
+    let stored = serde_json::from_value::<StoredCredentials>(value)
+        .map_err(|e| format!("invalid v{CONFIG_VERSION} config: {e}"))?;
+    if stored.profiles.is_empty() {
+        return Err("config has no profiles".into());
+    }
+    Ok((stored, from))
+}
+
+/// Move an unreadable file aside so a later save can't overwrite it.
+fn backup_unreadable(path: &Path) -> Result<PathBuf, String> {
+    let stamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default();
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(format!(".corrupt-{stamp}"));
+    let backup = PathBuf::from(backup);
+    std::fs::rename(path, &backup).map_err(|e| format!("backup failed: {e}"))?;
+    Ok(backup)
+}
+
+/// Move secrets left by older versions into per-profile store entries:
+/// plaintext values from the file, and unscoped `token`/`password` store
+/// entries (which belong to the active profile). The file is only
+/// rewritten once everything is stored, so a failing store never loses
+/// credentials; profiles whose secrets could not be moved keep them in the
+/// file on later writes too. Returns `true` when the file still has to hold
+/// plaintext.
+fn migrate_secrets(
+    path: &Path,
+    store: &dyn SecretStore,
+    stored: &mut StoredCredentials,
+    issues: &mut Vec<ConfigIssue>,
+) -> bool {
+    let in_file: Vec<bool> = stored
+        .profiles
+        .iter()
+        .map(GatewayProfile::has_secrets)
+        .collect();
+    let index = stored.active_index();
+    let mut unscoped = Vec::new();
+    for field in [TOKEN_KEY, PASSWORD_KEY] {
+        if let Ok(Some(value)) = store.get(field) {
+            let slot = stored.profiles[index].secret_mut(field);
+            if slot.is_empty() {
+                *slot = value;
+            }
+            unscoped.push(field);
+        }
+    }
+
+    if !stored.has_plaintext_secrets() {
+        return false;
+    }
+
+    let migrated = stored
+        .profiles
+        .iter()
+        .try_for_each(|profile| store_profile_secrets(store, profile));
+    match migrated.and_then(|()| write_creds_file(path, stored)) {
+        Ok(()) => {
+            for field in unscoped {
+                let _ = store.delete(field);
+            }
+            eprintln!("migrated plaintext credentials to {}", store.name());
+            false
+        }
+        Err(e) => {
+            for (profile, in_file) in stored.profiles.iter_mut().zip(in_file) {
+                profile.plaintext = in_file;
+            }
+            issues.push(ConfigIssue::new(
+                ConfigIssueKind::Secrets,
+                format!(
+                    "Secrets left in plaintext, {} unavailable: {e}",
+                    store.name()
+                ),
+            ));
+            true
+        }
+    }
+}
+
+/// Parse `credentials.json` without touching the secret store; an
+/// unreadable file is moved aside and reported. Returns the version the
+/// file was written with when it was older than the current one.
+fn read_settings_json(
+    path: &Path,
+    issues: &mut Vec<ConfigIssue>,
+) -> (StoredCredentials, Option<u64>) {
+    match std::fs::read_to_string(path) {
+        Ok(json) => match parse_creds_file(&json) {
+            Ok((stored, from)) => (stored, Some(from).filter(|&from| from < CONFIG_VERSION)),
+            Err(error) => {
+                let mut issue = ConfigIssue::new(
+                    ConfigIssueKind::Unreadable,
+                    format!("Settings file could not be read ({error}); defaults loaded"),
+                );
+                match backup_unreadable(path) {
+                    Ok(backup) => issue.backup_path = Some(backup.display().to_string()),
+                    Err(backup_error) => issue.message.push_str(&format!(" ({backup_error})")),
+                }
+                issues.push(issue);
+                (StoredCredentials::default(), None)
+            }
+        },
+        Err(_) => (StoredCredentials::default(), None),
+    }
+}
+
+/// Fill in the secrets of `profile` from the store.
+fn load_profile_secrets(
+    store: &dyn SecretStore,
+    profile: &mut GatewayProfile,
+    issues: &mut Vec<ConfigIssue>,
+) {
+    for field in [TOKEN_KEY, PASSWORD_KEY] {
+        match store.get(&profile.secret_key(field)) {
+            Ok(Some(value)) => *profile.secret_mut(field) = value,
+            Ok(None) => {}
+            Err(e) => {
+                profile.unread.push(field);
+                issues.push(ConfigIssue::new(
+                    ConfigIssueKind::Secrets,
+                    format!(
+                        "Could not read {field} of profile \"{}\" from {}: {e}",
+                        profile.name,
+                        store.name()
+                    ),
+                ));
+            }
+        }
+    }
+}
+
+/// Load `credentials.json` and its secrets. Never fails: problems are
+/// returned alongside whatever could be recovered.
+fn read_creds_file(path: &Path, store: &dyn SecretStore) -> (StoredCredentials, Vec<ConfigIssue>) {
+    let mut issues = Vec::new();
+    let (mut stored, upgraded_from) = read_settings_json(path, &mut issues);
+    let keep_plaintext = migrate_secrets(path, store, &mut stored, &mut issues);
+
+    if let Some(from) = upgraded_from {
+        // The old file stays readable, so it is kept while it holds secrets.
+        let result = if keep_plaintext {
+            Err("secrets could not be moved out of the file".to_string())
+        } else {
+            write_creds_file(path, &stored)
+        };
+        issues.push(ConfigIssue::new(
+            ConfigIssueKind::Migrated,
+            match result {
+                Ok(()) => format!("Settings upgraded from v{from} to v{CONFIG_VERSION}"),
+                Err(e) => format!("Settings upgraded from v{from} in memory only: {e}"),
+            },
+        ));
+    }
+
+    for profile in &mut stored.profiles {
+        load_profile_secrets(store, profile, &mut issues);
+    }
+    (stored, issues)
+}
+
+fn clear_creds(path: &Path, store: &dyn SecretStore, stored: &StoredCredentials) {
+    if path.exists() {
+        if let Err(e) = std::fs::remove_file(path) {
+            eprintln!("failed to remove credentials file: {e}");
+        }
+    }
+    for profile in &stored.profiles {
+        delete_profile_secrets(store, profile);
+    }
+}
+
+fn cache() -> Result<MutexGuard<'static, StoredCredentials>, String> {
+    CREDS_CACHE
+        .get_or_init(|| {
+            let path = creds_path();
+            let _lock = config_file::lock(&path)
+                .map_err(|e| eprintln!("config: loading without lock: {e}"))
+                .ok();
+            let (stored, issues) = read_creds_file(&path, secret_store());
+            add_load_issues(issues);
+            Mutex::new(stored)
+        })
+        .lock()
+        .map_err(|e| format!("Lock error: {e}"))
+}
+
+/// Read-modify-write of `credentials.json` under the config lock. The file
+/// is re-read first so changes made by another MacClaw instance since our
+/// last load are kept rather than overwritten. Secrets come from `cached`,
+/// so only profiles added elsewhere hit the store. Problems found on the
+/// way join the load report; a file that became unreadable is not replaced
+/// by defaults plus this one change.
+fn update_creds_file<T>(
+    path: &Path,
+    store: &dyn SecretStore,
+    cached: &StoredCredentials,
+    change: impl FnOnce(&mut StoredCredentials, &dyn SecretStore) -> Result<T, String>,
+) -> Result<(T, StoredCredentials), String> {
+    let _lock = config_file::lock(path)?;
+    let mut issues = Vec::new();
+    let (mut stored, _) = read_settings_json(path, &mut issues);
+    if let Some(issue) = issues.first() {
+        let message = format!("Settings not saved: {}", issue.message);
+        add_load_issues(issues);
+        return Err(message);
+    }
+    for profile in &mut stored.profiles {
+        if profile.has_secrets() {
+            // The file holds them itself (older version, or no store).
+            profile.plaintext = true;
+        } else if let Some(known) = cached.profiles.iter().find(|known| known.id == profile.id) {
+            profile.token = known.token.clone();
+            profile.password = known.password.clone();
+            profile.plaintext = known.plaintext;
+            profile.unread = known.unread.clone();
+        } else {
+            load_profile_secrets(store, profile, &mut issues);
         }
     }
-    std::fs::write(&path, json).map_err(|error| format!("Write error: {error}"))?;
+    add_load_issues(issues);
+    let result = change(&mut stored, store)?;
+    write_creds_file(path, &stored)?;
+    Ok((result, stored))
+}
 
-    let cache = CREDS_CACHE.get_or_init(|| Mutex::new(Credentials::default()));
-    if let Ok(mut guard) = cache.lock() {
-        *guard = creds;
+/// Log `issues` and add those not reported yet to what
+/// [`config_load_report`] returns.
+fn add_load_issues(issues: Vec<ConfigIssue>) {
+    let mut reported = LOAD_ISSUES.lock().unwrap_or_else(PoisonError::into_inner);
+    for issue in issues {
+        if !reported.contains(&issue) {
+            eprintln!("config: {}", issue.message);
+            reported.push(issue);
+        }
     }
+}
+
+/// Apply `change` to the settings file and only then replace the cache.
+fn update_stored<T>(
+    change: impl FnOnce(&mut StoredCredentials, &dyn SecretStore) -> Result<T, String>,
+) -> Result<T, String> {
+    let mut guard = cache()?;
+    let (result, stored) = update_creds_file(&creds_path(), secret_store(), &guard, change)?;
+    *guard = stored;
+    Ok(result)
+}
+
+#[tauri::command]
+pub fn load_credentials() -> Result<Credentials, String> {
+    Ok(cache()?.credentials())
+}
+
+/// Problems found while loading the settings file, at startup or when it
+/// was re-read to save a change.
+#[tauri::command]
+pub fn config_load_report() -> Result<Vec<ConfigIssue>, String> {
+    drop(cache()?);
+    let issues = LOAD_ISSUES.lock().map_err(|e| format!("Lock error: {e}"))?;
+    Ok(issues.clone())
+}
+
+#[tauri::command]
+pub fn save_credentials(creds: Credentials) -> Result<(), String> {
+    update_stored(|stored, store| {
+        stored.update_active(&creds);
+        store_profile_secrets(store, stored.active())
+    })?;
+    selection::watch_clipboard(&creds.selection);
     Ok(())
 }
 
+/// Persist shortcut bindings that were successfully registered.
+pub fn save_shortcuts(shortcuts: Vec<ShortcutBinding>) -> Result<(), String> {
+    update_stored(|stored, _| {
+        stored.shortcuts = Some(shortcuts);
+        Ok(())
+    })
+}
+
+/// Spots the panel was dragged to in earlier launches.
+pub fn load_panel_layout() -> Result<PanelLayout, String> {
+    Ok(cache()?.layout.clone())
+}
+
+pub fn save_panel_layout(layout: PanelLayout) -> Result<(), String> {
+    update_stored(|stored, _| {
+        stored.layout = layout;
+        Ok(())
+    })
+}
+
+/// Drop the saved spots and the preferred expanded size.
+pub fn reset_panel_layout() -> Result<PanelSettings, String> {
+    update_stored(|stored, _| {
+        stored.layout = PanelLayout::default();
+        stored.panel.expanded = PanelSize::default();
+        Ok(stored.panel.clone())
+    })
+}
+
 #[tauri::command]
 pub fn clear_credentials() -> Result<(), String> {
+    let mut guard = cache()?;
     let path = creds_path();
-    if path.exists() {
-        if let Err(e) = std::fs::remove_file(&path) {
-            eprintln!("failed to remove credentials file: {e}");
+    let _lock = config_file::lock(&path)?;
+    clear_creds(&path, secret_store(), &guard);
+    *guard = StoredCredentials::default();
+    Ok(())
+}
+
+#[tauri::command]
+pub fn list_profiles() -> Result<Vec<ProfileSummary>, String> {
+    Ok(cache()?.summaries())
+}
+
+/// Create a profile from `settings`, or from the defaults when omitted.
+#[tauri::command]
+pub fn create_profile(
+    name: String,
+    settings: Option<Credentials>,
+) -> Result<Vec<ProfileSummary>, String> {
+    update_stored(|stored, store| {
+        let profile = stored.create(&name, &settings.unwrap_or_default())?;
+        store_profile_secrets(store, profile)?;
+        Ok(stored.summaries())
+    })
+}
+
+#[tauri::command]
+pub fn rename_profile(from: String, to: String) -> Result<Vec<ProfileSummary>, String> {
+    update_stored(|stored, _| {
+        stored.rename(&from, &to)?;
+        Ok(stored.summaries())
+    })
+}
+
+#[tauri::command]
+pub fn delete_profile(name: String) -> Result<Vec<ProfileSummary>, String> {
+    let removed = update_stored(|stored, _| stored.delete(&name))?;
+    delete_profile_secrets(secret_store(), &removed);
+    list_profiles()
+}
+
+/// Make `name` the active profile and return its settings so the caller
+/// can reconnect.
+#[tauri::command]
+pub fn activate_profile(name: String) -> Result<Credentials, String> {
+    update_stored(|stored, _| {
+        stored.activate(&name)?;
+        Ok(stored.credentials())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::secret_store::{MemoryStore, UnavailableStore};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct BrokenStore;
+
+    impl SecretStore for BrokenStore {
+        fn name(&self) -> &'static str {
+            "broken"
+        }
+        fn get(&self, _key: &str) -> Result<Option<String>, String> {
+            Err("unavailable".into())
+        }
+        fn set(&self, _key: &str, _value: &str) -> Result<(), String> {
+            Err("unavailable".into())
+        }
+        fn delete(&self, _key: &str) -> Result<(), String> {
+            Err("unavailable".into())
+        }
+    }
+
+    /// Holds secrets but cannot read them back, like a locked keyring.
+    #[derive(Default)]
+    struct LockedStore(MemoryStore);
+
+    impl SecretStore for LockedStore {
+        fn name(&self) -> &'static str {
+            "locked"
+        }
+        fn get(&self, _key: &str) -> Result<Option<String>, String> {
+            Err("locked".into())
+        }
+        fn set(&self, key: &str, value: &str) -> Result<(), String> {
+            self.0.set(key, value)
+        }
+        fn delete(&self, key: &str) -> Result<(), String> {
+            self.0.delete(key)
         }
     }
 
-    let cache = CREDS_CACHE.get_or_init(|| Mutex::new(Credentials::default()));
-    if let Ok(mut guard) = cache.lock() {
-        *guard = Credentials::default();
+    /// Counts reads, which hit the keyring (and may prompt) in the app.
+    #[derive(Default)]
+    struct CountingStore {
+        inner: MemoryStore,
+        gets: AtomicUsize,
+    }
+
+    impl SecretStore for CountingStore {
+        fn name(&self) -> &'static str {
+            "counting"
+        }
+        fn get(&self, key: &str) -> Result<Option<String>, String> {
+            self.gets.fetch_add(1, Ordering::Relaxed);
+            self.inner.get(key)
+        }
+        fn set(&self, key: &str, value: &str) -> Result<(), String> {
+            self.inner.set(key, value)
+        }
+        fn delete(&self, key: &str) -> Result<(), String> {
+            self.inner.delete(key)
+        }
+    }
+
+    fn sample() -> Credentials {
+        Credentials {
+            token: "tok-123".into(),
+            password: "hunter2".into(),
+            agent_id: "ops".into(),
+            ..Credentials::default()
+        }
+    }
+
+    /// Same steps as `save_credentials`, against a temp file.
+    fn save(
+        path: &Path,
+        store: &dyn SecretStore,
+        stored: &mut StoredCredentials,
+        creds: &Credentials,
+    ) {
+        stored.update_active(creds);
+        store_profile_secrets(store, stored.active()).unwrap();
+        write_creds_file(path, stored).unwrap();
+    }
+
+    fn names(stored: &StoredCredentials) -> Vec<&str> {
+        stored.profiles.iter().map(|p| p.name.as_str()).collect()
+    }
+
+    #[test]
+    fn save_keeps_secrets_out_of_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CREDS_FILE);
+        let store = MemoryStore::default();
+        let mut stored = StoredCredentials::default();
+
+        save(&path, &store, &mut stored, &sample());
+
+        let raw = std::fs::read_to_string(&path).unwrap();
+        assert!(!raw.contains("tok-123"));
+        assert!(!raw.contains("hunter2"));
+
+        let loaded = read_creds_file(&path, &store).0.credentials();
+        assert_eq!(loaded.token, "tok-123");
+        assert_eq!(loaded.password, "hunter2");
+        assert_eq!(loaded.agent_id, "ops");
+    }
+
+    #[test]
+    fn legacy_plaintext_file_is_migrated() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CREDS_FILE);
+        std::fs::write(&path, serde_json::to_string(&sample()).unwrap()).unwrap();
+        let store = MemoryStore::default();
+
+        let stored = read_creds_file(&path, &store).0;
+
+        assert_eq!(names(&stored), ["Default"]);
+        assert_eq!(stored.credentials().token, "tok-123");
+        let key = stored.active().secret_key(PASSWORD_KEY);
+        assert_eq!(store.get(&key).unwrap(), Some("hunter2".into()));
+        let raw = std::fs::read_to_string(&path).unwrap();
+        assert!(!raw.contains("tok-123"));
+        assert!(raw.contains("\"profiles\""));
+        assert!(raw.contains("ops"));
+    }
+
+    #[test]
+    fn unscoped_store_secrets_move_to_active_profile() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CREDS_FILE);
+        let flat = Credentials {
+            token: String::new(),
+            password: String::new(),
+            ..sample()
+        };
+        std::fs::write(&path, serde_json::to_string(&flat).unwrap()).unwrap();
+        let store = MemoryStore::default();
+        store.set(TOKEN_KEY, "from-keyring").unwrap();
+
+        let stored = read_creds_file(&path, &store).0;
+
+        assert_eq!(stored.credentials().token, "from-keyring");
+        assert_eq!(store.get(TOKEN_KEY).unwrap(), None);
+        assert_eq!(
+            read_creds_file(&path, &store).0.credentials().token,
+            "from-keyring"
+        );
+    }
+
+    #[test]
+    fn failed_migration_keeps_plaintext() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CREDS_FILE);
+        std::fs::write(&path, serde_json::to_string(&sample()).unwrap()).unwrap();
+
+        let stored = read_creds_file(&path, &BrokenStore).0;
+
+        assert_eq!(stored.credentials().token, "tok-123");
+        assert!(std::fs::read_to_string(&path).unwrap().contains("tok-123"));
+    }
+
+    #[test]
+    fn secrets_stay_in_the_file_without_a_secret_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CREDS_FILE);
+        std::fs::write(&path, serde_json::to_string(&sample()).unwrap()).unwrap();
+        let store = UnavailableStore::new("no machine id");
+
+        let (stored, issues) = read_creds_file(&path, &store);
+        assert_eq!(stored.credentials().token, "tok-123");
+        assert!(issues
+            .iter()
+            .any(|issue| issue.kind == ConfigIssueKind::Secrets));
+
+        update_creds_file(&path, &store, &stored, |stored, _| {
+            stored.history.enabled = false;
+            Ok(())
+        })
+        .unwrap();
+        assert!(std::fs::read_to_string(&path).unwrap().contains("tok-123"));
+
+        let saved = update_creds_file(&path, &store, &stored, |stored, store| {
+            let profile = stored.create("Staging", &sample())?;
+            store_profile_secrets(store, profile)
+        });
+        assert!(saved.is_err());
+        assert_eq!(read_creds_file(&path, &store).0.profiles.len(), 1);
+    }
+
+    #[test]
+    fn empty_secret_removes_stored_value() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CREDS_FILE);
+        let store = MemoryStore::default();
+        let mut stored = StoredCredentials::default();
+        save(&path, &store, &mut stored, &sample());
+
+        let without_password = Credentials {
+            password: String::new(),
+            ..sample()
+        };
+        save(&path, &store, &mut stored, &without_password);
+
+        let key = stored.active().secret_key(PASSWORD_KEY);
+        assert_eq!(store.get(&key).unwrap(), None);
+    }
+
+    #[test]
+    fn secrets_that_failed_to_load_survive_a_save() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CREDS_FILE);
+        let store = LockedStore::default();
+        let mut stored = StoredCredentials::default();
+        save(&path, &store, &mut stored, &sample());
+        let token_key = stored.active().secret_key(TOKEN_KEY);
+        let password_key = stored.active().secret_key(PASSWORD_KEY);
+
+        let (mut loaded, issues) = read_creds_file(&path, &store);
+        assert_eq!(issues.len(), 2);
+        let mut settings = loaded.credentials();
+        assert_eq!(settings.token, "");
+        settings.password = "new-password".into();
+        save(&path, &store, &mut loaded, &settings);
+
+        assert_eq!(store.0.get(&token_key).unwrap(), Some("tok-123".into()));
+        assert_eq!(
+            store.0.get(&password_key).unwrap(),
+            Some("new-password".into())
+        );
+
+        settings.password.clear();
+        save(&path, &store, &mut loaded, &settings);
+        assert_eq!(store.0.get(&password_key).unwrap(), None);
+        assert_eq!(store.0.get(&token_key).unwrap(), Some("tok-123".into()));
+    }
+
+    #[test]
+    fn clear_removes_file_and_secrets() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CREDS_FILE);
+        let store = MemoryStore::default();
+        let mut stored = StoredCredentials::default();
+        save(&path, &store, &mut stored, &sample());
+
+        clear_creds(&path, &store, &stored);
+
+        assert!(!path.exists());
+        assert_eq!(read_creds_file(&path, &store).0.credentials().token, "");
+    }
+
+    #[test]
+    fn profiles_keep_separate_settings() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CREDS_FILE);
+        let store = MemoryStore::default();
+        let mut stored = StoredCredentials::default();
+        save(&path, &store, &mut stored, &sample());
+
+        let staging = Credentials {
+            gateway_url: "wss://staging.example/ws".into(),
+            token: "staging-token".into(),
+            ..Credentials::default()
+        };
+        let profile = stored.create("Staging", &staging).unwrap();
+        store_profile_secrets(&store, profile).unwrap();
+        stored.activate("staging").unwrap();
+        write_creds_file(&path, &stored).unwrap();
+
+        let loaded = read_creds_file(&path, &store).0;
+        assert_eq!(loaded.active_profile, "Staging");
+        assert_eq!(loaded.credentials().token, "staging-token");
+        assert_eq!(loaded.credentials().gateway_url, "wss://staging.example/ws");
+
+        let mut loaded = loaded;
+        loaded.activate("Default").unwrap();
+        assert_eq!(loaded.credentials().token, "tok-123");
+    }
+
+    #[test]
+    fn profile_names_are_unique_and_non_empty() {
+        let mut stored = StoredCredentials::default();
+        stored.create("Prod", &Credentials::default()).unwrap();
+
+        assert!(stored.create("prod", &Credentials::default()).is_err());
+        assert!(stored.create("  ", &Credentials::default()).is_err());
+        assert!(stored.rename("Prod", "default").is_err());
+        assert!(stored.rename("Missing", "Other").is_err());
+        stored.rename("Prod", "PROD").unwrap();
+        assert_eq!(names(&stored), ["Default", "PROD"]);
+    }
+
+    #[test]
+    fn renaming_active_profile_moves_pointer() {
+        let mut stored = StoredCredentials::default();
+        stored.rename("Default", "Local").unwrap();
+
+        assert_eq!(stored.active_profile, "Local");
+        assert!(stored.summaries()[0].active);
+    }
+
+    #[test]
+    fn deleting_active_profile_activates_first_remaining() {
+        let mut stored = StoredCredentials::default();
+        stored.create("Staging", &Credentials::default()).unwrap();
+        stored.activate("Staging").unwrap();
+
+        stored.delete("Staging").unwrap();
+        assert_eq!(stored.active_profile, "Default");
+        assert_eq!(
+            stored.delete("Default").unwrap_err(),
+            "Cannot delete the only profile"
+        );
+    }
+
+    #[test]
+    fn unreadable_file_is_backed_up_and_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CREDS_FILE);
+        std::fs::write(&path, "{ \"gatewayUrl\": ").unwrap();
+
+        let (stored, issues) = read_creds_file(&path, &MemoryStore::default());
+
+        assert_eq!(stored.credentials().gateway_url, "ws://127.0.0.1:19819");
+        assert!(!path.exists());
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].kind, ConfigIssueKind::Unreadable);
+        let backup = issues[0].backup_path.as_deref().unwrap();
+        assert_eq!(
+            std::fs::read_to_string(backup).unwrap(),
+            "{ \"gatewayUrl\": "
+        );
+    }
+
+    #[test]
+    fn save_refuses_a_file_that_became_unreadable() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CREDS_FILE);
+        std::fs::write(&path, "{ \"gatewayUrl\": ").unwrap();
+
+        let cached = StoredCredentials::default();
+        let result = update_creds_file(&path, &MemoryStore::default(), &cached, |stored, _| {
+            stored.history.enabled = false;
+            Ok(())
+        });
+
+        let error = result.unwrap_err();
+        assert!(error.starts_with("Settings not saved: Settings file could not be read"));
+        assert!(!path.exists());
+        let issues = LOAD_ISSUES.lock().unwrap_or_else(PoisonError::into_inner);
+        assert!(issues
+            .iter()
+            .any(|issue| issue.kind == ConfigIssueKind::Unreadable));
+    }
+
+    #[test]
+    fn saves_take_secrets_from_the_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CREDS_FILE);
+        let store = CountingStore::default();
+        let mut stored = StoredCredentials::default();
+        save(&path, &store, &mut stored, &sample());
+        let cached = read_creds_file(&path, &store).0;
+        let staging = Credentials {
+            token: "staging-token".into(),
+            ..Credentials::default()
+        };
+        // Another instance adds a profile after this one loaded.
+        update_creds_file(&path, &store, &cached, |stored, store| {
+            store_profile_secrets(store, stored.create("Staging", &staging)?)
+        })
+        .unwrap();
+        store.gets.store(0, Ordering::Relaxed);
+
+        let ((), saved) = update_creds_file(&path, &store, &cached, |stored, _| {
+            stored.history.enabled = false;
+            Ok(())
+        })
+        .unwrap();
+
+        assert_eq!(store.gets.load(Ordering::Relaxed), 2);
+        assert_eq!(saved.credentials().token, "tok-123");
+        let staging = &saved.profiles[saved.find("Staging").unwrap()];
+        assert_eq!(staging.token, "staging-token");
+    }
+
+    #[test]
+    fn issues_are_reported_once() {
+        let issue = ConfigIssue::new(ConfigIssueKind::Secrets, "reported once".into());
+        add_load_issues(vec![issue.clone()]);
+        add_load_issues(vec![issue.clone()]);
+
+        let issues = LOAD_ISSUES.lock().unwrap_or_else(PoisonError::into_inner);
+        assert_eq!(issues.iter().filter(|known| **known == issue).count(), 1);
+    }
+
+    #[test]
+    fn newer_version_is_not_overwritten() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CREDS_FILE);
+        let future = format!("{{\"version\": {}}}", CONFIG_VERSION + 1);
+        std::fs::write(&path, &future).unwrap();
+
+        let (_, issues) = read_creds_file(&path, &MemoryStore::default());
+
+        assert_eq!(issues[0].kind, ConfigIssueKind::Unreadable);
+        assert!(issues[0].message.contains("unsupported config version"));
+        let backup = issues[0].backup_path.as_deref().unwrap();
+        assert_eq!(std::fs::read_to_string(backup).unwrap(), future);
+    }
+
+    #[test]
+    fn flat_file_with_missing_fields_is_upgraded() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CREDS_FILE);
+        std::fs::write(&path, r#"{"gatewayUrl": "wss://gw.example/ws"}"#).unwrap();
+
+        let (stored, issues) = read_creds_file(&path, &MemoryStore::default());
+
+        assert_eq!(stored.credentials().gateway_url, "wss://gw.example/ws");
+        assert_eq!(stored.credentials().session_key, "main");
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].kind, ConfigIssueKind::Migrated);
+        let raw: Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(raw["version"], CONFIG_VERSION);
+    }
+
+    #[test]
+    fn current_file_loads_without_issues() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CREDS_FILE);
+        let store = MemoryStore::default();
+        let mut stored = StoredCredentials::default();
+        save(&path, &store, &mut stored, &sample());
+
+        let (loaded, issues) = read_creds_file(&path, &store);
+
+        assert!(issues.is_empty());
+        assert_eq!(loaded.version, CONFIG_VERSION);
+    }
+
+    #[test]
+    fn leftover_temp_file_from_crash_is_ignored() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CREDS_FILE);
+        let store = MemoryStore::default();
+        let mut stored = StoredCredentials::default();
+        save(&path, &store, &mut stored, &sample());
+        // A crash between writing the temp file and renaming it.
+        std::fs::write(dir.path().join("credentials.json.tmp-999-0"), "{\"versi").unwrap();
+
+        let (loaded, issues) = read_creds_file(&path, &store);
+
+        assert!(issues.is_empty());
+        assert_eq!(loaded.credentials().agent_id, "ops");
+    }
+
+    #[test]
+    fn concurrent_saves_keep_every_change() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CREDS_FILE);
+        let store = std::sync::Arc::new(MemoryStore::default());
+        write_creds_file(&path, &StoredCredentials::default()).unwrap();
+
+        let threads: Vec<_> = (0..8)
+            .map(|index| {
+                let (path, store) = (path.clone(), store.clone());
+                std::thread::spawn(move || {
+                    let settings = Credentials {
+                        token: format!("token-{index}"),
+                        ..Credentials::default()
+                    };
+                    let cached = StoredCredentials::default();
+                    update_creds_file(&path, store.as_ref(), &cached, |stored, store| {
+                        let profile = stored.create(&format!("P{index}"), &settings)?;
+                        store_profile_secrets(store, profile)
+                    })
+                    .unwrap();
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        let (loaded, issues) = read_creds_file(&path, store.as_ref());
+        assert!(issues.is_empty());
+        assert_eq!(loaded.profiles.len(), 9);
+        let p3 = &loaded.profiles[loaded.find("P3").unwrap()];
+        assert_eq!(p3.token, "token-3");
     }
-    Ok(())
 }
@@ -0,0 +1,144 @@
+use serde_json::Value;
+
+/// Pull displayable text out of a gateway payload. Mirrors `extractText`
+/// in `src/lib/extract-text.ts` so both clients render the same answer.
+pub fn extract_text(input: &Value) -> String {
+    if let Some(text) = input.as_str() {
+        return text.to_string();
+    }
+
+    let Some(map) = input.as_object() else {
+        return String::new();
+    };
+
+    for field in ["text", "message", "content", "summary", "delta"] {
+        if let Some(text) = map.get(field).and_then(Value::as_str) {
+            if !text.is_empty() {
+                return text.to_string();
+            }
+        }
+    }
+
+    // Content blocks: [{type: "text", text: "..."}]
+    if let Some(blocks) = map.get("content").and_then(Value::as_array) {
+        let parts: Vec<&str> = blocks
+            .iter()
+            .filter(|block| block.get("type").and_then(Value::as_str) == Some("text"))
+            .filter_map(|block| block.get("text").and_then(Value::as_str))
+            .filter(|text| !text.is_empty())
+            .collect();
+        if !parts.is_empty() {
+            return parts.join("\n");
+        }
+    }
+
+    if let Some(payloads) = map.get("payloads").and_then(Value::as_array) {
+        let nested = payloads
+            .iter()
+            .map(extract_text)
+            .filter(|text| !text.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let nested = nested.trim();
+        if !nested.is_empty() {
+            return nested.to_string();
+        }
+    }
+
+    String::new()
+}
+
+/// Like `extract_text` but keeps base64 image blocks as inline markers:
+/// `<!--INLINE_IMAGE:media_type:base64_data-->`, parsed at render time.
+pub fn extract_text_with_media(input: &Value) -> String {
+    if let Some(text) = input.as_str() {
+        return text.to_string();
+    }
+
+    if let Some(blocks) = input.get("content").and_then(Value::as_array) {
+        let mut parts = Vec::new();
+        for block in blocks {
+            match block.get("type").and_then(Value::as_str) {
+                Some("text") => {
+                    if let Some(text) = block.get("text").and_then(Value::as_str) {
+                        parts.push(text.to_string());
+                    }
+                }
+                Some("image") => {
+                    let Some(source) = block.get("source") else {
+                        continue;
+                    };
+                    if source.get("type").and_then(Value::as_str) != Some("base64") {
+                        continue;
+                    }
+                    if let (Some(media_type), Some(data)) = (
+                        source.get("media_type").and_then(Value::as_str),
+                        source.get("data").and_then(Value::as_str),
+                    ) {
+                        parts.push(format!("<!--INLINE_IMAGE:{media_type}:{data}-->"));
+                    }
+                }
+                _ => {}
+            }
+        }
+        if !parts.is_empty() {
+            return parts.join("\n");
+        }
+    }
+
+    extract_text(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn returns_strings_as_is() {
+        assert_eq!(extract_text(&json!("hello")), "hello");
+    }
+
+    #[test]
+    fn reads_direct_text_fields() {
+        assert_eq!(extract_text(&json!({ "message": "ok" })), "ok");
+    }
+
+    #[test]
+    fn reads_nested_payload_arrays() {
+        let input = json!({ "payloads": [{ "text": "first" }, { "content": "second" }] });
+        assert_eq!(extract_text(&input), "first\nsecond");
+    }
+
+    #[test]
+    fn reads_content_blocks() {
+        let input = json!({
+            "content": [
+                { "type": "text", "text": "block one" },
+                { "type": "text", "text": "block two" }
+            ]
+        });
+        assert_eq!(extract_text(&input), "block one\nblock two");
+    }
+
+    #[test]
+    fn returns_empty_for_unsupported_values() {
+        assert_eq!(extract_text(&json!({ "foo": "bar" })), "");
+        assert_eq!(extract_text(&Value::Null), "");
+        assert_eq!(extract_text(&json!([1, 2, 3])), "");
+    }
+
+    #[test]
+    fn keeps_inline_images_as_markers() {
+        let input = json!({
+            "content": [
+                { "type": "text", "text": "look" },
+                { "type": "image", "source": { "type": "base64", "media_type": "image/png", "data": "AAA" } }
+            ]
+        });
+        assert_eq!(
+            extract_text_with_media(&input),
+            "look\n<!--INLINE_IMAGE:image/png:AAA-->"
+        );
+    }
+}
@@ -0,0 +1,1101 @@
+use futures_util::{SinkExt, StreamExt};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock, PoisonError};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::{mpsc, oneshot, Notify};
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::credentials::{self, Credentials};
+use crate::extract_text::{extract_text, extract_text_with_media};
+use crate::history::{self, ChatContext};
+use crate::jobs::JobState;
+use crate::notch::publish_jobs;
+use crate::panel::PANEL;
+use crate::redaction::redact;
+
+pub const PROTOCOL_VERSION: u64 = 3;
+pub const GATEWAY_STATE_EVENT: &str = "gateway-state";
+pub const GATEWAY_EVENT: &str = "gateway-event";
+
+const CLIENT_ID: &str = "openclaw-control-ui";
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(8);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+const RECONNECT_INITIAL_MS: u64 = 1000;
+const RECONNECT_MAX_MS: u64 = 15_000;
+
+static GATEWAY: OnceLock<GatewayClient> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConnectionState {
+    Idle,
+    Connecting,
+    Connected,
+    Error,
+}
+
+/// Chat-level events, shaped like `BotEvent` in `src/lib/ws-client.ts`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum BotEvent {
+    Assistant {
+        text: String,
+    },
+    AssistantDelta {
+        text: String,
+    },
+    AssistantDone,
+    Error {
+        text: String,
+    },
+    /// The answer was stopped; the text streamed so far stays.
+    Aborted,
+}
+
+/// Which prompt an event answers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChatRun {
+    /// Carried by the response to the `chat.send` request with this id.
+    Request(String),
+    /// Chat or agent event of the run with this id, which the response to
+    /// `chat.send` named.
+    Run(String),
+}
+
+/// What the agent does besides writing the answer, for the notch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AgentActivity {
+    /// A tool call started (`true`) or returned (`false`).
+    Tool { running: bool },
+    /// Tokens the answer used, reported before it finishes.
+    Usage { tokens: u64 },
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GatewayStatus {
+    pub state: ConnectionState,
+    pub note: Option<String>,
+}
+
+/// Receives connection state changes and chat events. The Tauri app
+/// forwards them to every webview; tests record them.
+pub trait GatewaySink: Send + Sync {
+    fn on_state(&self, state: ConnectionState, note: Option<&str>);
+    fn on_event(&self, run: &ChatRun, event: BotEvent);
+    /// The `chat.send` request `request` started the run `run`.
+    fn on_run_started(&self, _request: &str, _run: &str) {}
+    fn on_activity(&self, _run: &ChatRun, _activity: AgentActivity) {}
+}
+
+type Pending = HashMap<String, oneshot::Sender<Result<Value, String>>>;
+
+struct Connection {
+    outgoing: mpsc::UnboundedSender<Message>,
+    pending: Mutex<Pending>,
+    authenticated: AtomicBool,
+    shutdown: Notify,
+}
+
+impl Connection {
+    fn send(&self, frame: &Value) -> Result<(), String> {
+        self.outgoing
+            .send(Message::Text(frame.to_string().into()))
+            .map_err(|_| "Not connected".to_string())
+    }
+
+    fn reject_all(&self, reason: &str) {
+        let drained: Vec<_> = lock(&self.pending).drain().collect();
+        for (_, pending) in drained {
+            let _ = pending.send(Err(reason.to_string()));
+        }
+    }
+}
+
+struct Inner {
+    sink: Arc<dyn GatewaySink>,
+    connection: Mutex<Option<Arc<Connection>>>,
+    settings: Mutex<Option<Credentials>>,
+    status: Mutex<GatewayStatus>,
+    request_id: AtomicU64,
+    generation: AtomicU64,
+}
+
+impl Inner {
+    fn set_state(&self, state: ConnectionState, note: Option<String>) {
+        *lock(&self.status) = GatewayStatus {
+            state,
+            note: note.clone(),
+        };
+        self.sink.on_state(state, note.as_deref());
+    }
+
+    fn next_id(&self) -> String {
+        self.request_id.fetch_add(1, Ordering::SeqCst).to_string()
+    }
+
+    fn is_current(&self, generation: u64) -> bool {
+        self.generation.load(Ordering::SeqCst) == generation
+    }
+}
+
+/// Native OpenClaw gateway client. Owns the WebSocket, the
+/// connect.challenge handshake, pending requests and reconnect backoff,
+/// so streaming survives the webview being hidden or reloaded.
+#[derive(Clone)]
+pub struct GatewayClient {
+    inner: Arc<Inner>,
+}
+
+impl GatewayClient {
+    pub fn new(sink: Arc<dyn GatewaySink>) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                sink,
+                connection: Mutex::new(None),
+                settings: Mutex::new(None),
+                status: Mutex::new(GatewayStatus {
+                    state: ConnectionState::Idle,
+                    note: None,
+                }),
+                request_id: AtomicU64::new(1),
+                generation: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    pub fn status(&self) -> GatewayStatus {
+        lock(&self.inner.status).clone()
+    }
+
+    /// Connect and wait for the handshake to be accepted. The connection
+    /// keeps reconnecting with exponential backoff until `disconnect`.
+    pub async fn connect(&self, settings: Credentials) -> Result<(), String> {
+        let generation = self.stop();
+        *lock(&self.inner.settings) = Some(settings.clone());
+        self.inner.set_state(ConnectionState::Connecting, None);
+
+        let (ready_tx, ready_rx) = oneshot::channel();
+        tokio::spawn(supervise(
+            self.inner.clone(),
+            settings,
+            generation,
+            ready_tx,
+        ));
+
+        match tokio::time::timeout(CONNECT_TIMEOUT, ready_rx).await {
+            Ok(Ok(result)) => result,
+            Ok(Err(_)) => Err("Connection closed".into()),
+            Err(_) => {
+                self.stop();
+                let reason = "Connection timed out".to_string();
+                self.inner
+                    .set_state(ConnectionState::Error, Some(reason.clone()));
+                Err(reason)
+            }
+        }
+    }
+
+    pub fn disconnect(&self) {
+        self.stop();
+        self.inner.set_state(ConnectionState::Idle, None);
+    }
+
+    /// Settings of the current (or last) connection.
+    pub fn settings(&self) -> Option<Credentials> {
+        lock(&self.inner.settings).clone()
+    }
+
+    /// Fire-and-forget `chat.send`; the answer arrives as `BotEvent`s.
+    /// Returns the request id, which the response to it carries.
+    pub fn send_chat(&self, message: &str) -> Result<String, String> {
+        let connection = self.authenticated_connection()?;
+        let settings = lock(&self.inner.settings).clone().unwrap_or_default();
+        let id = self.inner.next_id();
+        connection.send(&chat_send_frame(&id, message, &settings))?;
+        Ok(id)
+    }
+
+    /// Fire-and-forget `chat.abort` for the configured session; the gateway
+    /// answers with an `aborted` chat event.
+    pub fn abort_chat(&self) -> Result<(), String> {
+        let connection = self.authenticated_connection()?;
+        let settings = lock(&self.inner.settings).clone().unwrap_or_default();
+        connection.send(&json!({
+            "type": "req",
+            "id": self.inner.next_id(),
+            "method": "chat.abort",
+            "params": { "sessionKey": session_key(&settings) },
+        }))
+    }
+
+    /// Send an RPC request and wait for the matching `res` frame.
+    pub async fn request(&self, method: &str, params: Option<Value>) -> Result<Value, String> {
+        let connection = self.authenticated_connection()?;
+        let id = self.inner.next_id();
+        let mut frame = json!({ "type": "req", "id": id, "method": method });
+        if let Some(params) = params {
+            frame["params"] = params;
+        }
+
+        let (tx, rx) = oneshot::channel();
+        lock(&connection.pending).insert(id.clone(), tx);
+        if let Err(error) = connection.send(&frame) {
+            lock(&connection.pending).remove(&id);
+            return Err(error);
+        }
+
+        match tokio::time::timeout(REQUEST_TIMEOUT, rx).await {
+            Ok(Ok(result)) => result,
+            Ok(Err(_)) => Err("Connection closed".into()),
+            Err(_) => {
+                lock(&connection.pending).remove(&id);
+                Err(format!("Request {method} timed out"))
+            }
+        }
+    }
+
+    fn authenticated_connection(&self) -> Result<Arc<Connection>, String> {
+        lock(&self.inner.connection)
+            .as_ref()
+            .filter(|connection| connection.authenticated.load(Ordering::SeqCst))
+            .cloned()
+            .ok_or_else(|| "Not connected".to_string())
+    }
+
+    /// Invalidate the running session and reconnect loop. Returns the new
+    /// generation so a fresh supervisor can claim it.
+    fn stop(&self) -> u64 {
+        let generation = self.inner.generation.fetch_add(1, Ordering::SeqCst) + 1;
+        if let Some(connection) = lock(&self.inner.connection).take() {
+            connection.authenticated.store(false, Ordering::SeqCst);
+            connection.reject_all("Disconnected");
+            connection.shutdown.notify_one();
+        }
+        generation
+    }
+}
+
+type Ready = Option<oneshot::Sender<Result<(), String>>>;
+
+async fn supervise(
+    inner: Arc<Inner>,
+    settings: Credentials,
+    generation: u64,
+    ready: oneshot::Sender<Result<(), String>>,
+) {
+    let mut ready = Some(ready);
+    let mut delay = RECONNECT_INITIAL_MS;
+
+    loop {
+        run_session(&inner, &settings, generation, &mut ready, &mut delay).await;
+        if !inner.is_current(generation) {
+            return;
+        }
+
+        tokio::time::sleep(Duration::from_millis(delay)).await;
+        if !inner.is_current(generation) {
+            return;
+        }
+        // Exponential backoff: 1s → 2s → 4s → 8s → 15s max.
+        delay = (delay * 2).min(RECONNECT_MAX_MS);
+        inner.set_state(ConnectionState::Connecting, None);
+    }
+}
+
+async fn run_session(
+    inner: &Arc<Inner>,
+    settings: &Credentials,
+    generation: u64,
+    ready: &mut Ready,
+    delay: &mut u64,
+) {
+    let stream = match tokio_tungstenite::connect_async(settings.gateway_url.as_str()).await {
+        Ok((stream, _)) => stream,
+        Err(error) => {
+            eprintln!("gateway connect failed: {error}");
+            let reason = format!("Cannot connect to {}", settings.gateway_url);
+            if inner.is_current(generation) {
+                inner.set_state(ConnectionState::Error, Some(reason.clone()));
+            }
+            resolve_ready(ready, Err(reason));
+            return;
+        }
+    };
+    *delay = RECONNECT_INITIAL_MS;
+
+    let (mut write, mut read) = stream.split();
+    let (outgoing, mut outgoing_rx) = mpsc::unbounded_channel::<Message>();
+    let connection = Arc::new(Connection {
+        outgoing,
+        pending: Mutex::new(HashMap::new()),
+        authenticated: AtomicBool::new(false),
+        shutdown: Notify::new(),
+    });
+
+    {
+        let mut slot = lock(&inner.connection);
+        if !inner.is_current(generation) {
+            return;
+        }
+        *slot = Some(connection.clone());
+    }
+
+    // The writer closes the socket once every sender handle is dropped.
+    tokio::spawn(async move {
+        while let Some(message) = outgoing_rx.recv().await {
+            if write.send(message).await.is_err() {
+                break;
+            }
+        }
+        let _ = write.close().await;
+    });
+
+    let mut handshake_id: Option<String> = None;
+    loop {
+        let message = tokio::select! {
+            message = read.next() => message,
+            _ = connection.shutdown.notified() => break,
+        };
+        let text = match message {
+            Some(Ok(Message::Text(text))) => text,
+            Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+            Some(Ok(_)) => continue,
+        };
+        let Ok(frame) = serde_json::from_str::<Value>(&text) else {
+            continue;
+        };
+        if !frame.is_object() {
+            continue;
+        }
+
+        let mut session = Session {
+            inner,
+            connection: &connection,
+            settings,
+            handshake_id: &mut handshake_id,
+            ready: &mut *ready,
+        };
+        session.handle_frame(&frame);
+    }
+
+    connection.authenticated.store(false, Ordering::SeqCst);
+    connection.reject_all("Connection closed");
+    {
+        let mut slot = lock(&inner.connection);
+        if slot
+            .as_ref()
+            .is_some_and(|current| Arc::ptr_eq(current, &connection))
+        {
+            *slot = None;
+        }
+    }
+
+    resolve_ready(ready, Err("Connection closed".into()));
+    if inner.is_current(generation) {
+        inner.set_state(ConnectionState::Idle, Some("Disconnected".into()));
+    }
+}
+
+struct Session<'a> {
+    inner: &'a Inner,
+    connection: &'a Connection,
+    settings: &'a Credentials,
+    handshake_id: &'a mut Option<String>,
+    ready: &'a mut Ready,
+}
+
+impl Session<'_> {
+    fn handle_frame(&mut self, frame: &Value) {
+        match frame.get("type").and_then(Value::as_str) {
+            Some("res") => self.handle_response(frame),
+            Some("event") => self.handle_event(frame),
+            _ => {}
+        }
+    }
+
+    fn handle_response(&mut self, frame: &Value) {
+        let id = frame.get("id").and_then(Value::as_str).unwrap_or_default();
+        let ok = frame.get("ok").and_then(Value::as_bool).unwrap_or(false);
+        let error = frame.get("error").unwrap_or(&Value::Null);
+
+        if self.handshake_id.as_deref() == Some(id) {
+            *self.handshake_id = None;
+            if ok {
+                self.connection.authenticated.store(true, Ordering::SeqCst);
+                self.inner.set_state(ConnectionState::Connected, None);
+                resolve_ready(self.ready, Ok(()));
+            } else {
+                let reason = text_or(extract_text(error), "Request failed");
+                self.inner
+                    .set_state(ConnectionState::Error, Some(reason.clone()));
+                resolve_ready(self.ready, Err(reason));
+            }
+            return;
+        }
+
+        let pending = lock(&self.connection.pending).remove(id);
+        if let Some(pending) = pending {
+            let result = if ok {
+                Ok(frame.get("payload").cloned().unwrap_or(Value::Null))
+            } else {
+                Err(text_or(extract_text(error), "Request failed"))
+            };
+            let _ = pending.send(result);
+            return;
+        }
+
+        // Response for chat.send or other fire-and-forget requests.
+        let sink = self.inner.sink.as_ref();
+        let request = ChatRun::Request(id.to_string());
+        if !ok && !error.is_null() {
+            let text = text_or(extract_text(error), "Gateway returned an error.");
+            sink.on_event(&request, BotEvent::Error { text });
+            return;
+        }
+
+        let payload = frame.get("payload").unwrap_or(&Value::Null);
+        if let Some(run) = payload.get("runId").and_then(Value::as_str) {
+            sink.on_run_started(id, run);
+        }
+        // An answer carried by the response itself is complete.
+        let text = extract_text(payload);
+        if !text.is_empty() {
+            sink.on_event(&request, BotEvent::Assistant { text });
+            sink.on_event(&request, BotEvent::AssistantDone);
+        }
+    }
+
+    fn handle_event(&mut self, frame: &Value) {
+        let payload = frame.get("payload").unwrap_or(&Value::Null);
+        match frame.get("event").and_then(Value::as_str) {
+            Some("connect.challenge") => {
+                let id = self.inner.next_id();
+                if self
+                    .connection
+                    .send(&handshake_frame(&id, self.settings))
+                    .is_ok()
+                {
+                    *self.handshake_id = Some(id);
+                }
+            }
+            Some("chat") => handle_chat_event(self.inner.sink.as_ref(), payload),
+            Some("agent") => handle_agent_event(self.inner.sink.as_ref(), payload),
+            _ => {}
+        }
+    }
+}
+
+/// Run named by a chat or agent event; events without one are dropped.
+fn event_run(payload: &Value) -> Option<ChatRun> {
+    let run = payload.get("runId").and_then(Value::as_str)?;
+    Some(ChatRun::Run(run.to_string()))
+}
+
+/// Chat events: `{event: "chat", payload: {runId, state: "delta"|"final"|"aborted"|"error", message, ...}}`.
+fn handle_chat_event(sink: &dyn GatewaySink, payload: &Value) {
+    let Some(run) = event_run(payload) else {
+        return;
+    };
+    let message = payload.get("message").unwrap_or(&Value::Null);
+    // Before the state: the answer is finished once that is handled.
+    if let Some(tokens) = usage_tokens(payload) {
+        sink.on_activity(&run, AgentActivity::Usage { tokens });
+    }
+    match payload.get("state").and_then(Value::as_str) {
+        Some("delta") => {
+            let text = extract_text(message);
+            if !text.is_empty() {
+                sink.on_event(&run, BotEvent::AssistantDelta { text });
+            }
+        }
+        Some("final") => {
+            // The final text first, so the answer is whole once it is done.
+            let text = extract_text_with_media(message);
+            if !text.is_empty() {
+                sink.on_event(&run, BotEvent::Assistant { text });
+            }
+            sink.on_event(&run, BotEvent::AssistantDone);
+        }
+        Some("aborted") => sink.on_event(&run, BotEvent::Aborted),
+        Some("error") => {
+            let text = match payload.get("errorMessage").and_then(Value::as_str) {
+                Some(error_message) => error_message.to_string(),
+                None => text_or(extract_text(message), "Agent error."),
+            };
+            sink.on_event(&run, BotEvent::Error { text });
+        }
+        _ => {}
+    }
+}
+
+/// `usage` of a chat event or its message: `totalTokens`, else `input` plus
+/// `output`.
+fn usage_tokens(payload: &Value) -> Option<u64> {
+    let usage = payload
+        .get("usage")
+        .or_else(|| payload.get("message")?.get("usage"))?;
+    let count = |key| usage.get(key).and_then(Value::as_u64);
+    count("totalTokens").or_else(|| Some(count("input")? + count("output")?))
+}
+
+/// Agent events: `{event: "agent", payload: {runId, stream: "tool", data: {phase: "start"|"update"|"result", name}}}`.
+fn handle_agent_event(sink: &dyn GatewaySink, payload: &Value) {
+    if payload.get("stream").and_then(Value::as_str) != Some("tool") {
+        return;
+    }
+    let Some(run) = event_run(payload) else {
+        return;
+    };
+    let running = match payload.pointer("/data/phase").and_then(Value::as_str) {
+        Some("start") => true,
+        Some("result") => false,
+        _ => return,
+    };
+    sink.on_activity(&run, AgentActivity::Tool { running });
+}
+
+fn handshake_frame(id: &str, settings: &Credentials) -> Value {
+    let mut params = json!({
+        "minProtocol": PROTOCOL_VERSION,
+        "maxProtocol": PROTOCOL_VERSION,
+        "client": {
+            "id": CLIENT_ID,
+            "version": env!("CARGO_PKG_VERSION"),
+            "platform": client_platform(),
+            "mode": "backend",
+        },
+        "role": "operator",
+        "scopes": ["operator.admin"],
+    });
+
+    let mut auth = serde_json::Map::new();
+    if !settings.token.is_empty() {
+        auth.insert("token".into(), json!(settings.token));
+    }
+    if !settings.password.is_empty() {
+        auth.insert("password".into(), json!(settings.password));
+    }
+    if !auth.is_empty() {
+        params["auth"] = Value::Object(auth);
+    }
+
+    json!({ "type": "req", "id": id, "method": "connect", "params": params })
+}
+
+fn session_key(settings: &Credentials) -> &str {
+    if settings.session_key.is_empty() {
+        "main"
+    } else {
+        settings.session_key.as_str()
+    }
+}
+
+fn chat_send_frame(id: &str, message: &str, settings: &Credentials) -> Value {
+    let mut params = json!({
+        "message": message,
+        "sessionKey": session_key(settings),
+        "idempotencyKey": uuid::Uuid::new_v4().to_string(),
+    });
+    if !settings.agent_id.is_empty() {
+        params["agentId"] = json!(settings.agent_id);
+    }
+
+    json!({ "type": "req", "id": id, "method": "chat.send", "params": params })
+}
+
+fn client_platform() -> &'static str {
+    match std::env::consts::OS {
+        "macos" => "darwin",
+        other => other,
+    }
+}
+
+fn resolve_ready(ready: &mut Ready, result: Result<(), String>) {
+    if let Some(ready) = ready.take() {
+        let _ = ready.send(result);
+    }
+}
+
+fn text_or(text: String, fallback: &str) -> String {
+    if text.is_empty() {
+        fallback.to_string()
+    } else {
+        text
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Forwards gateway callbacks to all webviews (panel and notch).
+struct TauriSink {
+    app: AppHandle,
+}
+
+impl GatewaySink for TauriSink {
+    fn on_state(&self, state: ConnectionState, note: Option<&str>) {
+        let status = GatewayStatus {
+            state,
+            note: note.map(str::to_string),
+        };
+        let _ = self.app.emit(GATEWAY_STATE_EVENT, status);
+        if !matches!(state, ConnectionState::Idle | ConnectionState::Error) {
+            return;
+        }
+        let reason = "Connection lost";
+        let failed = PANEL.jobs().fail_running(reason);
+        for &job in &failed {
+            history::record_event(
+                job,
+                &BotEvent::Error {
+                    text: reason.to_string(),
+                },
+            );
+        }
+        if !failed.is_empty() {
+            publish_jobs(&self.app);
+        }
+    }
+
+    fn on_event(&self, run: &ChatRun, event: BotEvent) {
+        let (job, shown) = {
+            let mut jobs = PANEL.jobs();
+            (jobs.record(run, &event), jobs.shown())
+        };
+        let Some(job) = job else {
+            return;
+        };
+        history::record_event(job, &event);
+        publish_jobs(&self.app);
+        // The panel shows one response; the others live in the notch.
+        if Some(job) == shown {
+            let _ = self.app.emit(GATEWAY_EVENT, event);
+        }
+    }
+
+    fn on_run_started(&self, request: &str, run: &str) {
+        PANEL.jobs().run_started(request, run);
+    }
+
+    fn on_activity(&self, run: &ChatRun, activity: AgentActivity) {
+        let (state, tokens) = match activity {
+            AgentActivity::Tool { running: true } => (JobState::ToolRunning, None),
+            AgentActivity::Tool { running: false } => (JobState::Streaming, None),
+            AgentActivity::Usage { tokens } => (JobState::Streaming, Some(tokens)),
+        };
+        if PANEL.jobs().update(run, state, tokens).is_some() {
+            publish_jobs(&self.app);
+        }
+    }
+}
+
+pub fn init_gateway(app: &AppHandle) {
+    GATEWAY.get_or_init(|| GatewayClient::new(Arc::new(TauriSink { app: app.clone() })));
+}
+
+fn gateway() -> Result<&'static GatewayClient, String> {
+    GATEWAY
+        .get()
+        .ok_or_else(|| "Gateway client is not initialized".to_string())
+}
+
+/// Abort the streaming response, if any (used by the abort shortcut).
+pub fn abort_chat() -> Result<(), String> {
+    gateway()?.abort_chat()
+}
+
+#[tauri::command]
+pub async fn gateway_connect(settings: Credentials) -> Result<String, String> {
+    gateway()?.connect(settings).await?;
+    Ok("Connected".into())
+}
+
+#[tauri::command]
+pub fn gateway_disconnect() -> Result<(), String> {
+    gateway()?.disconnect();
+    Ok(())
+}
+
+/// `context` is what the user typed and selected, recorded in the history
+/// instead of the assembled `message`. Selections arrive already redacted;
+/// typed text is redacted here when the settings ask for it. Returns the
+/// id of the job tracking the answer.
+#[tauri::command]
+pub fn gateway_send_chat(
+    app: AppHandle,
+    message: String,
+    context: Option<ChatContext>,
+) -> Result<u64, String> {
+    let client = gateway()?;
+    let mut context = context.unwrap_or_else(|| ChatContext {
+        prompt: message.clone(),
+        selection: None,
+    });
+    let redaction = credentials::load_credentials()
+        .map(|creds| creds.redaction)
+        .unwrap_or_default();
+    let message = if redaction.redact_prompts {
+        context.prompt = redact(&context.prompt, &redaction).text;
+        redact(&message, &redaction).text
+    } else {
+        message
+    };
+    let label = if context.prompt.trim().is_empty() {
+        &message
+    } else {
+        &context.prompt
+    };
+    // The job holds its request id before the response to it is handled:
+    // that waits for the lock.
+    let mut jobs = PANEL.jobs();
+    let job = jobs.start(label);
+    history::record_prompt(job, &context, &client.settings().unwrap_or_default());
+    let sent = match client.send_chat(&message) {
+        Ok(request) => {
+            jobs.sent(job, request);
+            Ok(job)
+        }
+        Err(error) => {
+            jobs.fail(job, &error);
+            history::record_event(
+                job,
+                &BotEvent::Error {
+                    text: error.clone(),
+                },
+            );
+            Err(error)
+        }
+    };
+    drop(jobs);
+    publish_jobs(&app);
+    sent
+}
+
+#[tauri::command]
+pub async fn gateway_request(method: String, params: Option<Value>) -> Result<Value, String> {
+    gateway()?.request(&method, params).await
+}
+
+#[tauri::command]
+pub fn gateway_status() -> Result<GatewayStatus, String> {
+    Ok(gateway()?.status())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::future::Future;
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::{accept_async, WebSocketStream};
+
+    type ServerSocket = WebSocketStream<tokio::net::TcpStream>;
+
+    #[derive(Default)]
+    struct RecordingSink {
+        states: Mutex<Vec<ConnectionState>>,
+        events: Mutex<Vec<BotEvent>>,
+        runs: Mutex<Vec<ChatRun>>,
+        started: Mutex<Vec<(String, String)>>,
+        activity: Mutex<Vec<AgentActivity>>,
+    }
+
+    impl GatewaySink for RecordingSink {
+        fn on_state(&self, state: ConnectionState, _note: Option<&str>) {
+            lock(&self.states).push(state);
+        }
+
+        fn on_event(&self, run: &ChatRun, event: BotEvent) {
+            lock(&self.runs).push(run.clone());
+            lock(&self.events).push(event);
+        }
+
+        fn on_run_started(&self, request: &str, run: &str) {
+            lock(&self.started).push((request.into(), run.into()));
+        }
+
+        fn on_activity(&self, _run: &ChatRun, activity: AgentActivity) {
+            lock(&self.activity).push(activity);
+        }
+    }
+
+    impl RecordingSink {
+        async fn wait_for_events(&self, count: usize) -> Vec<BotEvent> {
+            for _ in 0..200 {
+                if lock(&self.events).len() >= count {
+                    break;
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+            lock(&self.events).clone()
+        }
+    }
+
+    async fn spawn_server<F, Fut>(handler: F) -> String
+    where
+        F: FnOnce(ServerSocket) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let socket = accept_async(stream).await.unwrap();
+            handler(socket).await;
+        });
+        format!("ws://{addr}")
+    }
+
+    async fn send_json(socket: &mut ServerSocket, value: Value) {
+        socket
+            .send(Message::Text(value.to_string().into()))
+            .await
+            .unwrap();
+    }
+
+    async fn next_request(socket: &mut ServerSocket) -> Value {
+        loop {
+            match socket.next().await {
+                Some(Ok(Message::Text(text))) => return serde_json::from_str(&text).unwrap(),
+                Some(Ok(_)) => continue,
+                other => panic!("unexpected message: {other:?}"),
+            }
+        }
+    }
+
+    /// Issue the challenge and answer the `connect` request.
+    async fn handshake(socket: &mut ServerSocket, accept: bool) -> Value {
+        send_json(
+            socket,
+            json!({ "type": "event", "event": "connect.challenge", "payload": { "nonce": "n" } }),
+        )
+        .await;
+        let connect = next_request(socket).await;
+        let reply = if accept {
+            json!({ "type": "res", "id": connect["id"], "ok": true, "payload": {} })
+        } else {
+            json!({ "type": "res", "id": connect["id"], "ok": false, "error": { "message": "bad token" } })
+        };
+        send_json(socket, reply).await;
+        connect
+    }
+
+    fn settings(url: String) -> Credentials {
+        Credentials {
+            gateway_url: url,
+            token: "secret".into(),
+            agent_id: "agent-1".into(),
+            ..Credentials::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn handshakes_and_streams_chat_events() {
+        let url = spawn_server(|mut socket| async move {
+            let connect = handshake(&mut socket, true).await;
+            assert_eq!(connect["method"], "connect");
+            assert_eq!(connect["params"]["minProtocol"], 3);
+            assert_eq!(connect["params"]["auth"]["token"], "secret");
+
+            let send = next_request(&mut socket).await;
+            assert_eq!(send["method"], "chat.send");
+            assert_eq!(send["params"]["message"], "hi");
+            assert_eq!(send["params"]["agentId"], "agent-1");
+            assert_eq!(send["params"]["sessionKey"], "main");
+            send_json(
+                &mut socket,
+                json!({ "type": "res", "id": send["id"], "ok": true, "payload": { "runId": "r1" } }),
+            )
+            .await;
+
+            for state in [
+                json!({ "runId": "r1", "state": "delta", "message": { "text": "Hel" } }),
+                json!({ "runId": "r1", "state": "delta", "message": { "text": "Hello" } }),
+                json!({ "state": "delta", "message": { "text": "No run" } }),
+                json!({ "runId": "r1", "state": "final", "message": { "content": [{ "type": "text", "text": "Hello!" }] } }),
+            ] {
+                send_json(&mut socket, json!({ "type": "event", "event": "chat", "payload": state }))
+                    .await;
+            }
+            let _ = socket.next().await;
+        })
+        .await;
+
+        let sink = Arc::new(RecordingSink::default());
+        let client = GatewayClient::new(sink.clone());
+        client.connect(settings(url)).await.unwrap();
+        assert_eq!(client.status().state, ConnectionState::Connected);
+
+        let request = client.send_chat("hi").unwrap();
+        let events = sink.wait_for_events(4).await;
+        assert_eq!(lock(&sink.started).clone(), [(request, "r1".to_string())]);
+        assert!(lock(&sink.runs)
+            .iter()
+            .all(|run| *run == ChatRun::Run("r1".into())));
+        assert_eq!(
+            events,
+            vec![
+                BotEvent::AssistantDelta { text: "Hel".into() },
+                BotEvent::AssistantDelta {
+                    text: "Hello".into()
+                },
+                BotEvent::Assistant {
+                    text: "Hello!".into()
+                },
+                BotEvent::AssistantDone,
+            ]
+        );
+        client.disconnect();
+    }
+
+    #[tokio::test]
+    async fn rejected_handshake_reports_error() {
+        let url = spawn_server(|mut socket| async move {
+            handshake(&mut socket, false).await;
+            let _ = socket.next().await;
+        })
+        .await;
+
+        let sink = Arc::new(RecordingSink::default());
+        let client = GatewayClient::new(sink.clone());
+        let result = client.connect(settings(url)).await;
+        client.disconnect();
+
+        assert_eq!(result, Err("bad token".into()));
+        assert!(lock(&sink.states).contains(&ConnectionState::Error));
+    }
+
+    #[tokio::test]
+    async fn request_resolves_with_response_payload() {
+        let url = spawn_server(|mut socket| async move {
+            handshake(&mut socket, true).await;
+            let request = next_request(&mut socket).await;
+            assert_eq!(request["method"], "agents.list");
+            send_json(
+                &mut socket,
+                json!({ "type": "res", "id": request["id"], "ok": true, "payload": { "agents": [] } }),
+            )
+            .await;
+            let _ = socket.next().await;
+        })
+        .await;
+
+        let client = GatewayClient::new(Arc::new(RecordingSink::default()));
+        client.connect(settings(url)).await.unwrap();
+        let payload = client.request("agents.list", None).await.unwrap();
+        client.disconnect();
+
+        assert_eq!(payload, json!({ "agents": [] }));
+    }
+
+    #[tokio::test]
+    async fn rejected_send_names_its_request() {
+        let url = spawn_server(|mut socket| async move {
+            handshake(&mut socket, true).await;
+            let send = next_request(&mut socket).await;
+            send_json(
+                &mut socket,
+                json!({ "type": "res", "id": send["id"], "ok": false, "error": { "message": "busy" } }),
+            )
+            .await;
+            let _ = socket.next().await;
+        })
+        .await;
+
+        let sink = Arc::new(RecordingSink::default());
+        let client = GatewayClient::new(sink.clone());
+        client.connect(settings(url)).await.unwrap();
+        let request = client.send_chat("hi").unwrap();
+        let events = sink.wait_for_events(1).await;
+        client.disconnect();
+
+        assert_eq!(
+            events,
+            [BotEvent::Error {
+                text: "busy".into()
+            }]
+        );
+        assert_eq!(lock(&sink.runs).clone(), [ChatRun::Request(request)]);
+    }
+
+    #[test]
+    fn chat_error_and_abort_events() {
+        let sink = RecordingSink::default();
+        let run = |mut payload: Value| {
+            payload["runId"] = json!("r1");
+            payload
+        };
+        handle_chat_event(
+            &sink,
+            &run(json!({ "state": "error", "errorMessage": "boom" })),
+        );
+        handle_chat_event(&sink, &run(json!({ "state": "error" })));
+        handle_chat_event(&sink, &run(json!({ "state": "aborted" })));
+
+        assert_eq!(
+            lock(&sink.events).clone(),
+            vec![
+                BotEvent::Error {
+                    text: "boom".into()
+                },
+                BotEvent::Error {
+                    text: "Agent error.".into()
+                },
+                BotEvent::Aborted,
+            ]
+        );
+    }
+
+    #[test]
+    fn tool_calls_and_usage_become_activity() {
+        let sink = RecordingSink::default();
+        for phase in ["start", "update", "result"] {
+            let data = json!({ "phase": phase, "name": "exec" });
+            handle_agent_event(
+                &sink,
+                &json!({ "runId": "r1", "stream": "tool", "data": data }),
+            );
+        }
+        handle_agent_event(
+            &sink,
+            &json!({ "runId": "r1", "stream": "assistant", "data": {} }),
+        );
+        let message = json!({ "content": "Done", "usage": { "input": 300, "output": 112 } });
+        handle_chat_event(
+            &sink,
+            &json!({ "runId": "r1", "state": "final", "message": message }),
+        );
+        handle_chat_event(
+            &sink,
+            &json!({ "runId": "r1", "state": "final", "usage": { "totalTokens": 7 } }),
+        );
+
+        assert_eq!(
+            lock(&sink.activity).clone(),
+            vec![
+                AgentActivity::Tool { running: true },
+                AgentActivity::Tool { running: false },
+                AgentActivity::Usage { tokens: 412 },
+                AgentActivity::Usage { tokens: 7 },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn unreachable_gateway_fails_fast() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+
+        let client = GatewayClient::new(Arc::new(RecordingSink::default()));
+        let result = client.connect(settings(format!("ws://{addr}"))).await;
+        client.disconnect();
+
+        assert_eq!(result, Err(format!("Cannot connect to ws://{addr}")));
+    }
+}
@@ -0,0 +1,782 @@
+//! Conversation history in an SQLite file next to the settings. Every
+//! exchange sent from the panel is recorded with the context it was sent
+//! with and the final answer, so it survives the panel resetting.
+
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config_file;
+use crate::credentials::{self, Credentials};
+use crate::gateway::BotEvent;
+
+const HISTORY_FILE: &str = "history.sqlite3";
+const DEFAULT_LIST_LIMIT: u32 = 50;
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+/// Tokens of context around the matches in a search snippet.
+const SNIPPET_TOKENS: u32 = 24;
+
+/// Markers around matched words in [`SearchHit::snippet`]. Control
+/// characters, so they never clash with the text itself.
+pub const MATCH_START: &str = "\u{2}";
+pub const MATCH_END: &str = "\u{3}";
+
+/// `MIGRATIONS[n]` upgrades schema version `n` to `n + 1` (SQLite
+/// `user_version`).
+const MIGRATIONS: [&str; 2] = [
+    "CREATE TABLE entries (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        prompt TEXT NOT NULL,
+        selection TEXT,
+        agent_id TEXT NOT NULL,
+        session_key TEXT NOT NULL,
+        created_at INTEGER NOT NULL,
+        completed_at INTEGER,
+        response TEXT NOT NULL DEFAULT '',
+        status TEXT NOT NULL DEFAULT 'pending',
+        error TEXT
+    );
+    CREATE INDEX entries_created_at ON entries (created_at);",
+    // Full-text index kept in sync with `entries` by triggers.
+    "CREATE VIRTUAL TABLE entries_fts USING fts5(
+        prompt, selection, response,
+        content = 'entries', content_rowid = 'id',
+        tokenize = 'unicode61 remove_diacritics 2'
+    );
+    CREATE TRIGGER entries_fts_insert AFTER INSERT ON entries BEGIN
+        INSERT INTO entries_fts (rowid, prompt, selection, response)
+        VALUES (new.id, new.prompt, new.selection, new.response);
+    END;
+    CREATE TRIGGER entries_fts_delete AFTER DELETE ON entries BEGIN
+        INSERT INTO entries_fts (entries_fts, rowid, prompt, selection, response)
+        VALUES ('delete', old.id, old.prompt, old.selection, old.response);
+    END;
+    CREATE TRIGGER entries_fts_update AFTER UPDATE OF prompt, selection, response
+    ON entries BEGIN
+        INSERT INTO entries_fts (entries_fts, rowid, prompt, selection, response)
+        VALUES ('delete', old.id, old.prompt, old.selection, old.response);
+        INSERT INTO entries_fts (rowid, prompt, selection, response)
+        VALUES (new.id, new.prompt, new.selection, new.response);
+    END;
+    INSERT INTO entries_fts (entries_fts) VALUES ('rebuild');",
+];
+
+static HISTORY: OnceLock<Result<Mutex<History>, String>> = OnceLock::new();
+
+/// Retention policy, saved with the other app-wide settings.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct HistorySettings {
+    pub enabled: bool,
+    /// Entries older than this are deleted. `None` keeps them forever.
+    pub max_age_days: Option<u32>,
+    /// Only the newest entries are kept. `None` keeps all of them.
+    pub max_entries: Option<u32>,
+}
+
+impl Default for HistorySettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_age_days: Some(90),
+            max_entries: Some(1000),
+        }
+    }
+}
+
+/// What the user asked, as opposed to the assembled prompt sent to the
+/// agent.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChatContext {
+    pub prompt: String,
+    #[serde(default)]
+    pub selection: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EntryStatus {
+    /// Still streaming, or the app quit before the answer finished.
+    Pending,
+    Done,
+    Error,
+}
+
+impl EntryStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            EntryStatus::Pending => "pending",
+            EntryStatus::Done => "done",
+            EntryStatus::Error => "error",
+        }
+    }
+
+    fn parse(value: &str) -> Self {
+        match value {
+            "done" => EntryStatus::Done,
+            "error" => EntryStatus::Error,
+            _ => EntryStatus::Pending,
+        }
+    }
+}
+
+/// One exchange. Timestamps are Unix milliseconds.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryEntry {
+    pub id: i64,
+    pub prompt: String,
+    pub selection: Option<String>,
+    pub agent_id: String,
+    pub session_key: String,
+    pub created_at: i64,
+    pub completed_at: Option<i64>,
+    pub response: String,
+    pub status: EntryStatus,
+    pub error: Option<String>,
+}
+
+const ENTRY_COLUMNS: &str = "id, prompt, selection, agent_id, session_key, created_at, \
+     completed_at, response, status, error";
+
+impl HistoryEntry {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.get(0)?,
+            prompt: row.get(1)?,
+            selection: row.get(2)?,
+            agent_id: row.get(3)?,
+            session_key: row.get(4)?,
+            created_at: row.get(5)?,
+            completed_at: row.get(6)?,
+            response: row.get(7)?,
+            status: EntryStatus::parse(&row.get::<_, String>(8)?),
+            error: row.get(9)?,
+        })
+    }
+}
+
+/// A full-text search result.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHit {
+    pub entry: HistoryEntry,
+    /// BM25 relevance; higher is better.
+    pub score: f64,
+    /// Excerpt of the best matching field, matches wrapped in
+    /// [`MATCH_START`]/[`MATCH_END`].
+    pub snippet: String,
+}
+
+/// FTS5 query matching entries that contain every word of `query`, each as
+/// a prefix. `None` when the query has no words.
+fn match_expression(query: &str) -> Option<String> {
+    // Same word boundaries as the `unicode61` tokenizer, so no token needs
+    // quoting beyond the surrounding quotes.
+    let terms: Vec<String> = query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| format!("\"{word}\"*"))
+        .collect();
+    (!terms.is_empty()).then(|| terms.join(" "))
+}
+
+fn db_error(error: rusqlite::Error) -> String {
+    format!("History error: {error}")
+}
+
+pub struct HistoryStore {
+    conn: Connection,
+}
+
+impl HistoryStore {
+    /// Open (and create or upgrade) the database at `path`. The file is
+    /// owner-only since prompts and selections may hold secrets.
+    pub fn open(path: &Path) -> Result<Self, String> {
+        config_file::create_private(path)?;
+        Self::init(Connection::open(path).map_err(db_error)?)
+    }
+
+    pub fn open_in_memory() -> Result<Self, String> {
+        Self::init(Connection::open_in_memory().map_err(db_error)?)
+    }
+
+    fn init(conn: Connection) -> Result<Self, String> {
+        let version: usize = conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .map_err(db_error)?;
+        if version > MIGRATIONS.len() {
+            return Err(format!(
+                "History database version {version} is newer than this build"
+            ));
+        }
+        for (index, sql) in MIGRATIONS.iter().enumerate().skip(version) {
+            conn.execute_batch(&format!(
+                "BEGIN; {sql}; PRAGMA user_version = {}; COMMIT;",
+                index + 1
+            ))
+            .map_err(db_error)?;
+        }
+        Ok(Self { conn })
+    }
+
+    pub fn insert(
+        &self,
+        context: &ChatContext,
+        agent_id: &str,
+        session_key: &str,
+        now: i64,
+    ) -> Result<i64, String> {
+        self.conn
+            .execute(
+                "INSERT INTO entries (prompt, selection, agent_id, session_key, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    context.prompt,
+                    context.selection,
+                    agent_id,
+                    session_key,
+                    now
+                ],
+            )
+            .map_err(db_error)?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    pub fn complete(
+        &self,
+        id: i64,
+        status: EntryStatus,
+        response: &str,
+        error: Option<&str>,
+        now: i64,
+    ) -> Result<(), String> {
+        self.conn
+            .execute(
+                "UPDATE entries SET status = ?2, response = ?3, error = ?4, completed_at = ?5
+                 WHERE id = ?1",
+                params![id, status.as_str(), response, error, now],
+            )
+            .map_err(db_error)?;
+        Ok(())
+    }
+
+    pub fn get(&self, id: i64) -> Result<Option<HistoryEntry>, String> {
+        self.conn
+            .query_row(
+                &format!("SELECT {ENTRY_COLUMNS} FROM entries WHERE id = ?1"),
+                [id],
+                HistoryEntry::from_row,
+            )
+            .optional()
+            .map_err(db_error)
+    }
+
+    /// Newest first. `before` pages backwards from an entry id.
+    pub fn list(&self, limit: u32, before: Option<i64>) -> Result<Vec<HistoryEntry>, String> {
+        let mut statement = self
+            .conn
+            .prepare(&format!(
+                "SELECT {ENTRY_COLUMNS} FROM entries WHERE id < ?1 ORDER BY id DESC LIMIT ?2"
+            ))
+            .map_err(db_error)?;
+        let rows = statement
+            .query_map(
+                params![before.unwrap_or(i64::MAX), limit],
+                HistoryEntry::from_row,
+            )
+            .map_err(db_error)?;
+        rows.collect::<Result<_, _>>().map_err(db_error)
+    }
+
+    /// Entries containing all words of `query` in the prompt, selection or
+    /// answer, best match first. Prompt matches weigh double.
+    pub fn search(&self, query: &str, limit: u32) -> Result<Vec<SearchHit>, String> {
+        let Some(expression) = match_expression(query) else {
+            return Ok(Vec::new());
+        };
+        let mut statement = self
+            .conn
+            .prepare(&format!(
+                "SELECT {ENTRY_COLUMNS}, hits.score, hits.snippet FROM (
+                     SELECT rowid AS hit_id,
+                         -bm25(entries_fts, 2.0, 1.0, 1.0) AS score,
+                         snippet(entries_fts, -1, ?2, ?3, '…', ?4) AS snippet
+                     FROM entries_fts WHERE entries_fts MATCH ?1
+                 ) AS hits
+                 JOIN entries ON entries.id = hits.hit_id
+                 ORDER BY hits.score DESC, entries.id DESC LIMIT ?5"
+            ))
+            .map_err(db_error)?;
+        let rows = statement
+            .query_map(
+                params![expression, MATCH_START, MATCH_END, SNIPPET_TOKENS, limit],
+                |row| {
+                    Ok(SearchHit {
+                        entry: HistoryEntry::from_row(row)?,
+                        score: row.get(10)?,
+                        snippet: row.get(11)?,
+                    })
+                },
+            )
+            .map_err(db_error)?;
+        rows.collect::<Result<_, _>>().map_err(db_error)
+    }
+
+    pub fn delete(&self, id: i64) -> Result<bool, String> {
+        let deleted = self
+            .conn
+            .execute("DELETE FROM entries WHERE id = ?1", [id])
+            .map_err(db_error)?;
+        Ok(deleted > 0)
+    }
+
+    pub fn clear(&self) -> Result<(), String> {
+        self.conn
+            .execute("DELETE FROM entries", [])
+            .map_err(db_error)?;
+        Ok(())
+    }
+
+    /// Apply the retention policy. Returns the number of deleted entries.
+    pub fn prune(&self, settings: &HistorySettings, now: i64) -> Result<usize, String> {
+        let mut deleted = 0;
+        if let Some(days) = settings.max_age_days {
+            deleted += self
+                .conn
+                .execute(
+                    "DELETE FROM entries WHERE created_at < ?1",
+                    [now - i64::from(days) * DAY_MS],
+                )
+                .map_err(db_error)?;
+        }
+        if let Some(max) = settings.max_entries {
+            deleted += self
+                .conn
+                .execute(
+                    "DELETE FROM entries WHERE id NOT IN
+                     (SELECT id FROM entries ORDER BY id DESC LIMIT ?1)",
+                    [max],
+                )
+                .map_err(db_error)?;
+        }
+        Ok(deleted)
+    }
+}
+
+/// Follows the exchanges in flight through the gateway events and writes
+/// them to the store, keyed by the id of the job answering each prompt.
+#[derive(Default)]
+pub struct Recorder {
+    exchanges: HashMap<u64, Exchange>,
+}
+
+struct Exchange {
+    entry: i64,
+    text: String,
+}
+
+impl Recorder {
+    pub fn begin(
+        &mut self,
+        store: &HistoryStore,
+        job: u64,
+        context: &ChatContext,
+        settings: &Credentials,
+        now: i64,
+    ) -> Result<(), String> {
+        let entry = store.insert(context, &settings.agent_id, &settings.session_key, now)?;
+        self.exchanges.insert(
+            job,
+            Exchange {
+                entry,
+                text: String::new(),
+            },
+        );
+        Ok(())
+    }
+
+    pub fn on_event(
+        &mut self,
+        store: &HistoryStore,
+        job: u64,
+        event: &BotEvent,
+        now: i64,
+    ) -> Result<(), String> {
+        let Some(exchange) = self.exchanges.get_mut(&job) else {
+            return Ok(());
+        };
+        match event {
+            BotEvent::AssistantDelta { text } | BotEvent::Assistant { text } => {
+                exchange.text.clone_from(text)
+            }
+            BotEvent::AssistantDone => {
+                store.complete(exchange.entry, EntryStatus::Done, &exchange.text, None, now)?;
+                self.exchanges.remove(&job);
+            }
+            BotEvent::Error { text } => {
+                store.complete(
+                    exchange.entry,
+                    EntryStatus::Error,
+                    &exchange.text,
+                    Some(text),
+                    now,
+                )?;
+                self.exchanges.remove(&job);
+            }
+            BotEvent::Aborted => {
+                store.complete(
+                    exchange.entry,
+                    EntryStatus::Error,
+                    &exchange.text,
+                    Some("Stopped"),
+                    now,
+                )?;
+                self.exchanges.remove(&job);
+            }
+        }
+        Ok(())
+    }
+}
+
+struct History {
+    store: HistoryStore,
+    recorder: Recorder,
+}
+
+fn history_path() -> PathBuf {
+    credentials::config_dir().join(HISTORY_FILE)
+}
+
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+fn retention() -> HistorySettings {
+    credentials::load_credentials()
+        .map(|creds| creds.history)
+        .unwrap_or_default()
+}
+
+fn history() -> Result<MutexGuard<'static, History>, String> {
+    let history = HISTORY.get_or_init(|| {
+        let store = HistoryStore::open(&history_path())?;
+        if let Err(error) = store.prune(&retention(), now_ms()) {
+            eprintln!("history prune failed: {error}");
+        }
+        Ok(Mutex::new(History {
+            store,
+            recorder: Recorder::default(),
+        }))
+    });
+    history
+        .as_ref()
+        .map_err(Clone::clone)?
+        .lock()
+        .map_err(|e| format!("Lock error: {e}"))
+}
+
+/// Record a prompt sent with `settings` and answered by `job`, unless
+/// history is turned off.
+pub fn record_prompt(job: u64, context: &ChatContext, settings: &Credentials) {
+    let retention = retention();
+    if !retention.enabled {
+        return;
+    }
+    let result = history().and_then(|mut history| {
+        let History { store, recorder } = &mut *history;
+        store.prune(&retention, now_ms())?;
+        recorder.begin(store, job, context, settings, now_ms())
+    });
+    if let Err(error) = result {
+        eprintln!("history record failed: {error}");
+    }
+}
+
+/// Feed a gateway event to the exchange recorded for `job`, if any.
+pub fn record_event(job: u64, event: &BotEvent) {
+    let Some(Ok(history)) = HISTORY.get() else {
+        return;
+    };
+    let mut history = history.lock().unwrap_or_else(|e| e.into_inner());
+    let History { store, recorder } = &mut *history;
+    if let Err(error) = recorder.on_event(store, job, event, now_ms()) {
+        eprintln!("history record failed: {error}");
+    }
+}
+
+#[tauri::command]
+pub fn history_list(limit: Option<u32>, before: Option<i64>) -> Result<Vec<HistoryEntry>, String> {
+    history()?
+        .store
+        .list(limit.unwrap_or(DEFAULT_LIST_LIMIT), before)
+}
+
+#[tauri::command]
+pub fn history_search(query: String, limit: Option<u32>) -> Result<Vec<SearchHit>, String> {
+    history()?
+        .store
+        .search(&query, limit.unwrap_or(DEFAULT_LIST_LIMIT))
+}
+
+#[tauri::command]
+pub fn history_get(id: i64) -> Result<Option<HistoryEntry>, String> {
+    history()?.store.get(id)
+}
+
+#[tauri::command]
+pub fn history_delete(id: i64) -> Result<bool, String> {
+    history()?.store.delete(id)
+}
+
+#[tauri::command]
+pub fn history_clear() -> Result<(), String> {
+    history()?.store.clear()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context(prompt: &str, selection: Option<&str>) -> ChatContext {
+        ChatContext {
+            prompt: prompt.into(),
+            selection: selection.map(str::to_string),
+        }
+    }
+
+    fn settings() -> Credentials {
+        Credentials {
+            agent_id: "ops".into(),
+            session_key: "main".into(),
+            ..Credentials::default()
+        }
+    }
+
+    fn delta(text: &str) -> BotEvent {
+        BotEvent::AssistantDelta { text: text.into() }
+    }
+
+    #[test]
+    fn records_exchange_from_gateway_events() {
+        let store = HistoryStore::open_in_memory().unwrap();
+        let mut recorder = Recorder::default();
+
+        recorder
+            .begin(
+                &store,
+                1,
+                &context("explain", Some("ls -la")),
+                &settings(),
+                1_000,
+            )
+            .unwrap();
+        for event in [
+            delta("Lists"),
+            delta("Lists files"),
+            BotEvent::Assistant {
+                text: "Lists files, long format.".into(),
+            },
+            BotEvent::AssistantDone,
+        ] {
+            recorder.on_event(&store, 1, &event, 2_000).unwrap();
+        }
+
+        let entries = store.list(10, None).unwrap();
+        assert_eq!(entries.len(), 1);
+        let entry = &entries[0];
+        assert_eq!(entry.prompt, "explain");
+        assert_eq!(entry.selection.as_deref(), Some("ls -la"));
+        assert_eq!(entry.agent_id, "ops");
+        assert_eq!(entry.session_key, "main");
+        assert_eq!((entry.created_at, entry.completed_at), (1_000, Some(2_000)));
+        assert_eq!(entry.response, "Lists files, long format.");
+        assert_eq!(entry.status, EntryStatus::Done);
+        assert_eq!(store.get(entry.id).unwrap().as_ref(), Some(entry));
+    }
+
+    #[test]
+    fn concurrent_prompts_keep_their_own_answers() {
+        let store = HistoryStore::open_in_memory().unwrap();
+        let mut recorder = Recorder::default();
+
+        recorder
+            .begin(&store, 1, &context("first", None), &settings(), 1)
+            .unwrap();
+        recorder
+            .begin(&store, 2, &context("second", None), &settings(), 2)
+            .unwrap();
+        recorder.on_event(&store, 1, &delta("Half"), 3).unwrap();
+        recorder
+            .on_event(
+                &store,
+                1,
+                &BotEvent::Error {
+                    text: "Agent error.".into(),
+                },
+                4,
+            )
+            .unwrap();
+        let answer = BotEvent::Assistant {
+            text: "Second.".into(),
+        };
+        recorder.on_event(&store, 2, &answer, 5).unwrap();
+        recorder
+            .on_event(&store, 2, &BotEvent::AssistantDone, 5)
+            .unwrap();
+        // Events for unknown or finished jobs are ignored.
+        recorder.on_event(&store, 1, &delta("late"), 6).unwrap();
+
+        let entries = store.list(10, None).unwrap();
+        assert_eq!(entries[1].prompt, "first");
+        assert_eq!(entries[1].response, "Half");
+        assert_eq!(entries[1].status, EntryStatus::Error);
+        assert_eq!(entries[1].error.as_deref(), Some("Agent error."));
+        assert_eq!(entries[0].prompt, "second");
+        assert_eq!(entries[0].response, "Second.");
+        assert_eq!(entries[0].status, EntryStatus::Done);
+    }
+
+    #[test]
+    fn lists_pages_and_deletes() {
+        let store = HistoryStore::open_in_memory().unwrap();
+        let ids: Vec<i64> = ["grep 100%", "find files", "tar flags"]
+            .iter()
+            .map(|prompt| store.insert(&context(prompt, None), "", "main", 0).unwrap())
+            .collect();
+
+        let page: Vec<i64> = store
+            .list(2, Some(ids[2]))
+            .unwrap()
+            .iter()
+            .map(|entry| entry.id)
+            .collect();
+        assert_eq!(page, [ids[1], ids[0]]);
+
+        assert!(store.delete(ids[1]).unwrap());
+        assert!(!store.delete(ids[1]).unwrap());
+        assert_eq!(store.get(ids[1]).unwrap(), None);
+        store.clear().unwrap();
+        assert!(store.list(10, None).unwrap().is_empty());
+    }
+
+    fn answered(store: &HistoryStore, prompt: &str, response: &str) -> i64 {
+        let id = store.insert(&context(prompt, None), "", "main", 0).unwrap();
+        store
+            .complete(id, EntryStatus::Done, response, None, 1)
+            .unwrap();
+        id
+    }
+
+    fn hit_ids(hits: &[SearchHit]) -> Vec<i64> {
+        hits.iter().map(|hit| hit.entry.id).collect()
+    }
+
+    #[test]
+    fn search_ranks_prompt_matches_first() {
+        let store = HistoryStore::open_in_memory().unwrap();
+        let in_answer = answered(&store, "pack logs", "Use `tar -czf logs.tgz logs/`.");
+        let in_prompt = answered(&store, "tar flags to extract", "`tar -xzf file`");
+        answered(&store, "disk usage", "Run `du -sh *`.");
+
+        let hits = store.search("TAR", 10).unwrap();
+
+        assert_eq!(hit_ids(&hits), [in_prompt, in_answer]);
+        assert!(hits[0].score > hits[1].score);
+        assert!(hits[1]
+            .snippet
+            .contains(&format!("{MATCH_START}tar{MATCH_END} -czf")));
+    }
+
+    #[test]
+    fn search_matches_word_prefixes_across_fields() {
+        let store = HistoryStore::open_in_memory().unwrap();
+        let id = store
+            .insert(&context("résumé", Some("Café menu")), "", "main", 0)
+            .unwrap();
+        store
+            .complete(id, EntryStatus::Done, "Translated the menu.", None, 1)
+            .unwrap();
+
+        assert_eq!(hit_ids(&store.search("cafe transl", 10).unwrap()), [id]);
+        assert_eq!(hit_ids(&store.search("resume", 10).unwrap()), [id]);
+        assert!(store.search("menu pizza", 10).unwrap().is_empty());
+        assert!(store.search("\"*:( ", 10).unwrap().is_empty());
+
+        store.delete(id).unwrap();
+        assert!(store.search("menu", 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn upgrade_indexes_existing_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(HISTORY_FILE);
+        let conn = Connection::open(&path).unwrap();
+        conn.execute_batch(MIGRATIONS[0]).unwrap();
+        conn.execute_batch(
+            "PRAGMA user_version = 1;
+             INSERT INTO entries (prompt, agent_id, session_key, created_at, response)
+             VALUES ('rotate keys', '', 'main', 0, 'Use ssh-keygen.');",
+        )
+        .unwrap();
+        drop(conn);
+
+        let store = HistoryStore::open(&path).unwrap();
+
+        assert_eq!(store.search("keygen", 10).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn prune_applies_age_and_count_limits() {
+        let store = HistoryStore::open_in_memory().unwrap();
+        for day in 0..5 {
+            store
+                .insert(&context("q", None), "", "main", day * DAY_MS)
+                .unwrap();
+        }
+        let now = 10 * DAY_MS;
+
+        let deleted = store
+            .prune(
+                &HistorySettings {
+                    max_age_days: Some(7),
+                    max_entries: Some(1),
+                    ..HistorySettings::default()
+                },
+                now,
+            )
+            .unwrap();
+
+        assert_eq!(deleted, 4);
+        let left = store.list(10, None).unwrap();
+        assert_eq!(left.len(), 1);
+        assert_eq!(left[0].created_at, 4 * DAY_MS);
+    }
+
+    #[test]
+    fn reopening_keeps_entries_and_schema() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(HISTORY_FILE);
+        HistoryStore::open(&path)
+            .unwrap()
+            .insert(&context("kept", None), "", "main", 0)
+            .unwrap();
+
+        let store = HistoryStore::open(&path).unwrap();
+
+        assert_eq!(store.list(10, None).unwrap()[0].prompt, "kept");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+}
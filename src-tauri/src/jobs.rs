@@ -0,0 +1,522 @@
+//! Prompts sent to the gateway, so several can stream at once and the notch
+//! can show each of them. Each job keeps the id of its `chat.send` request;
+//! the response to it names the run, which every later event of that run
+//! carries. Events of runs no job started (other clients on the same
+//! gateway) are dropped.
+
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::gateway::{BotEvent, ChatRun};
+
+/// Event sent to every webview with the [`JobSummary`] list.
+pub const JOBS_EVENT: &str = "jobs-changed";
+
+const LABEL_CHARS: usize = 60;
+const PREVIEW_CHARS: usize = 80;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    Streaming,
+    /// The agent is running a tool; text resumes afterwards.
+    ToolRunning,
+    Done,
+    Error,
+    Aborted,
+}
+
+impl JobState {
+    /// Still waiting for the answer.
+    pub fn is_active(self) -> bool {
+        matches!(self, Self::Streaming | Self::ToolRunning)
+    }
+}
+
+/// A prompt as listed in the notch.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobSummary {
+    pub id: u64,
+    pub label: String,
+    pub state: JobState,
+    /// Milliseconds since the Unix epoch.
+    pub started_at: u64,
+    /// Characters of response received so far.
+    pub text_len: usize,
+    /// Tokens used, when reported.
+    pub tokens: Option<u64>,
+    /// Milliseconds from the prompt until it finished, or until now.
+    pub elapsed_ms: u64,
+    /// First line of the response once it finished.
+    pub preview: Option<String>,
+}
+
+/// A prompt with its response, handed to the panel when it is restored.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobSnapshot {
+    #[serde(flatten)]
+    pub summary: JobSummary,
+    pub text: String,
+}
+
+#[derive(Debug)]
+struct Job {
+    id: u64,
+    label: String,
+    state: JobState,
+    started_at: u64,
+    finished_at: Option<u64>,
+    tokens: Option<u64>,
+    text: String,
+    /// Id of the `chat.send` request, once sent.
+    request: Option<String>,
+    /// Run the gateway started for the request.
+    run: Option<String>,
+}
+
+impl Job {
+    fn answers(&self, run: &ChatRun) -> bool {
+        match run {
+            ChatRun::Request(request) => self.request.as_ref() == Some(request),
+            ChatRun::Run(run) => self.run.as_ref() == Some(run),
+        }
+    }
+
+    fn summary(&self) -> JobSummary {
+        let preview = (!self.state.is_active()).then(|| {
+            let line = self.text.lines().find(|line| !line.trim().is_empty());
+            truncate(line.unwrap_or_default().trim(), PREVIEW_CHARS)
+        });
+        JobSummary {
+            id: self.id,
+            label: self.label.clone(),
+            state: self.state,
+            started_at: self.started_at,
+            text_len: self.text.chars().count(),
+            tokens: self.tokens,
+            elapsed_ms: self
+                .finished_at
+                .unwrap_or_else(now_ms)
+                .saturating_sub(self.started_at),
+            preview,
+        }
+    }
+}
+
+/// In-flight prompts plus finished ones nobody has looked at yet.
+#[derive(Debug, Default)]
+pub struct JobRegistry {
+    jobs: Vec<Job>,
+    next_id: u64,
+    /// The job the panel shows; only its events reach the panel.
+    shown: Option<u64>,
+    /// Jobs whose state changed since [`JobRegistry::take_changed`].
+    changed: Vec<u64>,
+    /// Bumped when a job starts, changes state or is shown.
+    revision: u64,
+}
+
+impl JobRegistry {
+    pub const fn new() -> Self {
+        Self {
+            jobs: Vec::new(),
+            next_id: 1,
+            shown: None,
+            changed: Vec::new(),
+            revision: 0,
+        }
+    }
+
+    /// Track a prompt the panel just sent, labelled by its first line.
+    /// Finished jobs are dropped: the user moved on to a new prompt.
+    pub fn start(&mut self, prompt: &str) -> u64 {
+        self.jobs.retain(|job| job.state.is_active());
+        let id = self.next_id;
+        self.next_id += 1;
+        let line = prompt.lines().find(|line| !line.trim().is_empty());
+        self.jobs.push(Job {
+            id,
+            label: truncate(line.unwrap_or("Prompt").trim(), LABEL_CHARS),
+            state: JobState::Streaming,
+            started_at: now_ms(),
+            finished_at: None,
+            tokens: None,
+            text: String::new(),
+            request: None,
+            run: None,
+        });
+        self.shown = Some(id);
+        self.changed.push(id);
+        self.revision += 1;
+        id
+    }
+
+    /// Job `id` went out as the `chat.send` request `request`.
+    pub fn sent(&mut self, id: u64, request: String) {
+        if let Some(job) = self.job_mut(id) {
+            job.request = Some(request);
+        }
+    }
+
+    /// The gateway started `run` for `request`. Returns the job that sent
+    /// it, if any.
+    pub fn run_started(&mut self, request: &str, run: &str) -> Option<u64> {
+        let job = self
+            .jobs
+            .iter_mut()
+            .find(|job| job.request.as_deref() == Some(request))?;
+        job.run = Some(run.to_string());
+        Some(job.id)
+    }
+
+    /// Apply a chat event to the running job it answers. Returns that job's
+    /// id, or `None` when no job is waiting for it.
+    pub fn record(&mut self, run: &ChatRun, event: &BotEvent) -> Option<u64> {
+        let id = self.running(run)?;
+        match event {
+            BotEvent::AssistantDelta { text } => {
+                self.job_mut(id)?.text = text.clone();
+                self.set_state(id, JobState::Streaming);
+            }
+            // The final text, with media links; `AssistantDone` follows.
+            BotEvent::Assistant { text } => self.job_mut(id)?.text = text.clone(),
+            BotEvent::AssistantDone => self.set_state(id, JobState::Done),
+            // Keep what arrived before the abort.
+            BotEvent::Aborted => self.set_state(id, JobState::Aborted),
+            BotEvent::Error { text } => {
+                self.job_mut(id)?.text = text.clone();
+                self.set_state(id, JobState::Error);
+            }
+        }
+        Some(id)
+    }
+
+    /// Move the running job of `run` into `state` and note the tokens it
+    /// used. For what the agent reports besides the answer text: running
+    /// tools and token usage. Returns the job's id, or `None` if there is no
+    /// such job.
+    pub fn update(&mut self, run: &ChatRun, state: JobState, tokens: Option<u64>) -> Option<u64> {
+        let id = self.running(run)?;
+        let job = self.job_mut(id)?;
+        if tokens.is_some() && job.tokens != tokens {
+            job.tokens = tokens;
+            self.changed.push(id);
+        }
+        self.set_state(id, state);
+        Some(id)
+    }
+
+    /// The connection dropped; nothing running will get an answer.
+    /// Returns the jobs that were running.
+    pub fn fail_running(&mut self, reason: &str) -> Vec<u64> {
+        let running: Vec<u64> = self
+            .jobs
+            .iter()
+            .filter(|job| job.state.is_active())
+            .map(|job| job.id)
+            .collect();
+        for &id in &running {
+            self.fail(id, reason);
+        }
+        running
+    }
+
+    /// Job `id` will get no answer, e.g. because its prompt was never sent.
+    /// Text that already arrived is kept.
+    pub fn fail(&mut self, id: u64, reason: &str) {
+        if let Some(job) = self.job_mut(id) {
+            if job.text.is_empty() {
+                job.text = reason.to_string();
+            }
+        }
+        self.set_state(id, JobState::Error);
+    }
+
+    pub fn shown(&self) -> Option<u64> {
+        self.shown
+    }
+
+    /// Hand job `id` to the panel, or the one it showed, or the newest.
+    /// Other finished jobs are dropped: the notch showed them already.
+    pub fn show(&mut self, id: Option<u64>) -> Option<JobSnapshot> {
+        let id = id
+            .filter(|id| self.jobs.iter().any(|job| job.id == *id))
+            .or(self
+                .shown
+                .filter(|id| self.jobs.iter().any(|job| job.id == *id)))
+            .or(self.jobs.last().map(|job| job.id))?;
+        self.jobs
+            .retain(|job| job.id == id || job.state.is_active());
+        self.shown = Some(id);
+        self.revision += 1;
+        let job = self.job_mut(id)?;
+        Some(JobSnapshot {
+            summary: job.summary(),
+            text: job.text.clone(),
+        })
+    }
+
+    pub fn summaries(&self) -> Vec<JobSummary> {
+        self.jobs.iter().map(Job::summary).collect()
+    }
+
+    /// Jobs whose state or token count changed since the last call.
+    pub fn take_changed(&mut self) -> Vec<JobSummary> {
+        let changed = std::mem::take(&mut self.changed);
+        self.jobs
+            .iter()
+            .filter(|job| changed.contains(&job.id))
+            .map(Job::summary)
+            .collect()
+    }
+
+    /// Changes when a job starts, changes state or is shown, so a delayed
+    /// action can tell whether anything happened in between.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    fn running(&self, run: &ChatRun) -> Option<u64> {
+        let job = self
+            .jobs
+            .iter()
+            .find(|job| job.state.is_active() && job.answers(run))?;
+        Some(job.id)
+    }
+
+    fn set_state(&mut self, id: u64, state: JobState) {
+        let Some(job) = self.job_mut(id) else {
+            return;
+        };
+        if job.state == state {
+            return;
+        }
+        job.state = state;
+        job.finished_at = (!state.is_active()).then(now_ms);
+        self.changed.push(id);
+        self.revision += 1;
+    }
+
+    fn job_mut(&mut self, id: u64) -> Option<&mut Job> {
+        self.jobs.iter_mut().find(|job| job.id == id)
+    }
+}
+
+fn truncate(text: &str, max: usize) -> String {
+    match text.char_indices().nth(max) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn delta(text: &str) -> BotEvent {
+        BotEvent::AssistantDelta { text: text.into() }
+    }
+
+    fn run(id: &str) -> ChatRun {
+        ChatRun::Run(id.into())
+    }
+
+    /// Start a job for `prompt` that went out as request `id` and started
+    /// run `run-<id>`.
+    fn send(jobs: &mut JobRegistry, prompt: &str, id: &str) -> u64 {
+        let job = jobs.start(prompt);
+        jobs.sent(job, id.into());
+        assert_eq!(jobs.run_started(id, &format!("run-{id}")), Some(job));
+        job
+    }
+
+    fn states(jobs: &JobRegistry) -> Vec<(u64, JobState)> {
+        jobs.summaries()
+            .iter()
+            .map(|job| (job.id, job.state))
+            .collect()
+    }
+
+    #[test]
+    fn interleaved_runs_keep_their_own_answers() {
+        let mut jobs = JobRegistry::new();
+        let first = send(&mut jobs, "Summarize\nthe rest", "1");
+        let second = send(&mut jobs, "Translate", "2");
+        assert_eq!(jobs.shown(), Some(second));
+
+        assert_eq!(jobs.record(&run("run-2"), &delta("Trad")), Some(second));
+        assert_eq!(jobs.record(&run("run-1"), &delta("Sum")), Some(first));
+        assert_eq!(
+            jobs.record(&run("run-2"), &delta("Traduction")),
+            Some(second)
+        );
+        let text = BotEvent::Assistant {
+            text: "Summary.".into(),
+        };
+        assert_eq!(jobs.record(&run("run-1"), &text), Some(first));
+        assert_eq!(
+            jobs.record(&run("run-1"), &BotEvent::AssistantDone),
+            Some(first)
+        );
+
+        let summaries = jobs.summaries();
+        assert_eq!(summaries[0].label, "Summarize");
+        assert_eq!(summaries[0].preview.as_deref(), Some("Summary."));
+        assert_eq!(summaries[1].text_len, 10);
+        assert_eq!(summaries[1].preview, None);
+
+        // Finished runs and runs of other clients are not ours.
+        assert_eq!(jobs.record(&run("run-1"), &delta("late")), None);
+        assert_eq!(jobs.record(&run("run-9"), &delta("other")), None);
+        assert_eq!(
+            jobs.record(&ChatRun::Request("7".into()), &delta("x")),
+            None
+        );
+        assert_eq!(
+            states(&jobs),
+            [(first, JobState::Done), (second, JobState::Streaming)]
+        );
+    }
+
+    #[test]
+    fn rejected_request_fails_only_its_own_job() {
+        let mut jobs = JobRegistry::new();
+        let first = send(&mut jobs, "One", "1");
+        let second = jobs.start("Two");
+        jobs.sent(second, "2".into());
+        let third = send(&mut jobs, "Three", "3");
+
+        let error = BotEvent::Error {
+            text: "Session busy.".into(),
+        };
+        assert_eq!(
+            jobs.record(&ChatRun::Request("2".into()), &error),
+            Some(second)
+        );
+        // A session-wide abort ends every run, each with its own event.
+        assert_eq!(jobs.record(&run("run-3"), &BotEvent::Aborted), Some(third));
+        assert_eq!(
+            jobs.record(&run("run-1"), &delta("Still here")),
+            Some(first)
+        );
+        assert_eq!(
+            states(&jobs),
+            [
+                (first, JobState::Streaming),
+                (second, JobState::Error),
+                (third, JobState::Aborted)
+            ]
+        );
+        assert_eq!(
+            jobs.summaries()[1].preview.as_deref(),
+            Some("Session busy.")
+        );
+        assert_eq!(jobs.run_started("9", "run-9"), None);
+    }
+
+    #[test]
+    fn showing_a_job_drops_the_other_finished_ones() {
+        let mut jobs = JobRegistry::new();
+        let first = send(&mut jobs, "One", "1");
+        let second = send(&mut jobs, "Two", "2");
+        let third = send(&mut jobs, "Three", "3");
+        for (id, text) in [("run-1", "1"), ("run-2", "2")] {
+            jobs.record(&run(id), &BotEvent::Assistant { text: text.into() });
+            jobs.record(&run(id), &BotEvent::AssistantDone);
+        }
+
+        let snapshot = jobs.show(Some(first)).unwrap();
+        assert_eq!(snapshot.text, "1");
+        assert_eq!(snapshot.summary.state, JobState::Done);
+        assert_eq!(jobs.shown(), Some(first));
+        assert_eq!(
+            states(&jobs),
+            [(first, JobState::Done), (third, JobState::Streaming)]
+        );
+
+        // Unknown ids fall back to the job the panel showed.
+        assert_eq!(jobs.show(Some(second)).unwrap().summary.id, first);
+        // A new prompt clears what was finished.
+        let fourth = jobs.start("Four");
+        assert_eq!(
+            states(&jobs),
+            [(third, JobState::Streaming), (fourth, JobState::Streaming)]
+        );
+    }
+
+    #[test]
+    fn lost_connection_fails_what_is_running() {
+        let mut jobs = JobRegistry::new();
+        let first = send(&mut jobs, "One", "1");
+        jobs.record(&run("run-1"), &delta("Half an answer"));
+        let second = jobs.start("Two");
+        let third = jobs.start("Three");
+        jobs.fail(third, "Not connected");
+
+        assert_eq!(jobs.fail_running("Connection lost"), [first, second]);
+        let summaries = jobs.summaries();
+        assert_eq!(summaries[0].preview.as_deref(), Some("Half an answer"));
+        assert_eq!(summaries[1].preview.as_deref(), Some("Connection lost"));
+        assert_eq!(summaries[2].preview.as_deref(), Some("Not connected"));
+        assert!(jobs.fail_running("Connection lost").is_empty());
+        assert_eq!(JobRegistry::new().show(None), None);
+    }
+
+    #[test]
+    fn states_outside_the_chat_stream() {
+        let mut jobs = JobRegistry::new();
+        let first = send(&mut jobs, "One", "1");
+        let second = send(&mut jobs, "Two", "2");
+        let changed: Vec<u64> = jobs.take_changed().iter().map(|job| job.id).collect();
+        assert_eq!(changed, [first, second]);
+
+        assert_eq!(
+            jobs.update(&run("run-1"), JobState::ToolRunning, None),
+            Some(first)
+        );
+        jobs.record(&run("run-1"), &delta("Found it"));
+        let revision = jobs.revision();
+        jobs.update(&run("run-1"), JobState::Streaming, Some(42));
+        assert_eq!(jobs.revision(), revision);
+        let changed = jobs.take_changed();
+        assert_eq!(changed.len(), 1);
+        assert_eq!(changed[0].state, JobState::Streaming);
+        assert_eq!(changed[0].tokens, Some(42));
+        assert_eq!(jobs.update(&run("run-9"), JobState::Done, None), None);
+
+        // An aborted answer keeps what arrived before the abort.
+        jobs.record(&run("run-1"), &BotEvent::Aborted);
+        jobs.record(
+            &run("run-2"),
+            &BotEvent::Error {
+                text: "Agent error.".into(),
+            },
+        );
+        assert_eq!(
+            states(&jobs),
+            [(first, JobState::Aborted), (second, JobState::Error)]
+        );
+        assert_eq!(jobs.summaries()[0].preview.as_deref(), Some("Found it"));
+        assert!(jobs.revision() > revision);
+    }
+
+    #[test]
+    fn long_labels_are_cut() {
+        let mut jobs = JobRegistry::new();
+        jobs.start(&"x".repeat(100));
+        jobs.start("  \n");
+        let summaries = jobs.summaries();
+        assert_eq!(summaries[0].label.chars().count(), LABEL_CHARS + 1);
+        assert!(summaries[0].label.ends_with('…'));
+        assert_eq!(summaries[1].label, "Prompt");
+    }
+}
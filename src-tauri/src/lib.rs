@@ -0,0 +1,253 @@
+pub mod cli;
+pub mod clipboard;
+pub mod config_file;
+pub mod config_schema;
+pub mod credentials;
+pub mod extract_text;
+pub mod gateway;
+pub mod history;
+pub mod jobs;
+#[cfg(feature = "mock-gateway")]
+pub mod mock_gateway;
+pub mod panel_state;
+pub mod placement;
+pub mod redaction;
+pub mod secret_store;
+pub mod selection;
+pub mod shortcuts;
+pub mod source_app;
+pub mod write_back;
+mod actions;
+mod notch;
+mod panel;
+mod window;
+
+use std::sync::{Mutex, PoisonError};
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+use window_vibrancy::{apply_vibrancy, NSVisualEffectMaterial, NSVisualEffectState};
+
+use credentials::{
+    activate_profile, clear_credentials, config_load_report, create_profile, delete_profile,
+    list_profiles, load_credentials, rename_profile, save_credentials,
+};
+use gateway::{
+    gateway_connect, gateway_disconnect, gateway_request, gateway_send_chat, gateway_status,
+    init_gateway,
+};
+use history::{history_clear, history_delete, history_get, history_list, history_search};
+use notch::{hide_notch, list_jobs, notch_clicked, show_notch};
+use panel::{
+    dismiss_panel, hide_panel, list_displays, panel_moved, reset_panel_layout, restore_panel,
+    set_panel_expanded, transition, PANEL, WINDOW_LABEL,
+};
+use panel_state::PanelEvent;
+use selection::watch_clipboard;
+use shortcuts::{
+    apply_bindings, default_bindings, ShortcutAction, ShortcutBinding, ShortcutRegistrar,
+    ShortcutReport,
+};
+use write_back::WriteMode;
+
+/// Registers shortcuts with the global-shortcut plugin.
+struct TauriRegistrar<'a> {
+    app: &'a AppHandle,
+}
+
+impl ShortcutRegistrar for TauriRegistrar<'_> {
+    type Key = Shortcut;
+
+    fn parse(&self, accelerator: &str) -> Result<Shortcut, String> {
+        accelerator.parse::<Shortcut>().map_err(|e| e.to_string())
+    }
+
+    fn unregister_all(&mut self) {
+        let _ = self.app.global_shortcut().unregister_all();
+    }
+
+    fn register(&mut self, key: Shortcut, action: ShortcutAction) -> Result<(), String> {
+        self.app
+            .global_shortcut()
+            .on_shortcut(key, move |app, _shortcut, event| {
+                if event.state == ShortcutState::Pressed {
+                    actions::handle_action(app, &action);
+                }
+            })
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// Bindings currently registered with the OS; the rollback target.
+static ACTIVE_SHORTCUTS: Mutex<Vec<ShortcutBinding>> = Mutex::new(Vec::new());
+/// Outcome of the most recent registration, for `/status`.
+static SHORTCUT_REPORT: Mutex<Option<ShortcutReport>> = Mutex::new(None);
+
+fn register_shortcuts(app: &AppHandle, bindings: &[ShortcutBinding]) -> ShortcutReport {
+    let mut active = ACTIVE_SHORTCUTS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    let last_good = if active.is_empty() {
+        default_bindings()
+    } else {
+        active.clone()
+    };
+
+    let mut registrar = TauriRegistrar { app };
+    let report = apply_bindings(&mut registrar, bindings, &last_good);
+    if report.applied {
+        *active = bindings.to_vec();
+    } else if active.is_empty() {
+        // Nothing registered yet (startup): the defaults keep the panel reachable.
+        if !report.rolled_back {
+            apply_bindings(&mut registrar, &last_good, &[]);
+        }
+        *active = last_good;
+    }
+
+    for status in &report.bindings {
+        if let Some(problem) = &status.problem {
+            eprintln!("shortcut {} not active: {problem:?}", status.accelerator);
+        }
+    }
+    if let Some(error) = &report.error {
+        eprintln!("failed to update global shortcuts: {error}");
+    }
+
+    *SHORTCUT_REPORT
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner) = Some(report.clone());
+    report
+}
+
+/// Register `shortcuts` and save them if they were applied. The report
+/// lists every binding that is not active and why.
+#[tauri::command]
+fn update_shortcuts(
+    app: AppHandle,
+    shortcuts: Vec<ShortcutBinding>,
+) -> Result<ShortcutReport, String> {
+    let report = register_shortcuts(&app, &shortcuts);
+    if report.applied {
+        credentials::save_shortcuts(shortcuts)?;
+    }
+    Ok(report)
+}
+
+#[tauri::command]
+fn shortcut_report() -> Option<ShortcutReport> {
+    SHORTCUT_REPORT
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+}
+
+/// Paste `text` into the app the last selection came from, over the
+/// selection or after it. The panel is hidden first so focus can go back,
+/// and shown again with the answer if writing fails.
+#[tauri::command]
+async fn write_back_response(app: AppHandle, text: String, mode: WriteMode) -> Result<(), String> {
+    dismiss_panel(&app);
+    let result = tauri::async_runtime::spawn_blocking(move || write_back::write_back(&text, mode))
+        .await
+        .map_err(|e| format!("write back error: {e}"))
+        .and_then(|result| result);
+    if result.is_err() && transition(&app, PanelEvent::Reopen) {
+        restore_panel(&app);
+    }
+    result
+}
+
+pub fn run() {
+    tauri::Builder::default()
+        .plugin(tauri_plugin_global_shortcut::Builder::new().build())
+        .invoke_handler(tauri::generate_handler![
+            load_credentials,
+            save_credentials,
+            clear_credentials,
+            config_load_report,
+            list_profiles,
+            create_profile,
+            rename_profile,
+            delete_profile,
+            activate_profile,
+            hide_panel,
+            set_panel_expanded,
+            reset_panel_layout,
+            list_displays,
+            show_notch,
+            hide_notch,
+            notch_clicked,
+            list_jobs,
+            update_shortcuts,
+            shortcut_report,
+            write_back_response,
+            gateway_connect,
+            gateway_disconnect,
+            gateway_send_chat,
+            gateway_request,
+            gateway_status,
+            history_list,
+            history_search,
+            history_get,
+            history_delete,
+            history_clear
+        ])
+        .setup(move |app| {
+            #[cfg(target_os = "macos")]
+            app.set_activation_policy(tauri::ActivationPolicy::Accessory);
+
+            init_gateway(app.handle());
+
+            // Load saved shortcuts or fall back to defaults.
+            let bindings = credentials::load_credentials()
+                .ok()
+                .and_then(|creds| creds.shortcuts)
+                .unwrap_or_else(default_bindings);
+            register_shortcuts(app.handle(), &bindings);
+
+            // Clipboard history records copies from launch on.
+            if let Ok(creds) = credentials::load_credentials() {
+                watch_clipboard(&creds.selection);
+            }
+
+            match credentials::load_panel_layout() {
+                Ok(layout) => PANEL.load_layout(layout),
+                Err(error) => eprintln!("failed to load panel layout: {error}"),
+            }
+
+            if let Some(window) = app.get_webview_window(WINDOW_LABEL) {
+                if let Err(error) = apply_vibrancy(
+                    &window,
+                    NSVisualEffectMaterial::HudWindow,
+                    Some(NSVisualEffectState::Active),
+                    Some(12.0),
+                ) {
+                    eprintln!("failed to apply vibrancy: {error}");
+                }
+
+                // Window starts hidden (visible: false in tauri.conf.json).
+                // present_panel() will center + show when hotkey is pressed.
+
+                // On focus loss: notify JS but do NOT hide yet. JS handles
+                // background-mode detection via the "panel-dismiss" event,
+                // then calls hide_panel itself. Hiding here (orderOut:)
+                // would suppress the DOM blur and IPC delivery.
+                let window_clone = window.clone();
+                window.on_window_event(move |event| {
+                    if let tauri::WindowEvent::Focused(false) = event {
+                        if transition(window_clone.app_handle(), PanelEvent::Dismiss) {
+                            let _ = window_clone.emit("panel-dismiss", "");
+                        }
+                    } else if let tauri::WindowEvent::Moved(position) = event {
+                        // Moves are the user dragging the panel (or present_panel
+                        // placing it, which records the same spot).
+                        panel_moved(window_clone.app_handle(), position.x, position.y);
+                    }
+                });
+            }
+
+            Ok(())
+        })
+        .run(tauri::generate_context!())
+        .expect("error while running tauri application");
+}
@@ -0,0 +1,356 @@
+//! In-process OpenClaw gateway speaking the same frame protocol as the
+//! real one. Used by integration tests and the `mock-gateway` binary for
+//! CI and offline demos.
+
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{mpsc, Notify};
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::gateway::PROTOCOL_VERSION;
+
+/// Failure injected into every `chat.send` run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Fault {
+    /// Answer `chat.send` with `ok: false` and this message.
+    RejectSend(String),
+    /// Stream a `chat` error event with this message instead of `final`.
+    ChatError(String),
+    /// Emit an `aborted` chat event after this many deltas.
+    AbortAfter(usize),
+    /// Drop the socket after this many deltas.
+    DisconnectAfter(usize),
+}
+
+#[derive(Debug, Clone)]
+pub struct MockGatewayConfig {
+    pub addr: SocketAddr,
+    /// Required `auth.token`; `None` accepts any client.
+    pub token: Option<String>,
+    /// Required `auth.password`; either credential is enough when both are set.
+    pub password: Option<String>,
+    /// Replies used in order (cycling). Empty echoes the prompt back.
+    pub replies: Vec<String>,
+    /// Number of delta events each reply is split into.
+    pub chunks: usize,
+    pub chunk_delay: Duration,
+    pub handshake_delay: Duration,
+    pub fault: Option<Fault>,
+}
+
+impl Default for MockGatewayConfig {
+    fn default() -> Self {
+        Self {
+            addr: SocketAddr::from(([127, 0, 0, 1], 0)),
+            token: None,
+            password: None,
+            replies: Vec::new(),
+            chunks: 4,
+            chunk_delay: Duration::from_millis(40),
+            handshake_delay: Duration::ZERO,
+            fault: None,
+        }
+    }
+}
+
+/// Running mock gateway. Stops accepting connections when dropped.
+pub struct MockGateway {
+    addr: SocketAddr,
+    shutdown: Arc<Notify>,
+}
+
+impl MockGateway {
+    pub async fn start(config: MockGatewayConfig) -> std::io::Result<Self> {
+        let listener = TcpListener::bind(config.addr).await?;
+        let addr = listener.local_addr()?;
+        let shutdown = Arc::new(Notify::new());
+        let state = Arc::new(ServerState {
+            config,
+            reply_index: AtomicUsize::new(0),
+            run_id: AtomicUsize::new(1),
+        });
+
+        let stop = shutdown.clone();
+        tokio::spawn(async move {
+            loop {
+                let stream = tokio::select! {
+                    accepted = listener.accept() => match accepted {
+                        Ok((stream, _)) => stream,
+                        Err(error) => {
+                            eprintln!("mock gateway accept failed: {error}");
+                            continue;
+                        }
+                    },
+                    _ = stop.notified() => return,
+                };
+                tokio::spawn(serve_connection(stream, state.clone()));
+            }
+        });
+
+        Ok(Self { addr, shutdown })
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    pub fn url(&self) -> String {
+        format!("ws://{}", self.addr)
+    }
+}
+
+impl Drop for MockGateway {
+    fn drop(&mut self) {
+        self.shutdown.notify_one();
+    }
+}
+
+struct ServerState {
+    config: MockGatewayConfig,
+    reply_index: AtomicUsize,
+    run_id: AtomicUsize,
+}
+
+impl ServerState {
+    fn next_reply(&self, prompt: &str) -> String {
+        if self.config.replies.is_empty() {
+            return format!("Echo: {prompt}");
+        }
+        let index = self.reply_index.fetch_add(1, Ordering::SeqCst);
+        self.config.replies[index % self.config.replies.len()].clone()
+    }
+
+    fn accepts(&self, auth: &Value) -> bool {
+        let token = self.config.token.as_deref();
+        let password = self.config.password.as_deref();
+        if token.is_none() && password.is_none() {
+            return true;
+        }
+        let matches = |expected: Option<&str>, field: &str| {
+            expected
+                .is_some_and(|expected| auth.get(field).and_then(Value::as_str) == Some(expected))
+        };
+        matches(token, "token") || matches(password, "password")
+    }
+}
+
+/// Outgoing half of a connection, shared with streaming runs.
+#[derive(Clone)]
+struct Outbox {
+    tx: mpsc::UnboundedSender<Message>,
+}
+
+impl Outbox {
+    fn send(&self, frame: Value) -> bool {
+        self.tx
+            .send(Message::Text(frame.to_string().into()))
+            .is_ok()
+    }
+
+    fn respond(&self, id: &Value, result: Result<Value, (&str, String)>) -> bool {
+        let frame = match result {
+            Ok(payload) => json!({ "type": "res", "id": id, "ok": true, "payload": payload }),
+            Err((code, message)) => json!({
+                "type": "res",
+                "id": id,
+                "ok": false,
+                "error": { "code": code, "message": message },
+            }),
+        };
+        self.send(frame)
+    }
+
+    fn chat(&self, payload: Value) -> bool {
+        self.send(json!({ "type": "event", "event": "chat", "payload": payload }))
+    }
+
+    fn close(&self) {
+        let _ = self.tx.send(Message::Close(None));
+    }
+}
+
+async fn serve_connection(stream: TcpStream, state: Arc<ServerState>) {
+    let Ok(socket) = tokio_tungstenite::accept_async(stream).await else {
+        return;
+    };
+    let (mut write, mut read) = socket.split();
+    let (tx, mut rx) = mpsc::unbounded_channel::<Message>();
+    let outbox = Outbox { tx };
+
+    tokio::spawn(async move {
+        while let Some(message) = rx.recv().await {
+            let closing = matches!(message, Message::Close(_));
+            if write.send(message).await.is_err() || closing {
+                break;
+            }
+        }
+        let _ = write.close().await;
+    });
+
+    outbox.send(json!({
+        "type": "event",
+        "event": "connect.challenge",
+        "payload": { "nonce": uuid::Uuid::new_v4().to_string() },
+    }));
+
+    let mut authenticated = false;
+    let mut active_run: Option<Arc<AtomicBool>> = None;
+
+    while let Some(Ok(message)) = read.next().await {
+        let text = match message {
+            Message::Text(text) => text,
+            Message::Close(_) => break,
+            _ => continue,
+        };
+        let Ok(frame) = serde_json::from_str::<Value>(&text) else {
+            continue;
+        };
+        if frame.get("type").and_then(Value::as_str) != Some("req") {
+            continue;
+        }
+
+        let id = frame.get("id").cloned().unwrap_or(Value::Null);
+        let params = frame.get("params").cloned().unwrap_or(Value::Null);
+        match frame
+            .get("method")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+        {
+            "connect" => {
+                tokio::time::sleep(state.config.handshake_delay).await;
+                let result = check_handshake(&state, &params);
+                authenticated = result.is_ok();
+                outbox.respond(&id, result);
+            }
+            _ if !authenticated => {
+                outbox.respond(&id, Err(("UNAUTHENTICATED", "connect first".into())));
+            }
+            "chat.send" => {
+                if let Some(Fault::RejectSend(message)) = &state.config.fault {
+                    outbox.respond(&id, Err(("CHAT_REJECTED", message.clone())));
+                    continue;
+                }
+                let prompt = params
+                    .get("message")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default();
+                let run_id = format!("run-{}", state.run_id.fetch_add(1, Ordering::SeqCst));
+                let session_key = params
+                    .get("sessionKey")
+                    .and_then(Value::as_str)
+                    .unwrap_or("main")
+                    .to_string();
+                outbox.respond(&id, Ok(json!({ "runId": run_id, "status": "started" })));
+
+                let aborted = Arc::new(AtomicBool::new(false));
+                active_run = Some(aborted.clone());
+                tokio::spawn(stream_reply(
+                    outbox.clone(),
+                    state.clone(),
+                    StreamRun {
+                        run_id,
+                        session_key,
+                        reply: state.next_reply(prompt),
+                        aborted,
+                    },
+                ));
+            }
+            "chat.abort" => {
+                if let Some(run) = active_run.take() {
+                    run.store(true, Ordering::SeqCst);
+                }
+                outbox.respond(&id, Ok(json!({ "aborted": true })));
+            }
+            method => {
+                outbox.respond(
+                    &id,
+                    Err(("UNKNOWN_METHOD", format!("unknown method: {method}"))),
+                );
+            }
+        }
+    }
+}
+
+fn check_handshake(state: &ServerState, params: &Value) -> Result<Value, (&'static str, String)> {
+    let min = params
+        .get("minProtocol")
+        .and_then(Value::as_u64)
+        .unwrap_or(0);
+    let max = params
+        .get("maxProtocol")
+        .and_then(Value::as_u64)
+        .unwrap_or(0);
+    if !(min..=max).contains(&PROTOCOL_VERSION) {
+        return Err((
+            "PROTOCOL_MISMATCH",
+            format!("protocol {PROTOCOL_VERSION} not in {min}..={max}"),
+        ));
+    }
+
+    if !state.accepts(params.get("auth").unwrap_or(&Value::Null)) {
+        return Err(("AUTH_FAILED", "invalid token or password".into()));
+    }
+
+    Ok(json!({ "protocol": PROTOCOL_VERSION, "server": { "name": "macclaw-mock-gateway" } }))
+}
+
+struct StreamRun {
+    run_id: String,
+    session_key: String,
+    reply: String,
+    aborted: Arc<AtomicBool>,
+}
+
+async fn stream_reply(outbox: Outbox, state: Arc<ServerState>, run: StreamRun) {
+    let config = &state.config;
+    let chars: Vec<char> = run.reply.chars().collect();
+    let chunks = config.chunks.max(1);
+    let chunk_len = chars.len().div_ceil(chunks).max(1);
+    let base = json!({ "runId": run.run_id, "sessionKey": run.session_key });
+    let event = |extra: Value| {
+        let mut payload = base.clone();
+        if let (Some(payload), Some(extra)) = (payload.as_object_mut(), extra.as_object()) {
+            payload.extend(extra.clone());
+        }
+        payload
+    };
+
+    // Deltas carry the cumulative text, like the real gateway.
+    for (sent, end) in (chunk_len..chars.len() + chunk_len)
+        .step_by(chunk_len)
+        .enumerate()
+    {
+        tokio::time::sleep(config.chunk_delay).await;
+
+        if run.aborted.load(Ordering::SeqCst) || config.fault == Some(Fault::AbortAfter(sent)) {
+            outbox.chat(event(json!({ "state": "aborted" })));
+            return;
+        }
+        if config.fault == Some(Fault::DisconnectAfter(sent)) {
+            outbox.close();
+            return;
+        }
+
+        let text: String = chars[..end.min(chars.len())].iter().collect();
+        if !outbox.chat(event(
+            json!({ "state": "delta", "message": { "text": text } }),
+        )) {
+            return;
+        }
+    }
+
+    tokio::time::sleep(config.chunk_delay).await;
+    if let Some(Fault::ChatError(message)) = &config.fault {
+        outbox.chat(event(json!({ "state": "error", "errorMessage": message })));
+        return;
+    }
+    outbox.chat(event(json!({
+        "state": "final",
+        "message": { "role": "assistant", "content": [{ "type": "text", "text": run.reply }] },
+    })));
+}
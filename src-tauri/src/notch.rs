@@ -1,18 +1,104 @@
-use std::sync::atomic::{AtomicBool, Ordering};
-use tauri::{Emitter, Manager};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::Manager;
 
-use crate::panel::{restore_panel, WINDOW_LABEL};
+use crate::jobs::{JobState, JobSummary, JOBS_EVENT};
+use crate::panel::{restore_panel, transition, WINDOW_LABEL};
+use crate::panel_state::{PanelEvent, PanelState};
+use crate::window::{emit, WindowHost};
 
 pub const NOTCH_LABEL: &str = "notch";
+/// Event with the [`JobSummary`] of a job that changed state.
+pub const NOTCH_STATE_EVENT: &str = "notch-state";
 
 // Indicator width (logical points). Height is computed from menu bar + content.
 const INDICATOR_WIDTH: f64 = 300.0;
 const CONTENT_HEIGHT: f64 = 44.0;
 
-pub static IS_BACKGROUND_RESPONSE: AtomicBool = AtomicBool::new(false);
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct NotchSettings {
+    /// Seconds the notch stays once every answer arrived; `None` keeps it
+    /// until clicked. An error always keeps it.
+    pub auto_hide_secs: Option<u64>,
+}
+
+impl Default for NotchSettings {
+    fn default() -> Self {
+        Self {
+            auto_hide_secs: Some(4),
+        }
+    }
+}
+
+/// Hide the notch and bring a background response back into the panel:
+/// job `job`, or the one the panel showed, or the newest. The panel gets
+/// the job's text with "notch-restore". Does nothing unless a response is
+/// in the background.
+pub fn restore_from_notch(host: &dyn WindowHost, job: Option<u64>) {
+    if !transition(host, PanelEvent::Restore) {
+        return;
+    }
+    let snapshot = host.panel().jobs().show(job);
+    if let Some(window) = host.window(NOTCH_LABEL) {
+        window.hide();
+    }
+    if let Some(window) = host.window(WINDOW_LABEL) {
+        emit(&*window, "notch-restore", snapshot);
+    }
+    publish_jobs(host);
+    restore_panel(host);
+}
+
+/// Send the prompts in flight to the panel and the notch, plus a
+/// "notch-state" event for each job that changed state. Once all of them
+/// finished without an error, the notch hides after the configured delay.
+pub fn publish_jobs(host: &dyn WindowHost) {
+    let (summaries, changed, revision) = {
+        let mut jobs = host.panel().jobs();
+        (jobs.summaries(), jobs.take_changed(), jobs.revision())
+    };
+    // Events reach every webview, whichever window sends them.
+    let Some(window) = host.window(WINDOW_LABEL) else {
+        return;
+    };
+    emit(&*window, JOBS_EVENT, &summaries);
+    if changed.is_empty() {
+        return;
+    }
+    for job in &changed {
+        emit(&*window, NOTCH_STATE_EVENT, job);
+    }
+    if let Some(delay) = auto_hide_delay(&summaries, &host.notch_settings()) {
+        host.after(delay, Box::new(move |host| auto_hide(host, revision)));
+    }
+}
+
+fn auto_hide_delay(jobs: &[JobSummary], settings: &NotchSettings) -> Option<Duration> {
+    let settled = !jobs.is_empty()
+        && jobs
+            .iter()
+            .all(|job| matches!(job.state, JobState::Done | JobState::Aborted));
+    let secs = settings.auto_hide_secs.filter(|_| settled)?;
+    Some(Duration::from_secs(secs))
+}
+
+/// Drop the notch unless anything happened since the delay started, such
+/// as a new prompt or a restore.
+fn auto_hide(host: &dyn WindowHost, revision: u64) {
+    if host.panel().jobs().revision() != revision {
+        return;
+    }
+    if transition(host, PanelEvent::LeaveBackground) {
+        if let Some(window) = host.window(NOTCH_LABEL) {
+            window.hide();
+        }
+    }
+}
 
 /// Detect real notch dimensions from NSScreen APIs (macOS 12+).
 /// Returns (width, height, scale_factor) in logical points, or None if no notch.
+#[cfg(target_os = "macos")]
 fn detect_notch() -> Option<(f64, f64, f64)> {
     use objc2_app_kit::NSScreen;
     use objc2_foundation::MainThreadMarker;
@@ -36,9 +122,16 @@ fn detect_notch() -> Option<(f64, f64, f64)> {
     Some((notch_width, notch_height, scale))
 }
 
+#[cfg(not(target_os = "macos"))]
+fn detect_notch() -> Option<(f64, f64, f64)> {
+    None
+}
+
 #[tauri::command]
 pub fn show_notch(app: tauri::AppHandle) -> Result<(), String> {
-    IS_BACKGROUND_RESPONSE.store(true, Ordering::SeqCst);
+    if !transition(&app, PanelEvent::SendToBackground) {
+        return Ok(());
+    }
 
     // Get menu bar height and scale factor (needed for both re-show and first create).
     let (menu_bar_h, scale) = detect_notch()
@@ -54,14 +147,14 @@ pub fn show_notch(app: tauri::AppHandle) -> Result<(), String> {
         });
 
     if let Some(window) = app.get_webview_window(NOTCH_LABEL) {
-        // BUG 1: Reset DOM to streaming state before showing to avoid stale content flash.
-        let _ = window.emit("notch-state", serde_json::json!({"state": "streaming"}));
+        // BUG 1: Send the current jobs before showing to avoid stale content flash.
+        publish_jobs(&app);
         // BUG 4: Sync CSS spacer height with real menu bar height.
-        let _ = window.eval(&format!(
+        let _ = window.eval(format!(
             "document.documentElement.style.setProperty('--menu-bar-h','{}px')",
             menu_bar_h
         ));
-        let _ = window.show();
+        show_if_background(&app, &window);
         return Ok(());
     }
 
@@ -107,10 +200,12 @@ pub fn show_notch(app: tauri::AppHandle) -> Result<(), String> {
         ));
     }
 
-    let _ = notch_window.show();
+    // Building the window takes a while; a shortcut may have restored the
+    // panel in the meantime.
+    show_if_background(&app, &notch_window);
 
     // BUG 4: Sync CSS spacer height with real menu bar height.
-    let _ = notch_window.eval(&format!(
+    let _ = notch_window.eval(format!(
         "document.documentElement.style.setProperty('--menu-bar-h','{}px')",
         menu_bar_h
     ));
@@ -121,7 +216,14 @@ pub fn show_notch(app: tauri::AppHandle) -> Result<(), String> {
     Ok(())
 }
 
+fn show_if_background(app: &tauri::AppHandle, window: &tauri::WebviewWindow) {
+    if app.panel().state() == PanelState::Background {
+        let _ = window.show();
+    }
+}
+
 /// Configure NSWindow: status-level (25) + no shadow for seamless notch blend.
+#[cfg(target_os = "macos")]
 fn configure_notch_window(window: &tauri::WebviewWindow) {
     use raw_window_handle::HasWindowHandle;
     let Ok(handle) = window.window_handle() else { return };
@@ -137,24 +239,183 @@ fn configure_notch_window(window: &tauri::WebviewWindow) {
     }
 }
 
+#[cfg(not(target_os = "macos"))]
+fn configure_notch_window(_window: &tauri::WebviewWindow) {}
+
 #[tauri::command]
 pub fn hide_notch(app: tauri::AppHandle) -> Result<(), String> {
-    IS_BACKGROUND_RESPONSE.store(false, Ordering::SeqCst);
+    transition(&app, PanelEvent::LeaveBackground);
     if let Some(window) = app.get_webview_window(NOTCH_LABEL) {
         let _ = window.hide();
     }
     Ok(())
 }
 
+/// `job` is the prompt the notch was showing when clicked.
 #[tauri::command]
-pub fn notch_clicked(app: tauri::AppHandle) -> Result<(), String> {
-    IS_BACKGROUND_RESPONSE.store(false, Ordering::SeqCst);
-    if let Some(window) = app.get_webview_window(NOTCH_LABEL) {
-        let _ = window.hide();
+pub fn notch_clicked(app: tauri::AppHandle, job: Option<u64>) -> Result<(), String> {
+    restore_from_notch(&app, job);
+    Ok(())
+}
+
+/// Prompts in flight, for a notch window that just loaded.
+#[tauri::command]
+pub fn list_jobs(app: tauri::AppHandle) -> Vec<JobSummary> {
+    app.panel().jobs().summaries()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gateway::{BotEvent, ChatRun};
+    use crate::window::testing::FakeHost;
+
+    #[test]
+    fn restore_hands_the_response_back_to_the_panel() {
+        let host = FakeHost::new(&[WINDOW_LABEL, NOTCH_LABEL]);
+        transition(&host, PanelEvent::SendToBackground);
+        host.take_calls();
+
+        restore_from_notch(&host, None);
+        assert_eq!(
+            host.take_calls(),
+            [
+                "main emit panel-state",
+                "notch hide",
+                "main emit notch-restore",
+                "main emit jobs-changed",
+                "main center",
+                "main show",
+                "main focus",
+                "main emit panel-state",
+                "main emit panel-show"
+            ]
+        );
+        assert_eq!(host.panel().state(), PanelState::Expanded);
+
+        // A second click after the restore finished is ignored.
+        restore_from_notch(&host, None);
+        assert!(host.take_calls().is_empty());
     }
-    if let Some(window) = app.get_webview_window(WINDOW_LABEL) {
-        let _ = window.emit("notch-restore", "");
-        restore_panel(&window);
+
+    #[test]
+    fn clicking_a_job_restores_that_response() {
+        let host = FakeHost::new(&[WINDOW_LABEL, NOTCH_LABEL]);
+        let first = send(&host, "Summarize");
+        let second = send(&host, "Translate");
+        let delta = BotEvent::AssistantDelta {
+            text: "Half a summary".into(),
+        };
+        host.panel().jobs().record(&run(first), &delta);
+        transition(&host, PanelEvent::SendToBackground);
+
+        restore_from_notch(&host, Some(first));
+        let restored = host.payload("notch-restore").unwrap();
+        assert_eq!(restored["id"], first);
+        assert_eq!(restored["label"], "Summarize");
+        assert_eq!(restored["state"], "streaming");
+        assert_eq!(restored["text"], "Half a summary");
+        assert_eq!(host.panel().jobs().shown(), Some(first));
+
+        // Both are still running, so the notch keeps listing both.
+        let listed = host.payload(JOBS_EVENT).unwrap();
+        let ids: Vec<_> = listed.as_array().unwrap().iter().map(|job| &job["id"]).collect();
+        assert_eq!(ids, [first, second]);
+    }
+
+    /// Start a job whose request and run are named after its id.
+    fn send(host: &FakeHost, prompt: &str) -> u64 {
+        let mut jobs = host.panel().jobs();
+        let job = jobs.start(prompt);
+        jobs.sent(job, job.to_string());
+        jobs.run_started(&job.to_string(), &format!("run-{job}"));
+        job
+    }
+
+    fn run(job: u64) -> ChatRun {
+        ChatRun::Run(format!("run-{job}"))
+    }
+
+    fn answer(host: &FakeHost, job: u64, text: &str) {
+        let mut jobs = host.panel().jobs();
+        jobs.record(&run(job), &BotEvent::Assistant { text: text.into() });
+        jobs.record(&run(job), &BotEvent::AssistantDone);
+    }
+
+    #[test]
+    fn notch_hides_a_while_after_the_last_answer() {
+        let host = FakeHost::new(&[WINDOW_LABEL, NOTCH_LABEL]);
+        let first = send(&host, "One");
+        let second = send(&host, "Two");
+        transition(&host, PanelEvent::SendToBackground);
+        publish_jobs(&host);
+        let started = host.payload(NOTCH_STATE_EVENT).unwrap();
+        assert_eq!(started["state"], "streaming");
+
+        answer(&host, first, "1");
+        publish_jobs(&host);
+        assert_eq!(host.payload(NOTCH_STATE_EVENT).unwrap()["state"], "done");
+        // The second prompt is still streaming.
+        assert!(host.run_tasks().is_empty());
+
+        answer(&host, second, "2");
+        publish_jobs(&host);
+        // Nothing changed, so nothing is scheduled again.
+        publish_jobs(&host);
+        host.take_calls();
+        assert_eq!(host.run_tasks(), [Duration::from_secs(4)]);
+        assert_eq!(host.take_calls(), ["main emit panel-state", "notch hide"]);
+        assert_eq!(host.panel().state(), PanelState::Hidden);
+    }
+
+    #[test]
+    fn errors_and_new_prompts_keep_the_notch() {
+        let host = FakeHost::new(&[WINDOW_LABEL, NOTCH_LABEL]);
+        let first = send(&host, "One");
+        transition(&host, PanelEvent::SendToBackground);
+        let error = BotEvent::Error {
+            text: "Agent error.".into(),
+        };
+        host.panel().jobs().record(&run(first), &error);
+        publish_jobs(&host);
+        assert!(host.run_tasks().is_empty());
+
+        let second = send(&host, "Two");
+        answer(&host, second, "2");
+        publish_jobs(&host);
+        // Another prompt started before the delay ran out.
+        host.panel().jobs().start("Three");
+        host.take_calls();
+        assert_eq!(host.run_tasks().len(), 1);
+        assert!(host.take_calls().is_empty());
+        assert_eq!(host.panel().state(), PanelState::Background);
+    }
+
+    #[test]
+    fn auto_hide_can_be_turned_off() {
+        let done = JobSummary {
+            id: 1,
+            label: "One".into(),
+            state: JobState::Done,
+            started_at: 0,
+            text_len: 0,
+            tokens: None,
+            elapsed_ms: 0,
+            preview: None,
+        };
+        let aborted = JobSummary {
+            state: JobState::Aborted,
+            ..done.clone()
+        };
+        let never = NotchSettings {
+            auto_hide_secs: None,
+        };
+        let settings = NotchSettings::default();
+        assert_eq!(
+            auto_hide_delay(&[done.clone(), aborted], &settings),
+            Some(Duration::from_secs(4))
+        );
+        assert_eq!(auto_hide_delay(&[done], &never), None);
+        assert_eq!(auto_hide_delay(&[], &settings), None);
     }
-    Ok(())
 }
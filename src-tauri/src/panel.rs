@@ -1,90 +1,579 @@
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::OnceLock;
-use tauri::{Emitter, Manager};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use crate::credentials;
+use crate::jobs::JobRegistry;
+use crate::panel_state::{PanelEvent, PanelMachine, PanelState, PANEL_STATE_EVENT};
+use crate::placement::{
+    pick_monitor, room, Anchor, Monitor, PanelLayout, PanelSettings, PanelSize, PlacementCache,
+    PlacementPolicy, COMPACT_HEIGHT, MIN_SIZE,
+};
+use crate::window::{emit, WindowController, WindowHost};
 
 pub const WINDOW_LABEL: &str = "main";
-pub const COMPACT_WIDTH: f64 = 750.0;
-pub const COMPACT_HEIGHT: f64 = 56.0;
 
-pub static IS_PANEL_OPEN: AtomicBool = AtomicBool::new(false);
-static CENTER_POS: OnceLock<(i32, i32)> = OnceLock::new();
+/// State of the app shared by every window.
+pub static PANEL: Panel = Panel::new();
+
+/// Panel visibility, where the panel reappears on each display and the
+/// prompts it has in flight.
+pub struct Panel {
+    machine: PanelMachine,
+    placement: Mutex<PlacementCache>,
+    jobs: Mutex<JobRegistry>,
+}
+
+impl Panel {
+    pub const fn new() -> Self {
+        Self {
+            machine: PanelMachine::new(),
+            placement: Mutex::new(PlacementCache::new()),
+            jobs: Mutex::new(JobRegistry::new()),
+        }
+    }
+
+    pub fn state(&self) -> PanelState {
+        self.machine.state()
+    }
+
+    /// Start from the spots saved by an earlier launch.
+    pub fn load_layout(&self, layout: PanelLayout) {
+        self.placement().load(layout);
+    }
+
+    pub fn jobs(&self) -> MutexGuard<'_, JobRegistry> {
+        self.jobs.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn placement(&self) -> MutexGuard<'_, PlacementCache> {
+        self.placement
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Default for Panel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Apply `event` to the panel state and tell the webviews about the change.
+/// Returns false when the current state refuses the event.
+pub fn transition(host: &dyn WindowHost, event: PanelEvent) -> bool {
+    let Some(change) = host.panel().machine.handle(event) else {
+        return false;
+    };
+    if change.from != change.to {
+        if let Some(window) = host.window(WINDOW_LABEL) {
+            emit(&*window, PANEL_STATE_EVENT, change);
+        }
+    }
+    true
+}
 
 /// Hide the panel. Size is NOT reset here — present_panel handles
 /// the compact reset, while restore_panel preserves the current size.
 /// Used by hide_panel command (Escape key) and hotkey toggle — NOT by
 /// focus-loss, which goes through JS via the "panel-dismiss" event.
-pub fn dismiss_panel(window: &tauri::WebviewWindow) {
-    IS_PANEL_OPEN.store(false, Ordering::SeqCst);
-    let _ = window.hide();
-}
-
-/// Show the panel at a fixed position and focus it.
-/// First call: let macOS center the window, store the exact physical
-/// coordinates. All subsequent calls: restore the stored position.
-/// This eliminates drift from Dock auto-hide, screen changes, etc.
-pub fn present_panel(window: &tauri::WebviewWindow) {
-    IS_PANEL_OPEN.store(true, Ordering::SeqCst);
-    let _ = window.set_size(tauri::Size::Logical(
-        tauri::LogicalSize::new(COMPACT_WIDTH, COMPACT_HEIGHT),
-    ));
-
-    match CENTER_POS.get() {
-        Some(&(x, y)) => {
-            let _ = window.set_position(tauri::Position::Physical(
-                tauri::PhysicalPosition::new(x, y),
-            ));
-        }
-        None => {
-            // First open — let macOS compute the centered position.
-            let _ = window.center();
-        }
+pub fn dismiss_panel(host: &dyn WindowHost) {
+    // Hidden either way: in background mode the panel is already closed.
+    transition(host, PanelEvent::Dismiss);
+    if let Some(window) = host.window(WINDOW_LABEL) {
+        window.hide();
+    }
+    save_layout(host);
+}
+
+/// Save where the panel was dragged, once it closes rather than on every
+/// move event.
+fn save_layout(host: &dyn WindowHost) {
+    let changes = host.panel().placement().take_changes();
+    if let Some(layout) = changes {
+        host.save_layout(layout);
     }
+}
 
-    let _ = window.show();
-    let _ = window.set_focus();
+/// The user dragged the panel; its top-left corner is at physical `x`, `y`.
+pub fn panel_moved(host: &dyn WindowHost, x: i32, y: i32) {
+    let monitors = host.monitors();
+    host.panel().placement().moved(&monitors, x, y);
+}
 
-    // After the first visible show, store the position permanently.
-    if CENTER_POS.get().is_none() {
-        if let Ok(pos) = window.outer_position() {
-            CENTER_POS.get_or_init(|| (pos.x, pos.y));
-        }
+/// Size the panel for a view `height` points tall, a response when
+/// `expanded`, as the layout preset asks and as far as its display allows.
+pub fn resize_panel(host: &dyn WindowHost, height: f64, expanded: bool) {
+    let event = if expanded {
+        PanelEvent::Expand
+    } else {
+        PanelEvent::Collapse
+    };
+    transition(host, event);
+    let Some(window) = host.window(WINDOW_LABEL) else {
+        return;
+    };
+    let settings = host.panel_settings();
+    let monitors = host.monitors();
+    let last = host.panel().placement().last().map(str::to_string);
+    let monitor = pick_monitor(&monitors, last.as_deref().map(Anchor::Id));
+    place(host, &*window, &settings, monitor, Some((height, expanded)));
+}
+
+/// Forget the saved spots and put a visible panel back in the middle of
+/// its display.
+pub fn reset_layout(host: &dyn WindowHost) {
+    host.panel().placement().reset();
+    if !matches!(
+        host.panel().state(),
+        PanelState::Compact | PanelState::Expanded
+    ) {
+        return;
+    }
+    if let Some(window) = host.window(WINDOW_LABEL) {
+        let settings = PanelSettings {
+            placement: PlacementPolicy::LastUsed,
+            ..host.panel_settings()
+        };
+        let monitors = host.monitors();
+        let monitor = pick_for(host, &settings, &monitors);
+        place(host, &*window, &settings, monitor, None);
     }
+}
 
-    let _ = window.emit("panel-show", "");
+/// Show the panel's input strip, shaped by the layout preset, on the
+/// display picked by the placement policy and focus it. Does nothing while
+/// the notch owns a response or hands it back.
+pub fn present_panel(host: &dyn WindowHost) {
+    if !transition(host, PanelEvent::Present) {
+        return;
+    }
+    let Some(window) = host.window(WINDOW_LABEL) else {
+        return;
+    };
+    let settings = host.panel_settings();
+    let monitors = host.monitors();
+    let monitor = pick_for(host, &settings, &monitors);
+    place(
+        host,
+        &*window,
+        &settings,
+        monitor,
+        Some((COMPACT_HEIGHT, false)),
+    );
+    window.show();
+    window.focus();
+    window.emit("panel-show", "".into());
 }
 
 /// Show the panel without resetting size — used when restoring from notch
-/// so the response content stays visible.
-pub fn restore_panel(window: &tauri::WebviewWindow) {
-    IS_PANEL_OPEN.store(true, Ordering::SeqCst);
-
-    match CENTER_POS.get() {
-        Some(&(x, y)) => {
-            let _ = window.set_position(tauri::Position::Physical(
-                tauri::PhysicalPosition::new(x, y),
-            ));
-        }
-        None => {
-            let _ = window.center();
-        }
-    }
+/// so the response content stays visible. It comes back on the display it
+/// left. Callers move the state to Restoring first; it becomes Expanded
+/// once the panel is shown.
+pub fn restore_panel(host: &dyn WindowHost) {
+    let Some(window) = host.window(WINDOW_LABEL) else {
+        return;
+    };
+    let settings = PanelSettings {
+        placement: PlacementPolicy::LastUsed,
+        ..host.panel_settings()
+    };
+    let monitors = host.monitors();
+    let monitor = pick_for(host, &settings, &monitors);
+    place(host, &*window, &settings, monitor, None);
+    window.show();
+    window.focus();
+    transition(host, PanelEvent::Restored);
+    window.emit("panel-show", "".into());
+}
 
-    let _ = window.show();
-    let _ = window.set_focus();
+fn pick_for<'a>(
+    host: &dyn WindowHost,
+    settings: &PanelSettings,
+    monitors: &'a [Monitor],
+) -> Option<&'a Monitor> {
+    let last = host.panel().placement().last().map(str::to_string);
+    let anchor = match settings.placement {
+        PlacementPolicy::Cursor => host.cursor().map(|(x, y)| Anchor::Point(x, y)),
+        PlacementPolicy::FocusedWindow => host.focused_window().map(Anchor::Window),
+        PlacementPolicy::Display => Some(Anchor::Name(&settings.display)),
+        PlacementPolicy::LastUsed => last.as_deref().map(Anchor::Id),
+    };
+    pick_monitor(monitors, anchor)
+}
 
-    if CENTER_POS.get().is_none() {
-        if let Ok(pos) = window.outer_position() {
-            CENTER_POS.get_or_init(|| (pos.x, pos.y));
+/// Move the panel to its spot on `monitor` for the layout preset and, given
+/// a view height, size it to fit between there and the work area edges,
+/// never below the window minimum.
+fn place(
+    host: &dyn WindowHost,
+    window: &dyn WindowController,
+    settings: &PanelSettings,
+    monitor: Option<&Monitor>,
+    view: Option<(f64, bool)>,
+) {
+    let wanted = view.map(|(height, expanded)| settings.wanted_size(height, expanded));
+    let Some(monitor) = monitor else {
+        // No display information — cap the height at the preferred one
+        // and let the OS center the window.
+        if let Some(wanted) = wanted {
+            let max = PanelSize {
+                height: wanted.height.min(settings.expanded.height),
+                ..wanted
+            };
+            let size = wanted.clamp(MIN_SIZE, max);
+            window.set_size(size.width, size.height);
         }
+        window.center();
+        return;
+    };
+
+    let strip = settings.wanted_size(COMPACT_HEIGHT, false);
+    let (x, y) = {
+        let mut placement = host.panel().placement();
+        placement.set_preset(settings);
+        placement.position(monitor, strip.width, strip.height)
+    };
+    if let Some(wanted) = wanted {
+        let size = wanted.clamp(MIN_SIZE, room(monitor, (x, y)));
+        window.set_size(size.width, size.height);
     }
+    window.set_position(x, y);
+}
 
-    let _ = window.emit("panel-show", "");
+/// Connected displays, for choosing a fixed one in `/settings`.
+#[tauri::command]
+pub fn list_displays(app: tauri::AppHandle) -> Vec<Monitor> {
+    app.monitors()
 }
 
 #[tauri::command]
 pub fn hide_panel(app: tauri::AppHandle) -> Result<(), String> {
-    if let Some(window) = app.get_webview_window(WINDOW_LABEL) {
-        dismiss_panel(&window);
-    }
+    dismiss_panel(&app);
+    Ok(())
+}
+
+/// The webview shows a view `height` points tall: a response when
+/// `expanded`, otherwise the input, hints or a form.
+#[tauri::command]
+pub fn set_panel_expanded(
+    app: tauri::AppHandle,
+    expanded: bool,
+    height: f64,
+) -> Result<(), String> {
+    resize_panel(&app, height, expanded);
     Ok(())
 }
+
+/// Forget where the panel was dragged and its preferred expanded size.
+/// Returns the panel settings with the default size.
+#[tauri::command]
+pub fn reset_panel_layout(app: tauri::AppHandle) -> Result<PanelSettings, String> {
+    let settings = credentials::reset_panel_layout()?;
+    reset_layout(&app);
+    Ok(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::placement::{centered, DockEdge, LayoutPreset, Rect, COMPACT_WIDTH, WIDE_WIDTH};
+    use crate::window::testing::FakeHost;
+
+    fn host() -> FakeHost {
+        FakeHost::new(&[WINDOW_LABEL])
+    }
+
+    fn display(name: &str, x: i32, width: u32, height: u32, scale: f64) -> Monitor {
+        let frame = Rect {
+            x,
+            y: 0,
+            width,
+            height,
+        };
+        Monitor {
+            name: name.into(),
+            frame,
+            work_area: frame,
+            scale,
+            primary: x == 0,
+        }
+    }
+
+    /// Retina laptop with an external display to its right.
+    fn two_displays() -> FakeHost {
+        let mut host = host();
+        host.monitors = vec![
+            display("Built-in", 0, 3024, 1964, 2.0),
+            display("External", 3024, 2560, 1440, 1.0),
+        ];
+        host
+    }
+
+    fn moved_to(host: &FakeHost, monitor: usize) -> String {
+        let (x, y) = centered(&host.monitors[monitor], COMPACT_WIDTH, COMPACT_HEIGHT);
+        format!("main move {x},{y}")
+    }
+
+    #[test]
+    fn present_without_displays_lets_the_os_center() {
+        let host = host();
+        present_panel(&host);
+
+        assert_eq!(
+            host.take_calls(),
+            [
+                "main emit panel-state",
+                "main size 750x56",
+                "main center",
+                "main show",
+                "main focus",
+                "main emit panel-show"
+            ]
+        );
+        assert_eq!(
+            host.payload(PANEL_STATE_EVENT),
+            Some(serde_json::json!({"from": "hidden", "to": "compact"}))
+        );
+    }
+
+    #[test]
+    fn present_follows_the_mouse_between_displays() {
+        let mut host = two_displays();
+        host.cursor = Some((4000, 700));
+        present_panel(&host);
+        assert!(host.take_calls().contains(&moved_to(&host, 1)));
+
+        dismiss_panel(&host);
+        host.cursor = Some((100, 100));
+        present_panel(&host);
+        assert!(host.take_calls().contains(&moved_to(&host, 0)));
+    }
+
+    #[test]
+    fn present_honours_the_placement_policy() {
+        let mut host = two_displays();
+        host.cursor = Some((100, 100));
+        host.focused_window = Some(Rect {
+            x: 3500,
+            y: 200,
+            width: 1200,
+            height: 800,
+        });
+        host.settings.placement = PlacementPolicy::FocusedWindow;
+        present_panel(&host);
+        assert!(host.take_calls().contains(&moved_to(&host, 1)));
+
+        dismiss_panel(&host);
+        host.settings.placement = PlacementPolicy::Display;
+        host.settings.display = "Built-in".into();
+        present_panel(&host);
+        assert!(host.take_calls().contains(&moved_to(&host, 0)));
+
+        dismiss_panel(&host);
+        host.settings.placement = PlacementPolicy::LastUsed;
+        host.cursor = Some((4000, 700));
+        present_panel(&host);
+        assert!(host.take_calls().contains(&moved_to(&host, 0)));
+    }
+
+    #[test]
+    fn restore_returns_to_the_display_it_left() {
+        let mut host = two_displays();
+        host.cursor = Some((4000, 700));
+        present_panel(&host);
+        transition(&host, PanelEvent::SendToBackground);
+        transition(&host, PanelEvent::Restore);
+        host.take_calls();
+
+        host.cursor = Some((100, 100));
+        restore_panel(&host);
+        assert!(host.take_calls().contains(&moved_to(&host, 1)));
+    }
+
+    #[test]
+    fn present_does_nothing_while_the_notch_owns_the_response() {
+        let host = host();
+        transition(&host, PanelEvent::SendToBackground);
+        host.take_calls();
+
+        present_panel(&host);
+        assert!(host.take_calls().is_empty());
+        assert_eq!(host.panel().state(), PanelState::Background);
+    }
+
+    #[test]
+    fn dismiss_hides_even_when_already_closed() {
+        let host = host();
+        dismiss_panel(&host);
+        assert_eq!(host.take_calls(), ["main hide"]);
+
+        present_panel(&host);
+        host.take_calls();
+        dismiss_panel(&host);
+        assert_eq!(host.take_calls(), ["main emit panel-state", "main hide"]);
+        assert_eq!(host.panel().state(), PanelState::Hidden);
+    }
+
+    #[test]
+    fn restore_keeps_the_size_and_finishes_the_restore() {
+        let host = host();
+        transition(&host, PanelEvent::SendToBackground);
+        transition(&host, PanelEvent::Restore);
+        host.take_calls();
+
+        restore_panel(&host);
+        assert_eq!(
+            host.take_calls(),
+            [
+                "main center",
+                "main show",
+                "main focus",
+                "main emit panel-state",
+                "main emit panel-show"
+            ]
+        );
+        assert_eq!(host.panel().state(), PanelState::Expanded);
+    }
+
+    #[test]
+    fn dragged_spots_are_saved_when_the_panel_closes() {
+        let mut host = two_displays();
+        host.cursor = Some((4000, 700));
+        present_panel(&host);
+        // Placing the panel is not worth a save.
+        dismiss_panel(&host);
+        assert_eq!(host.take_saved_layout(), None);
+
+        present_panel(&host);
+        panel_moved(&host, 3100, 40);
+        panel_moved(&host, 3200, 50);
+        assert_eq!(host.take_saved_layout(), None);
+        dismiss_panel(&host);
+        let layout = host.take_saved_layout().unwrap();
+        assert_eq!(
+            layout.positions[&host.monitors[1].id()],
+            crate::placement::Offset { x: 176, y: 50 }
+        );
+
+        // The next launch starts where the user left it.
+        let mut next = two_displays();
+        next.cursor = Some((4000, 700));
+        next.panel().load_layout(layout);
+        present_panel(&next);
+        assert!(next.take_calls().contains(&"main move 3200,50".to_string()));
+    }
+
+    /// Sizes set since the last call.
+    fn sizes(host: &FakeHost) -> Vec<String> {
+        host.take_calls()
+            .into_iter()
+            .filter(|call| call.starts_with("main size"))
+            .collect()
+    }
+
+    #[test]
+    fn expanding_stays_within_the_preferred_size_and_the_display() {
+        let mut host = two_displays();
+        host.cursor = Some((4000, 700));
+        host.settings.expanded = PanelSize {
+            width: 900.0,
+            height: 600.0,
+        };
+        present_panel(&host);
+        host.take_calls();
+
+        resize_panel(&host, 200.0, true);
+        assert_eq!(
+            host.take_calls(),
+            [
+                "main emit panel-state".to_string(),
+                "main size 900x200".to_string(),
+                moved_to(&host, 1)
+            ]
+        );
+        assert_eq!(host.panel().state(), PanelState::Expanded);
+        resize_panel(&host, 5000.0, true);
+        assert_eq!(sizes(&host), ["main size 900x600"]);
+
+        // Dragged near the bottom: only the room left below it.
+        panel_moved(&host, 3100, 1300);
+        resize_panel(&host, 5000.0, true);
+        assert_eq!(sizes(&host), ["main size 900x140"]);
+
+        resize_panel(&host, 160.0, false);
+        assert_eq!(sizes(&host), ["main size 750x140"]);
+        assert_eq!(host.panel().state(), PanelState::Compact);
+    }
+
+    #[test]
+    fn presets_size_the_panel_within_the_display() {
+        let mut host = two_displays();
+        host.cursor = Some((4000, 700));
+        host.settings.preset = LayoutPreset::Wide;
+        present_panel(&host);
+        let calls = host.take_calls();
+        assert!(calls.contains(&"main size 1100x56".to_string()));
+        let (x, y) = centered(&host.monitors[1], WIDE_WIDTH, COMPACT_HEIGHT);
+        assert!(calls.contains(&format!("main move {x},{y}")));
+
+        // Switching to the sidebar while the panel is open docks it.
+        host.settings.preset = LayoutPreset::Sidebar;
+        resize_panel(&host, 56.0, false);
+        assert_eq!(host.take_calls(), ["main size 640x56", "main move 4944,0"]);
+        resize_panel(&host, 300.0, true);
+        assert_eq!(
+            host.take_calls(),
+            [
+                "main emit panel-state",
+                "main size 640x1440",
+                "main move 4944,0"
+            ]
+        );
+
+        // The sidebar on the Retina display is 640 points of 2x pixels.
+        dismiss_panel(&host);
+        host.settings.dock = DockEdge::Left;
+        host.cursor = Some((100, 100));
+        present_panel(&host);
+        assert!(host.take_calls().contains(&"main move 0,0".to_string()));
+        resize_panel(&host, 300.0, true);
+        assert_eq!(sizes(&host), ["main size 640x982"]);
+    }
+
+    #[test]
+    fn without_displays_the_size_stays_sensible() {
+        let mut host = host();
+        host.settings.preset = LayoutPreset::Sidebar;
+        present_panel(&host);
+        host.take_calls();
+        resize_panel(&host, 300.0, true);
+        assert_eq!(sizes(&host), ["main size 640x420"]);
+    }
+
+    #[test]
+    fn reset_recenters_a_visible_panel() {
+        let mut host = two_displays();
+        host.cursor = Some((4000, 700));
+        present_panel(&host);
+        panel_moved(&host, 3100, 40);
+        host.take_calls();
+
+        reset_layout(&host);
+        let calls = host.take_calls();
+        assert!(calls.contains(&moved_to(&host, 1)));
+        dismiss_panel(&host);
+        assert_eq!(host.take_saved_layout(), None);
+        host.take_calls();
+
+        reset_layout(&host);
+        assert!(host.take_calls().is_empty());
+    }
+
+    #[test]
+    fn missing_window_still_moves_the_state() {
+        let host = FakeHost::new(&[]);
+        present_panel(&host);
+        assert_eq!(host.panel().state(), PanelState::Compact);
+        dismiss_panel(&host);
+        assert_eq!(host.panel().state(), PanelState::Hidden);
+    }
+}
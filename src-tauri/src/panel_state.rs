@@ -0,0 +1,227 @@
+//! Visibility of the panel and the notch as one state machine. Shortcuts,
+//! focus changes and notch clicks arrive on different threads; each becomes
+//! a [`PanelEvent`] applied under one lock, and events that make no sense
+//! in the current state (a second restore, a shortcut while the notch is
+//! being created) are refused instead of racing.
+
+use serde::Serialize;
+use std::sync::{Mutex, PoisonError};
+
+/// Event sent to the webviews on every state change.
+pub const PANEL_STATE_EVENT: &str = "panel-state";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PanelState {
+    /// Nothing on screen.
+    Hidden,
+    /// Input strip only.
+    Compact,
+    /// Grown to show a response.
+    Expanded,
+    /// Panel hidden, response streaming into the notch.
+    Background,
+    /// Notch going away, panel coming back with its response.
+    Restoring,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PanelEvent {
+    /// Show the panel at compact size.
+    Present,
+    /// The webview grew the panel for a response.
+    Expand,
+    /// The webview shrank the panel back to the input strip.
+    Collapse,
+    /// Hotkey toggle, Escape or focus loss.
+    Dismiss,
+    /// Hand the response over to the notch.
+    SendToBackground,
+    /// Drop the notch without showing the panel.
+    LeaveBackground,
+    /// Bring the notch's response back into the panel.
+    Restore,
+    /// Show the hidden panel again as it was.
+    Reopen,
+    /// The panel is visible again after a restore.
+    Restored,
+}
+
+/// A state change, as sent to the webviews.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct PanelTransition {
+    pub from: PanelState,
+    pub to: PanelState,
+}
+
+impl PanelState {
+    /// State after `event`, or `None` when `event` is not allowed now.
+    pub fn on(self, event: PanelEvent) -> Option<Self> {
+        use PanelEvent::*;
+        use PanelState::*;
+
+        match (self, event) {
+            (Restoring, Restored) => Some(Expanded),
+            // Nothing interrupts a restore in flight.
+            (Restoring, _) => None,
+            (Background, Restore) => Some(Restoring),
+            (Background, LeaveBackground) => Some(Hidden),
+            // The notch owns the response until it is restored or dropped.
+            (Background, _) => None,
+            (_, Present) => Some(Compact),
+            (_, SendToBackground) => Some(Background),
+            (Hidden, Reopen) => Some(Restoring),
+            (Compact, Expand) => Some(Expanded),
+            (Expanded, Collapse) => Some(Compact),
+            (Compact | Expanded, Dismiss) => Some(Hidden),
+            _ => None,
+        }
+    }
+
+    /// Whether the panel window is meant to be on screen.
+    pub fn is_panel_open(self) -> bool {
+        matches!(self, Self::Compact | Self::Expanded)
+    }
+}
+
+pub struct PanelMachine {
+    state: Mutex<PanelState>,
+}
+
+impl PanelMachine {
+    pub const fn new() -> Self {
+        Self {
+            state: Mutex::new(PanelState::Hidden),
+        }
+    }
+
+    pub fn state(&self) -> PanelState {
+        *self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Applies `event`; `None` when the current state refuses it.
+    pub fn handle(&self, event: PanelEvent) -> Option<PanelTransition> {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        let from = *state;
+        let to = from.on(event)?;
+        *state = to;
+        Some(PanelTransition { from, to })
+    }
+}
+
+impl Default for PanelMachine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use PanelEvent::*;
+    use PanelState::*;
+
+    fn run(events: &[PanelEvent]) -> (PanelMachine, Vec<Option<PanelState>>) {
+        let machine = PanelMachine::new();
+        let states = events
+            .iter()
+            .map(|event| machine.handle(*event).map(|change| change.to))
+            .collect();
+        (machine, states)
+    }
+
+    #[test]
+    fn toggling_shows_and_hides_the_panel() {
+        let (machine, states) = run(&[Present, Expand, Dismiss, Dismiss, Present]);
+
+        assert_eq!(
+            states,
+            [
+                Some(Compact),
+                Some(Expanded),
+                Some(Hidden),
+                None,
+                Some(Compact)
+            ]
+        );
+        assert!(machine.state().is_panel_open());
+    }
+
+    #[test]
+    fn background_response_round_trip() {
+        let (machine, states) = run(&[Present, Expand, SendToBackground, Restore, Restored]);
+
+        assert_eq!(
+            states,
+            [
+                Some(Compact),
+                Some(Expanded),
+                Some(Background),
+                Some(Restoring),
+                Some(Expanded)
+            ]
+        );
+        assert_eq!(machine.state(), Expanded);
+    }
+
+    #[test]
+    fn notch_keeps_the_response_until_restored_or_dropped() {
+        let (machine, states) = run(&[SendToBackground, Present, Dismiss, Expand, Reopen]);
+
+        assert_eq!(states, [Some(Background), None, None, None, None]);
+        assert_eq!(
+            machine.handle(LeaveBackground),
+            Some(PanelTransition {
+                from: Background,
+                to: Hidden
+            })
+        );
+        assert_eq!(
+            machine.handle(Present).map(|change| change.to),
+            Some(Compact)
+        );
+    }
+
+    #[test]
+    fn restore_in_flight_ignores_other_events() {
+        // Shortcut pressed twice while the notch hands the response back.
+        let (machine, states) = run(&[
+            SendToBackground,
+            Restore,
+            Restore,
+            Present,
+            Dismiss,
+            SendToBackground,
+        ]);
+
+        assert_eq!(
+            states,
+            [Some(Background), Some(Restoring), None, None, None, None]
+        );
+        assert_eq!(machine.state(), Restoring);
+        assert_eq!(
+            machine.handle(Restored).map(|change| change.to),
+            Some(Expanded)
+        );
+    }
+
+    #[test]
+    fn hidden_panel_can_be_reopened_but_not_restored() {
+        let (_, states) = run(&[Restore, Restored, Reopen, Restored]);
+        assert_eq!(states, [None, None, Some(Restoring), Some(Expanded)]);
+        let (_, states) = run(&[Present, Reopen, Collapse]);
+        assert_eq!(states, [Some(Compact), None, None]);
+    }
+
+    #[test]
+    fn states_serialize_for_the_webview() {
+        let change = PanelTransition {
+            from: Background,
+            to: Restoring,
+        };
+        assert_eq!(
+            serde_json::to_string(&change).unwrap(),
+            r#"{"from":"background","to":"restoring"}"#
+        );
+    }
+}
@@ -0,0 +1,695 @@
+//! Which display the panel opens on and where on it. The geometry here is
+//! pure: callers describe the displays, the mouse and the focused window in
+//! physical pixels and get back a top-left corner for the panel.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// How the display for the panel is chosen.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlacementPolicy {
+    /// The display under the mouse.
+    #[default]
+    Cursor,
+    /// The display showing most of the focused window.
+    FocusedWindow,
+    /// The display named in [`PanelSettings::display`].
+    Display,
+    /// Wherever the panel was last shown.
+    LastUsed,
+}
+
+/// Shape of the panel.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LayoutPreset {
+    /// A strip wherever it was put, growing to the expanded size for a
+    /// response.
+    #[default]
+    Compact,
+    /// A wider strip that keeps its width for a response.
+    Wide,
+    /// A column docked to a side of the screen, as tall as the screen for a
+    /// response.
+    Sidebar,
+}
+
+/// Side of the screen a [`LayoutPreset::Sidebar`] docks to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DockEdge {
+    Left,
+    #[default]
+    Right,
+}
+
+pub const COMPACT_WIDTH: f64 = 750.0;
+pub const COMPACT_HEIGHT: f64 = 56.0;
+pub const WIDE_WIDTH: f64 = 1100.0;
+/// `minWidth` / `minHeight` of the window in `tauri.conf.json`.
+pub const MIN_SIZE: PanelSize = PanelSize {
+    width: 640.0,
+    height: 48.0,
+};
+/// As narrow as the window allows.
+pub const SIDEBAR_WIDTH: f64 = MIN_SIZE.width;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct PanelSettings {
+    pub placement: PlacementPolicy,
+    /// Display name used by [`PlacementPolicy::Display`].
+    pub display: String,
+    /// Largest size the panel grows to for a response.
+    pub expanded: PanelSize,
+    pub preset: LayoutPreset,
+    pub dock: DockEdge,
+}
+
+impl PanelSettings {
+    /// Size the preset asks for to show a view `height` points tall, before
+    /// it is fitted to the display. `expanded` views hold a response.
+    pub fn wanted_size(&self, height: f64, expanded: bool) -> PanelSize {
+        let grown = if expanded {
+            height.min(self.expanded.height)
+        } else {
+            height
+        };
+        match self.preset {
+            LayoutPreset::Compact if expanded => PanelSize {
+                width: self.expanded.width,
+                height: grown,
+            },
+            LayoutPreset::Compact => PanelSize {
+                width: COMPACT_WIDTH,
+                height,
+            },
+            LayoutPreset::Wide => PanelSize {
+                width: WIDE_WIDTH,
+                height: grown,
+            },
+            LayoutPreset::Sidebar => PanelSize {
+                width: SIDEBAR_WIDTH,
+                height: if expanded { f64::INFINITY } else { height },
+            },
+        }
+    }
+}
+
+/// Window size in logical points.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PanelSize {
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Default for PanelSize {
+    fn default() -> Self {
+        Self {
+            width: COMPACT_WIDTH,
+            height: 420.0,
+        }
+    }
+}
+
+impl PanelSize {
+    /// At most `max` and at least `min`; `min` wins where they cross.
+    pub fn clamp(self, min: PanelSize, max: PanelSize) -> PanelSize {
+        PanelSize {
+            width: self.width.min(max.width).max(min.width),
+            height: self.height.min(max.height).max(min.height),
+        }
+    }
+}
+
+/// Top-left corner of the panel relative to its display, in physical pixels.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Offset {
+    pub x: i32,
+    pub y: i32,
+}
+
+/// Where the panel sits on each display, saved across launches. Keyed by
+/// [`Monitor::id`], with a suffix for presets other than the compact strip.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PanelLayout {
+    pub positions: BTreeMap<String, Offset>,
+}
+
+/// Rectangle in physical pixels, top-left origin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Rect {
+    fn right(&self) -> i32 {
+        self.x + self.width as i32
+    }
+
+    fn bottom(&self) -> i32 {
+        self.y + self.height as i32
+    }
+
+    fn contains(&self, x: i32, y: i32) -> bool {
+        (self.x..self.right()).contains(&x) && (self.y..self.bottom()).contains(&y)
+    }
+
+    /// Area shared with `other`.
+    fn overlap(&self, other: &Rect) -> u64 {
+        let width = self.right().min(other.right()) - self.x.max(other.x);
+        let height = self.bottom().min(other.bottom()) - self.y.max(other.y);
+        if width <= 0 || height <= 0 {
+            return 0;
+        }
+        width as u64 * height as u64
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Monitor {
+    pub name: String,
+    pub frame: Rect,
+    /// `frame` minus the menu bar, Dock or panels.
+    pub work_area: Rect,
+    pub scale: f64,
+    pub primary: bool,
+}
+
+impl Monitor {
+    /// Stable across rearranging displays, different for another display or
+    /// a new resolution.
+    pub fn id(&self) -> String {
+        format!("{}:{}x{}", self.name, self.frame.width, self.frame.height)
+    }
+}
+
+/// What picks the display.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Anchor<'a> {
+    Point(i32, i32),
+    Window(Rect),
+    Name(&'a str),
+    Id(&'a str),
+}
+
+/// Display for `anchor`, falling back to the primary display, then to any.
+pub fn pick_monitor<'a>(monitors: &'a [Monitor], anchor: Option<Anchor>) -> Option<&'a Monitor> {
+    let found = match anchor {
+        Some(Anchor::Point(x, y)) => monitors.iter().find(|m| m.frame.contains(x, y)),
+        Some(Anchor::Window(window)) => monitors
+            .iter()
+            .map(|m| (m.frame.overlap(&window), m))
+            .filter(|(overlap, _)| *overlap > 0)
+            .max_by_key(|(overlap, _)| *overlap)
+            .map(|(_, m)| m),
+        Some(Anchor::Name(name)) => monitors.iter().find(|m| m.name == name),
+        Some(Anchor::Id(id)) => monitors.iter().find(|m| m.id() == id),
+        None => None,
+    };
+    found
+        .or_else(|| monitors.iter().find(|m| m.primary))
+        .or_else(|| monitors.first())
+}
+
+/// Top-left corner that centers a `width` x `height` (logical) window in the
+/// work area of `monitor`.
+pub fn centered(monitor: &Monitor, width: f64, height: f64) -> (i32, i32) {
+    let area = monitor.work_area;
+    let width = (width * monitor.scale).round() as i32;
+    let height = (height * monitor.scale).round() as i32;
+    (
+        area.x + (area.width as i32 - width) / 2,
+        area.y + (area.height as i32 - height) / 2,
+    )
+}
+
+/// Top-left corner that keeps a `width` x `height` (pixels) window at `x`,
+/// `y` as far as it fits in the work area of `monitor`.
+pub fn clamped(monitor: &Monitor, x: i32, y: i32, width: u32, height: u32) -> (i32, i32) {
+    let area = monitor.work_area;
+    let fit =
+        |value: i32, start: i32, end: i32, length: u32| value.min(end - length as i32).max(start);
+    (
+        fit(x, area.x, area.right(), width),
+        fit(y, area.y, area.bottom(), height),
+    )
+}
+
+/// Top-left corner of a `width` (logical) column against `edge` of the work
+/// area of `monitor`.
+pub fn docked(monitor: &Monitor, edge: DockEdge, width: f64) -> (i32, i32) {
+    let area = monitor.work_area;
+    let width = ((width * monitor.scale).round() as i32).min(area.width as i32);
+    match edge {
+        DockEdge::Left => (area.x, area.y),
+        DockEdge::Right => (area.right() - width, area.y),
+    }
+}
+
+/// Logical space from `top_left` to the right and bottom edges of the work
+/// area of `monitor`.
+pub fn room(monitor: &Monitor, top_left: (i32, i32)) -> PanelSize {
+    let area = monitor.work_area;
+    PanelSize {
+        width: (area.right() - top_left.0).max(0) as f64 / monitor.scale,
+        height: (area.bottom() - top_left.1).max(0) as f64 / monitor.scale,
+    }
+}
+
+/// Converts a rectangle in screen points (macOS window frames) to pixels,
+/// using the scale of the display under its center.
+pub fn points_to_pixels(monitors: &[Monitor], rect: Rect) -> Rect {
+    let (cx, cy) = (
+        rect.x + rect.width as i32 / 2,
+        rect.y + rect.height as i32 / 2,
+    );
+    let scale = monitors
+        .iter()
+        .find(|m| {
+            let scaled = |value: i32| (value as f64 / m.scale).round() as i32;
+            let points = Rect {
+                x: scaled(m.frame.x),
+                y: scaled(m.frame.y),
+                width: scaled(m.frame.width as i32) as u32,
+                height: scaled(m.frame.height as i32) as u32,
+            };
+            points.contains(cx, cy)
+        })
+        .map_or(1.0, |m| m.scale);
+    let scaled = |value: i32| (value as f64 * scale).round() as i32;
+    Rect {
+        x: scaled(rect.x),
+        y: scaled(rect.y),
+        width: scaled(rect.width as i32) as u32,
+        height: scaled(rect.height as i32) as u32,
+    }
+}
+
+/// Where the panel sits on each display, kept relative to the display so a
+/// rearranged layout does not move it off screen. Reusing the spot instead
+/// of re-centering avoids drift when the Dock hides or the menu bar moves.
+/// Spots the user dragged the panel to are marked for saving. Each strip
+/// preset has its own spots; a sidebar is always docked.
+#[derive(Debug, Default)]
+pub struct PlacementCache {
+    layout: PanelLayout,
+    last: Option<String>,
+    changed: bool,
+    preset: LayoutPreset,
+    dock: DockEdge,
+}
+
+impl PlacementCache {
+    pub const fn new() -> Self {
+        Self {
+            layout: PanelLayout {
+                positions: BTreeMap::new(),
+            },
+            last: None,
+            changed: false,
+            preset: LayoutPreset::Compact,
+            dock: DockEdge::Right,
+        }
+    }
+
+    /// Place the panel for the preset in `settings` from now on.
+    pub fn set_preset(&mut self, settings: &PanelSettings) {
+        self.preset = settings.preset;
+        self.dock = settings.dock;
+    }
+
+    /// Key of the spot on `monitor` for the current preset.
+    fn slot(&self, monitor: &Monitor) -> Option<String> {
+        match self.preset {
+            LayoutPreset::Compact => Some(monitor.id()),
+            LayoutPreset::Wide => Some(format!("{} wide", monitor.id())),
+            LayoutPreset::Sidebar => None,
+        }
+    }
+
+    /// Start from spots saved by an earlier launch.
+    pub fn load(&mut self, layout: PanelLayout) {
+        self.layout = layout;
+        self.changed = false;
+    }
+
+    /// Forget every spot; the panel is centered again on each display.
+    pub fn reset(&mut self) {
+        self.load(PanelLayout::default());
+    }
+
+    /// Display the panel was last shown on.
+    pub fn last(&self) -> Option<&str> {
+        self.last.as_deref()
+    }
+
+    /// Top-left corner for a `width` x `height` (logical) panel on
+    /// `monitor`: docked for a sidebar, otherwise the saved spot or the
+    /// center, moved into the work area where the panel would stick out.
+    pub fn spot(&self, monitor: &Monitor, width: f64, height: f64) -> (i32, i32) {
+        let Some(slot) = self.slot(monitor) else {
+            return docked(monitor, self.dock, width);
+        };
+        let (x, y) = match self.layout.positions.get(&slot) {
+            Some(offset) => (monitor.frame.x + offset.x, monitor.frame.y + offset.y),
+            None => centered(monitor, width, height),
+        };
+        clamped(
+            monitor,
+            x,
+            y,
+            (width * monitor.scale).round() as u32,
+            (height * monitor.scale).round() as u32,
+        )
+    }
+
+    /// Like [`Self::spot`], and remembers the spot and the display.
+    pub fn position(&mut self, monitor: &Monitor, width: f64, height: f64) -> (i32, i32) {
+        let (x, y) = self.spot(monitor, width, height);
+        if let Some(slot) = self.slot(monitor) {
+            self.layout.positions.entry(slot).or_insert(Offset {
+                x: x - monitor.frame.x,
+                y: y - monitor.frame.y,
+            });
+        }
+        self.last = Some(monitor.id());
+        (x, y)
+    }
+
+    /// The panel was dragged so its top-left corner is at `x`, `y`. Ignored
+    /// off every display and for a docked sidebar.
+    pub fn moved(&mut self, monitors: &[Monitor], x: i32, y: i32) {
+        let Some(monitor) = monitors.iter().find(|m| m.frame.contains(x, y)) else {
+            return;
+        };
+        let Some(slot) = self.slot(monitor) else {
+            return;
+        };
+        let offset = Offset {
+            x: x - monitor.frame.x,
+            y: y - monitor.frame.y,
+        };
+        if self.layout.positions.get(&slot) != Some(&offset) {
+            self.layout.positions.insert(slot, offset);
+            self.changed = true;
+        }
+        self.last = Some(monitor.id());
+    }
+
+    /// The layout to save, if a drag changed it since the last call.
+    pub fn take_changes(&mut self) -> Option<PanelLayout> {
+        std::mem::take(&mut self.changed).then(|| self.layout.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x: i32, y: i32, width: u32, height: u32) -> Rect {
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    fn monitor(name: &str, frame: Rect, scale: f64, primary: bool) -> Monitor {
+        // 25 logical points of menu bar on top.
+        let bar = (25.0 * scale) as u32;
+        Monitor {
+            name: name.into(),
+            frame,
+            work_area: rect(
+                frame.x,
+                frame.y + bar as i32,
+                frame.width,
+                frame.height - bar,
+            ),
+            scale,
+            primary,
+        }
+    }
+
+    /// Retina laptop with a 1x external display to its right, top-aligned.
+    fn laptop_and_external() -> Vec<Monitor> {
+        vec![
+            monitor("Built-in", rect(0, 0, 3024, 1964), 2.0, true),
+            monitor("DELL U2720Q", rect(3024, 0, 2560, 1440), 1.0, false),
+        ]
+    }
+
+    #[test]
+    fn cursor_picks_the_display_under_the_mouse() {
+        let monitors = laptop_and_external();
+        let pick = |anchor| pick_monitor(&monitors, anchor).map(|m| m.name.as_str());
+
+        assert_eq!(pick(Some(Anchor::Point(4000, 700))), Some("DELL U2720Q"));
+        assert_eq!(pick(Some(Anchor::Point(10, 10))), Some("Built-in"));
+        // Below the shorter external display: nothing there, use primary.
+        assert_eq!(pick(Some(Anchor::Point(4000, 1800))), Some("Built-in"));
+        assert_eq!(pick(None), Some("Built-in"));
+        assert_eq!(pick_monitor(&[], Some(Anchor::Point(0, 0))), None);
+    }
+
+    #[test]
+    fn focused_window_picks_the_display_with_most_of_it() {
+        let monitors = laptop_and_external();
+        let pick =
+            |window| pick_monitor(&monitors, Some(Anchor::Window(window))).map(|m| m.name.as_str());
+
+        // Straddles the edge, mostly on the external display.
+        assert_eq!(pick(rect(2800, 100, 1200, 800)), Some("DELL U2720Q"));
+        assert_eq!(pick(rect(2000, 100, 1200, 800)), Some("Built-in"));
+        assert_eq!(pick(rect(-5000, 0, 100, 100)), Some("Built-in"));
+    }
+
+    #[test]
+    fn names_and_ids_pick_a_fixed_display() {
+        let monitors = laptop_and_external();
+        let external = &monitors[1];
+
+        assert_eq!(
+            pick_monitor(&monitors, Some(Anchor::Name("DELL U2720Q"))),
+            Some(external)
+        );
+        assert_eq!(
+            pick_monitor(&monitors, Some(Anchor::Id(&external.id()))),
+            Some(external)
+        );
+        // Unplugged: back to the primary display.
+        assert_eq!(
+            pick_monitor(&monitors, Some(Anchor::Name("LG"))),
+            Some(&monitors[0])
+        );
+    }
+
+    #[test]
+    fn centers_in_the_work_area_at_the_display_scale() {
+        let monitors = laptop_and_external();
+        // 750x56 points is 1500x112 pixels on the Retina display.
+        assert_eq!(centered(&monitors[0], 750.0, 56.0), (762, 951));
+        assert_eq!(centered(&monitors[1], 750.0, 56.0), (3024 + 905, 704));
+
+        // External display above and left of the primary one.
+        let above = monitor("Above", rect(-1920, -1080, 1920, 1080), 1.0, false);
+        assert_eq!(centered(&above, 750.0, 56.0), (-1920 + 585, -1055 + 499));
+    }
+
+    #[test]
+    fn window_points_become_pixels_of_their_display() {
+        let monitors = laptop_and_external();
+        // Each display's points are its pixels divided by its scale.
+        assert_eq!(
+            points_to_pixels(&monitors, rect(100, 50, 800, 600)),
+            rect(200, 100, 1600, 1200)
+        );
+        assert_eq!(
+            points_to_pixels(&monitors, rect(3100, 50, 800, 600)),
+            rect(3100, 50, 800, 600)
+        );
+    }
+
+    #[test]
+    fn cache_keeps_a_spot_per_display() {
+        let mut monitors = laptop_and_external();
+        let mut cache = PlacementCache::new();
+
+        let first = cache.position(&monitors[1], 750.0, 56.0);
+        assert_eq!(first, centered(&monitors[1], 750.0, 56.0));
+        assert_eq!(cache.last(), Some(monitors[1].id().as_str()));
+
+        // The external display moves to the left of the laptop.
+        monitors[1].frame.x = -2560;
+        monitors[1].work_area.x = -2560;
+        assert_eq!(
+            cache.position(&monitors[1], 750.0, 56.0),
+            (first.0 - 5584, first.1)
+        );
+
+        // A new resolution is a different display.
+        monitors[0].frame.width = 3456;
+        monitors[0].work_area.width = 3456;
+        assert_eq!(
+            cache.position(&monitors[0], 750.0, 56.0),
+            centered(&monitors[0], 750.0, 56.0)
+        );
+    }
+
+    #[test]
+    fn saved_spots_are_kept_inside_the_work_area() {
+        let monitors = laptop_and_external();
+        let mut cache = PlacementCache::new();
+        let mut layout = PanelLayout::default();
+        let external = monitors[1].id();
+        layout
+            .positions
+            .insert(external.clone(), Offset { x: 100, y: 200 });
+        // Saved when the built-in display was taller.
+        layout
+            .positions
+            .insert(monitors[0].id(), Offset { x: 2500, y: 5000 });
+        cache.load(layout);
+
+        assert_eq!(cache.position(&monitors[1], 750.0, 56.0), (3124, 200));
+        assert_eq!(
+            cache.position(&monitors[0], 750.0, 56.0),
+            (3024 - 1500, 1964 - 112)
+        );
+        // Placing does not count as a change worth saving.
+        assert_eq!(cache.take_changes(), None);
+
+        cache.moved(&monitors, 3124, 200);
+        assert_eq!(cache.take_changes(), None);
+        cache.moved(&monitors, 3324, 300);
+        assert_eq!(cache.last(), Some(external.as_str()));
+        let saved = cache.take_changes().unwrap();
+        assert_eq!(saved.positions[&external], Offset { x: 300, y: 300 });
+        assert_eq!(cache.take_changes(), None);
+
+        // Dragged off every display.
+        cache.moved(&monitors, -500, -500);
+        assert_eq!(cache.take_changes(), None);
+
+        cache.reset();
+        assert_eq!(
+            cache.position(&monitors[1], 750.0, 56.0),
+            centered(&monitors[1], 750.0, 56.0)
+        );
+    }
+
+    #[test]
+    fn presets_keep_their_own_spots_and_the_sidebar_docks() {
+        let monitors = laptop_and_external();
+        let external = &monitors[1];
+        let mut cache = PlacementCache::new();
+        let compact = cache.position(external, COMPACT_WIDTH, COMPACT_HEIGHT);
+
+        let mut settings = PanelSettings {
+            preset: LayoutPreset::Wide,
+            ..PanelSettings::default()
+        };
+        cache.set_preset(&settings);
+        assert_eq!(
+            cache.position(external, WIDE_WIDTH, COMPACT_HEIGHT),
+            centered(external, WIDE_WIDTH, COMPACT_HEIGHT)
+        );
+        cache.moved(&monitors, 3100, 600);
+        assert_eq!(
+            cache.spot(external, WIDE_WIDTH, COMPACT_HEIGHT),
+            (3100, 600)
+        );
+
+        settings.preset = LayoutPreset::Sidebar;
+        settings.dock = DockEdge::Left;
+        cache.set_preset(&settings);
+        assert_eq!(
+            cache.position(external, SIDEBAR_WIDTH, COMPACT_HEIGHT),
+            (3024, 25)
+        );
+        settings.dock = DockEdge::Right;
+        cache.set_preset(&settings);
+        assert_eq!(
+            cache.spot(&monitors[0], SIDEBAR_WIDTH, COMPACT_HEIGHT),
+            (3024 - 1280, 50)
+        );
+        // Dragging a docked sidebar saves nothing.
+        cache.take_changes();
+        cache.moved(&monitors, 3300, 300);
+        assert_eq!(cache.take_changes(), None);
+
+        settings.preset = LayoutPreset::Compact;
+        cache.set_preset(&settings);
+        assert_eq!(cache.spot(external, COMPACT_WIDTH, COMPACT_HEIGHT), compact);
+    }
+
+    #[test]
+    fn too_wide_panels_start_at_the_work_area_edge() {
+        let small = monitor("Projector", rect(0, 0, 1024, 768), 1.0, true);
+        let cache = PlacementCache::new();
+        assert_eq!(cache.spot(&small, WIDE_WIDTH, COMPACT_HEIGHT), (0, 368));
+    }
+
+    #[test]
+    fn presets_ask_for_their_own_sizes() {
+        let mut settings = PanelSettings::default();
+        let size = |settings: &PanelSettings, height, expanded| {
+            let size = settings.wanted_size(height, expanded);
+            (size.width, size.height)
+        };
+        assert_eq!(size(&settings, 160.0, false), (750.0, 160.0));
+        assert_eq!(size(&settings, 900.0, true), (750.0, 420.0));
+
+        settings.preset = LayoutPreset::Wide;
+        assert_eq!(size(&settings, 56.0, false), (1100.0, 56.0));
+        assert_eq!(size(&settings, 300.0, true), (1100.0, 300.0));
+
+        settings.preset = LayoutPreset::Sidebar;
+        assert_eq!(size(&settings, 56.0, false), (640.0, 56.0));
+        assert_eq!(size(&settings, 300.0, true), (640.0, f64::INFINITY));
+    }
+
+    #[test]
+    fn expanded_size_fits_the_room_below_the_panel() {
+        let monitors = laptop_and_external();
+        let min = PanelSize {
+            width: 640.0,
+            height: 48.0,
+        };
+        let wanted = PanelSize {
+            width: 900.0,
+            height: 800.0,
+        };
+
+        // 1964 - 1800 pixels is 82 points on the Retina display.
+        let space = room(&monitors[0], (1000, 1800));
+        assert_eq!(space.height, 82.0);
+        assert_eq!(
+            wanted.clamp(min, space),
+            PanelSize {
+                width: 900.0,
+                height: 82.0
+            }
+        );
+        // Never below the window minimum, even without room.
+        assert_eq!(wanted.clamp(min, room(&monitors[1], (5584, 1440))), min);
+    }
+
+    #[test]
+    fn settings_default_to_the_mouse_display() {
+        let settings: PanelSettings =
+            serde_json::from_str(r#"{"placement":"focused_window"}"#).unwrap();
+        assert_eq!(settings.placement, PlacementPolicy::FocusedWindow);
+        assert_eq!(PanelSettings::default().placement, PlacementPolicy::Cursor);
+        assert_eq!(settings.expanded, PanelSize::default());
+    }
+}
@@ -0,0 +1,554 @@
+//! Storage for gateway secrets (token, password). The OS keyring is
+//! preferred; when it is unavailable secrets go to an encrypted file whose
+//! key is derived from a passphrase or from a machine identifier.
+
+use argon2::Argon2;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+
+use crate::config_file;
+
+pub const KEYRING_SERVICE: &str = "ai.macclaw.panel";
+pub const SECRETS_FILE: &str = "secrets.enc.json";
+/// Set to unlock the encrypted file with a passphrase instead of the machine key.
+pub const PASSPHRASE_ENV: &str = "MACCLAW_PASSPHRASE";
+/// Set to `file` to skip the OS keyring.
+pub const BACKEND_ENV: &str = "MACCLAW_SECRET_STORE";
+
+const NONCE_LEN: usize = 12;
+const SALT_LEN: usize = 16;
+const FILE_VERSION: u32 = 1;
+
+pub trait SecretStore: Send + Sync {
+    /// Short backend name, shown in diagnostics.
+    fn name(&self) -> &'static str;
+    fn get(&self, key: &str) -> Result<Option<String>, String>;
+    fn set(&self, key: &str, value: &str) -> Result<(), String>;
+    fn delete(&self, key: &str) -> Result<(), String>;
+}
+
+/// macOS Keychain / Secret Service / Windows Credential Manager.
+pub struct KeyringStore {
+    service: String,
+}
+
+impl KeyringStore {
+    pub fn new(service: &str) -> Self {
+        Self {
+            service: service.to_string(),
+        }
+    }
+
+    /// Probe the platform keyring; `false` when no backend is reachable
+    /// (headless Linux without a Secret Service daemon, sandboxed CI, ...).
+    pub fn is_available(&self) -> bool {
+        match self.get("__probe__") {
+            Ok(_) => true,
+            Err(error) => {
+                eprintln!("keyring unavailable: {error}");
+                false
+            }
+        }
+    }
+
+    fn entry(&self, key: &str) -> Result<keyring::Entry, String> {
+        keyring::Entry::new(&self.service, key).map_err(|error| format!("Keyring error: {error}"))
+    }
+}
+
+impl SecretStore for KeyringStore {
+    fn name(&self) -> &'static str {
+        "keyring"
+    }
+
+    fn get(&self, key: &str) -> Result<Option<String>, String> {
+        match self.entry(key)?.get_password() {
+            Ok(value) => Ok(Some(value)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(error) => Err(format!("Keyring read error: {error}")),
+        }
+    }
+
+    fn set(&self, key: &str, value: &str) -> Result<(), String> {
+        self.entry(key)?
+            .set_password(value)
+            .map_err(|error| format!("Keyring write error: {error}"))
+    }
+
+    fn delete(&self, key: &str) -> Result<(), String> {
+        match self.entry(key)?.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(error) => Err(format!("Keyring delete error: {error}")),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum KeySource {
+    Passphrase(String),
+    Machine,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SecretsFile {
+    version: u32,
+    kdf: String,
+    salt: String,
+    /// Entry name → base64(nonce || ciphertext). The entry name is bound
+    /// as associated data so ciphertexts can't be swapped between keys.
+    entries: BTreeMap<String, String>,
+}
+
+/// ChaCha20-Poly1305 encrypted JSON file, key derived with Argon2id.
+pub struct EncryptedFileStore {
+    path: PathBuf,
+    secret: String,
+    /// Salt of the file as last seen and the cipher derived from it.
+    keyed: Mutex<([u8; SALT_LEN], ChaCha20Poly1305)>,
+}
+
+impl EncryptedFileStore {
+    pub fn open(path: &Path, source: &KeySource) -> Result<Self, String> {
+        let secret = match source {
+            KeySource::Passphrase(passphrase) => passphrase.clone(),
+            KeySource::Machine => machine_secret()?,
+        };
+        let salt = {
+            let _lock = config_file::lock(path)?;
+            match read_secrets_file(path)? {
+                Some(file) => decode_salt(&file.salt)?,
+                None => create_secrets_file(path)?,
+            }
+        };
+        let cipher = derive_cipher(&secret, &salt)?;
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            secret,
+            keyed: Mutex::new((salt, cipher)),
+        })
+    }
+
+    /// Read the file; callers hold [`config_file::lock`].
+    fn load(&self) -> Result<SecretsFile, String> {
+        if let Some(file) = read_secrets_file(&self.path)? {
+            return Ok(file);
+        }
+        let (salt, _) = *self.keyed.lock().unwrap_or_else(PoisonError::into_inner);
+        Ok(empty_secrets_file(&salt))
+    }
+
+    /// Cipher for the salt in `file`, re-derived if another process
+    /// recreated the file with a different salt since it was opened.
+    fn cipher(&self, file: &SecretsFile) -> Result<ChaCha20Poly1305, String> {
+        let salt = decode_salt(&file.salt)?;
+        let mut keyed = self.keyed.lock().unwrap_or_else(PoisonError::into_inner);
+        if keyed.0 != salt {
+            *keyed = (salt, derive_cipher(&self.secret, &salt)?);
+        }
+        Ok(keyed.1.clone())
+    }
+
+    fn store(&self, file: &SecretsFile) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(file)
+            .map_err(|error| format!("Serialize error: {error}"))?;
+        config_file::write_private(&self.path, json.as_bytes())
+    }
+
+    fn encrypt(&self, file: &SecretsFile, key: &str, value: &str) -> Result<String, String> {
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher(file)?
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: value.as_bytes(),
+                    aad: key.as_bytes(),
+                },
+            )
+            .map_err(|_| "Encryption failed".to_string())?;
+        let mut sealed = nonce.to_vec();
+        sealed.extend_from_slice(&ciphertext);
+        Ok(BASE64.encode(sealed))
+    }
+
+    fn decrypt(&self, file: &SecretsFile, key: &str, sealed: &str) -> Result<String, String> {
+        let sealed = BASE64
+            .decode(sealed)
+            .map_err(|_| format!("Corrupt secret entry: {key}"))?;
+        if sealed.len() < NONCE_LEN {
+            return Err(format!("Corrupt secret entry: {key}"));
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        let plaintext = self
+            .cipher(file)?
+            .decrypt(
+                Nonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: key.as_bytes(),
+                },
+            )
+            .map_err(|_| "Cannot decrypt secrets (wrong passphrase or machine key?)".to_string())?;
+        String::from_utf8(plaintext).map_err(|_| format!("Corrupt secret entry: {key}"))
+    }
+}
+
+impl SecretStore for EncryptedFileStore {
+    fn name(&self) -> &'static str {
+        "encrypted-file"
+    }
+
+    fn get(&self, key: &str) -> Result<Option<String>, String> {
+        let file = {
+            let _lock = config_file::lock(&self.path)?;
+            self.load()?
+        };
+        match file.entries.get(key) {
+            Some(sealed) => self.decrypt(&file, key, sealed).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn set(&self, key: &str, value: &str) -> Result<(), String> {
+        let _lock = config_file::lock(&self.path)?;
+        let mut file = self.load()?;
+        let sealed = self.encrypt(&file, key, value)?;
+        file.entries.insert(key.to_string(), sealed);
+        self.store(&file)
+    }
+
+    fn delete(&self, key: &str) -> Result<(), String> {
+        let _lock = config_file::lock(&self.path)?;
+        let mut file = self.load()?;
+        if file.entries.remove(key).is_some() {
+            self.store(&file)?;
+        }
+        Ok(())
+    }
+}
+
+/// Process-local store, used by tests.
+#[derive(Default)]
+pub struct MemoryStore {
+    entries: Mutex<HashMap<String, String>>,
+}
+
+impl SecretStore for MemoryStore {
+    fn name(&self) -> &'static str {
+        "memory"
+    }
+
+    fn get(&self, key: &str) -> Result<Option<String>, String> {
+        let entries = self
+            .entries
+            .lock()
+            .map_err(|e| format!("Lock error: {e}"))?;
+        Ok(entries.get(key).cloned())
+    }
+
+    fn set(&self, key: &str, value: &str) -> Result<(), String> {
+        let mut entries = self
+            .entries
+            .lock()
+            .map_err(|e| format!("Lock error: {e}"))?;
+        entries.insert(key.to_string(), value.to_string());
+        Ok(())
+    }
+
+    fn delete(&self, key: &str) -> Result<(), String> {
+        let mut entries = self
+            .entries
+            .lock()
+            .map_err(|e| format!("Lock error: {e}"))?;
+        entries.remove(key);
+        Ok(())
+    }
+}
+
+/// Stand-in when neither backend can be opened. Nothing is read or written,
+/// so secrets stay where they are instead of going to memory and being lost
+/// on exit.
+pub struct UnavailableStore {
+    reason: String,
+}
+
+impl UnavailableStore {
+    pub fn new(reason: &str) -> Self {
+        Self {
+            reason: reason.to_string(),
+        }
+    }
+}
+
+impl SecretStore for UnavailableStore {
+    fn name(&self) -> &'static str {
+        "no secret store"
+    }
+
+    fn get(&self, _key: &str) -> Result<Option<String>, String> {
+        Err(self.reason.clone())
+    }
+
+    fn set(&self, _key: &str, _value: &str) -> Result<(), String> {
+        Err(self.reason.clone())
+    }
+
+    fn delete(&self, _key: &str) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// Pick the backend for this machine: the OS keyring when reachable,
+/// otherwise the encrypted file in `config_dir`, otherwise a store that
+/// refuses secrets.
+pub fn open_default_store(config_dir: &Path) -> Box<dyn SecretStore> {
+    let force_file = std::env::var(BACKEND_ENV).is_ok_and(|value| value == "file");
+    if !force_file {
+        let keyring = KeyringStore::new(KEYRING_SERVICE);
+        if keyring.is_available() {
+            return Box::new(keyring);
+        }
+    }
+
+    let source = match std::env::var(PASSPHRASE_ENV) {
+        Ok(passphrase) if !passphrase.is_empty() => KeySource::Passphrase(passphrase),
+        _ => KeySource::Machine,
+    };
+    match EncryptedFileStore::open(&config_dir.join(SECRETS_FILE), &source) {
+        Ok(store) => Box::new(store),
+        Err(error) => {
+            eprintln!("encrypted secret store unavailable: {error}");
+            Box::new(UnavailableStore::new(&format!(
+                "Encrypted secret store unavailable: {error}"
+            )))
+        }
+    }
+}
+
+fn read_secrets_file(path: &Path) -> Result<Option<SecretsFile>, String> {
+    match std::fs::read_to_string(path) {
+        Ok(json) => serde_json::from_str(&json)
+            .map(Some)
+            .map_err(|error| format!("Corrupt secrets file {}: {error}", path.display())),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(error) => Err(format!("Read error: {error}")),
+    }
+}
+
+fn empty_secrets_file(salt: &[u8; SALT_LEN]) -> SecretsFile {
+    SecretsFile {
+        version: FILE_VERSION,
+        kdf: "argon2id".into(),
+        salt: BASE64.encode(salt),
+        entries: BTreeMap::new(),
+    }
+}
+
+/// Write an empty file with a fresh salt unless one appeared meanwhile, in
+/// which case its salt wins. Returns the salt the file ends up with.
+fn create_secrets_file(path: &Path) -> Result<[u8; SALT_LEN], String> {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let json = serde_json::to_string_pretty(&empty_secrets_file(&salt))
+        .map_err(|error| format!("Serialize error: {error}"))?;
+    match config_file::create_private_with(path, json.as_bytes()) {
+        Ok(true) => Ok(salt),
+        Ok(false) => match read_secrets_file(path)? {
+            Some(file) => decode_salt(&file.salt),
+            None => Err(format!("Secrets file {} vanished", path.display())),
+        },
+        Err(error) => Err(error),
+    }
+}
+
+fn derive_cipher(secret: &str, salt: &[u8; SALT_LEN]) -> Result<ChaCha20Poly1305, String> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(secret.as_bytes(), salt, &mut key)
+        .map_err(|error| format!("Key derivation failed: {error}"))?;
+    Ok(ChaCha20Poly1305::new(Key::from_slice(&key)))
+}
+
+fn decode_salt(encoded: &str) -> Result<[u8; SALT_LEN], String> {
+    BASE64
+        .decode(encoded)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| "Corrupt secrets file salt".to_string())
+}
+
+/// Stable per-machine, per-user secret used when no passphrase is set.
+fn machine_secret() -> Result<String, String> {
+    let user = std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_default();
+    Ok(format!("macclaw:{}:{user}", machine_id()?))
+}
+
+#[cfg(target_os = "macos")]
+fn machine_id() -> Result<String, String> {
+    let output = std::process::Command::new("/usr/sbin/ioreg")
+        .args(["-rd1", "-c", "IOPlatformExpertDevice"])
+        .output()
+        .map_err(|error| format!("failed to run ioreg: {error}"))?;
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find(|line| line.contains("IOPlatformUUID"))
+        .and_then(|line| line.split('"').nth(3))
+        .map(str::to_string)
+        .ok_or_else(|| "IOPlatformUUID not found".to_string())
+}
+
+#[cfg(not(target_os = "macos"))]
+fn machine_id() -> Result<String, String> {
+    ["/etc/machine-id", "/var/lib/dbus/machine-id"]
+        .iter()
+        .filter_map(|path| std::fs::read_to_string(path).ok())
+        .map(|id| id.trim().to_string())
+        .find(|id| !id.is_empty())
+        .ok_or_else(|| "machine-id not found".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn passphrase(value: &str) -> KeySource {
+        KeySource::Passphrase(value.into())
+    }
+
+    #[test]
+    fn memory_store_round_trips() {
+        let store = MemoryStore::default();
+        store.set("token", "abc").unwrap();
+        assert_eq!(store.get("token").unwrap(), Some("abc".into()));
+        store.delete("token").unwrap();
+        assert_eq!(store.get("token").unwrap(), None);
+    }
+
+    #[test]
+    fn encrypted_file_persists_across_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(SECRETS_FILE);
+
+        let store = EncryptedFileStore::open(&path, &passphrase("correct horse")).unwrap();
+        store.set("token", "gateway-token-123").unwrap();
+        drop(store);
+
+        let reopened = EncryptedFileStore::open(&path, &passphrase("correct horse")).unwrap();
+        assert_eq!(
+            reopened.get("token").unwrap(),
+            Some("gateway-token-123".into())
+        );
+        assert_eq!(reopened.get("password").unwrap(), None);
+    }
+
+    #[test]
+    fn encrypted_file_never_contains_plaintext() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(SECRETS_FILE);
+
+        let store = EncryptedFileStore::open(&path, &passphrase("pw")).unwrap();
+        store.set("token", "super-secret-value").unwrap();
+
+        let raw = std::fs::read_to_string(&path).unwrap();
+        assert!(!raw.contains("super-secret-value"));
+        assert!(raw.contains("argon2id"));
+    }
+
+    #[test]
+    fn wrong_passphrase_cannot_decrypt() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(SECRETS_FILE);
+
+        EncryptedFileStore::open(&path, &passphrase("right"))
+            .unwrap()
+            .set("token", "abc")
+            .unwrap();
+
+        let wrong = EncryptedFileStore::open(&path, &passphrase("wrong")).unwrap();
+        assert!(wrong.get("token").is_err());
+    }
+
+    #[test]
+    fn entries_cannot_be_swapped() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(SECRETS_FILE);
+        let store = EncryptedFileStore::open(&path, &passphrase("pw")).unwrap();
+        store.set("token", "t").unwrap();
+        store.set("password", "p").unwrap();
+
+        let mut file = read_secrets_file(&path).unwrap().unwrap();
+        let token = file.entries["token"].clone();
+        file.entries.insert("password".into(), token);
+        std::fs::write(&path, serde_json::to_string(&file).unwrap()).unwrap();
+
+        assert!(store.get("password").is_err());
+    }
+
+    #[test]
+    fn stores_opened_before_the_first_write_share_the_salt() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(SECRETS_FILE);
+        let first = EncryptedFileStore::open(&path, &passphrase("pw")).unwrap();
+        let second = EncryptedFileStore::open(&path, &passphrase("pw")).unwrap();
+
+        first.set("token", "t").unwrap();
+        second.set("password", "p").unwrap();
+
+        let reopened = EncryptedFileStore::open(&path, &passphrase("pw")).unwrap();
+        assert_eq!(reopened.get("token").unwrap(), Some("t".into()));
+        assert_eq!(reopened.get("password").unwrap(), Some("p".into()));
+    }
+
+    #[test]
+    fn recreated_file_is_read_with_its_own_salt() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(SECRETS_FILE);
+        let stale = EncryptedFileStore::open(&path, &passphrase("pw")).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        EncryptedFileStore::open(&path, &passphrase("pw"))
+            .unwrap()
+            .set("token", "abc")
+            .unwrap();
+
+        assert_eq!(stale.get("token").unwrap(), Some("abc".into()));
+        stale.set("password", "p").unwrap();
+        let reopened = EncryptedFileStore::open(&path, &passphrase("pw")).unwrap();
+        assert_eq!(reopened.get("password").unwrap(), Some("p".into()));
+    }
+
+    #[test]
+    fn delete_removes_entry() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(SECRETS_FILE);
+        let store = EncryptedFileStore::open(&path, &passphrase("pw")).unwrap();
+        store.set("token", "abc").unwrap();
+        store.delete("token").unwrap();
+        assert_eq!(store.get("token").unwrap(), None);
+    }
+
+    #[test]
+    fn machine_key_round_trips() {
+        if machine_secret().is_err() {
+            return; // No machine id in this sandbox.
+        }
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(SECRETS_FILE);
+        EncryptedFileStore::open(&path, &KeySource::Machine)
+            .unwrap()
+            .set("token", "abc")
+            .unwrap();
+        let reopened = EncryptedFileStore::open(&path, &KeySource::Machine).unwrap();
+        assert_eq!(reopened.get("token").unwrap(), Some("abc".into()));
+    }
+}
@@ -1,9 +1,13 @@
+use crate::clipboard::{self, Clipboard};
+use crate::source_app::{AppPrompt, SourceApp};
+#[cfg(target_os = "macos")]
 use accessibility_sys::{
     error_string, kAXErrorAttributeUnsupported, kAXErrorNoValue, kAXErrorSuccess,
     kAXFocusedUIElementAttribute, kAXSelectedTextAttribute, kAXTrustedCheckOptionPrompt, AXError,
     AXIsProcessTrustedWithOptions, AXUIElementCopyAttributeValue, AXUIElementCreateSystemWide,
     AXUIElementRef,
 };
+#[cfg(target_os = "macos")]
 use core_foundation::{
     base::{CFType, CFTypeRef, TCFType},
     boolean::CFBoolean,
@@ -15,61 +19,463 @@ use core_graphics::{
     event::{CGEvent, CGEventFlags, CGEventTapLocation, CGKeyCode},
     event_source::{CGEventSource, CGEventSourceStateID},
 };
-use serde::Serialize;
-use std::{
-    io::Write,
-    process::{Command, Stdio},
-    ptr, thread,
-    time::{Duration, Instant},
-};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+#[cfg(target_os = "macos")]
+use std::ptr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, Once, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant};
 
-pub const SELECTION_SHORTCUT: &str = "CmdOrCtrl+Shift+L";
-pub const MAX_SELECTION_CHARS: usize = 12_000;
+pub const DEFAULT_MAX_SELECTION_CHARS: usize = 12_000;
+/// Bounds for the configurable limit; the text goes into a single prompt.
+const MIN_SELECTION_CHARS: usize = 200;
+const MAX_SELECTION_CHARS: usize = 100_000;
 const CLIPBOARD_FALLBACK_WAIT_MS: u64 = 420;
 const CLIPBOARD_FALLBACK_POLL_MS: u64 = 35;
+/// Upper bound for the configurable copy wait; the shortcut handler blocks
+/// while it runs.
+const MAX_COPY_WAIT_MS: u64 = 5_000;
+const DEFAULT_CLIPBOARD_HISTORY: usize = 5;
+const MAX_CLIPBOARD_HISTORY: usize = 50;
+/// How often the clipboard history looks for a new copy.
+const CLIPBOARD_WATCH_MS: u64 = 750;
+/// Longest pause when every look means reading the clipboard (Linux
+/// without copy notifications); it doubles while nothing is copied.
+const MAX_CLIPBOARD_WATCH_MS: u64 = 6_000;
 
+#[cfg(target_os = "macos")]
 const ACCESSIBILITY_PERMISSION_ERROR: &str = "Grant Accessibility permission to MacClaw and retry";
 #[cfg(target_os = "macos")]
 const KEYCODE_C: CGKeyCode = 8;
 
+static CLIPBOARD_HISTORY: Mutex<ClipboardHistory> = Mutex::new(ClipboardHistory::new());
+static WATCHING_CLIPBOARD: AtomicBool = AtomicBool::new(false);
+
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SelectionPrefillPayload {
     pub text: String,
     pub has_text: bool,
     pub error: Option<String>,
+    /// Strategy that produced `text`.
+    pub source: Option<SelectionStrategy>,
+    /// Secrets replaced with placeholders in `text`.
+    pub redacted: usize,
+    /// `text` was cut down to the configured limit.
+    pub truncated: bool,
+    /// Length of the selection before truncation.
+    pub original_chars: usize,
+    /// App in front when the shortcut fired.
+    pub app: Option<SourceApp>,
+    /// Configured prompt for that app.
+    pub app_prompt: Option<String>,
+}
+
+/// Ways to find the selected text, tried in the configured order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SelectionStrategy {
+    /// Selected text of the focused element (macOS Accessibility API).
+    Accessibility,
+    /// Press Cmd+C, read the clipboard, then put the old contents back
+    /// (macOS).
+    SimulatedCopy,
+    /// Whatever is on the clipboard right now.
+    Clipboard,
+    /// X11 / Wayland primary selection (Linux).
+    PrimarySelection,
+    /// The last texts copied while MacClaw ran, newest first.
+    ClipboardHistory,
+}
+
+/// Which part of an oversized selection is kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TruncationMode {
+    /// The beginning.
+    Head,
+    /// The end, where logs and stack traces usually matter.
+    Tail,
+    /// Both ends around a marker saying how much was left out.
+    HeadTail,
 }
 
-pub fn capture_selected_text() -> Result<String, String> {
-    #[cfg(target_os = "macos")]
-    {
-        capture_selected_text_macos()
+/// Per-user capture settings, saved with the other app-wide settings.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct SelectionSettings {
+    pub strategies: Vec<SelectionStrategy>,
+    /// How long simulated copy waits for the clipboard to change.
+    pub copy_wait_ms: u64,
+    /// How often simulated copy checks the clipboard while waiting.
+    pub copy_poll_ms: u64,
+    /// Entries used by [`SelectionStrategy::ClipboardHistory`].
+    pub clipboard_history: usize,
+    /// Longest selection sent as is, in characters.
+    pub max_chars: usize,
+    pub truncation: TruncationMode,
+    /// Cut at line breaks when one is close to the limit.
+    pub truncate_at_lines: bool,
+    /// Prompts prefilled for selections from specific apps.
+    pub app_prompts: Vec<AppPrompt>,
+}
+
+impl Default for SelectionSettings {
+    fn default() -> Self {
+        Self {
+            strategies: default_strategies(),
+            copy_wait_ms: CLIPBOARD_FALLBACK_WAIT_MS,
+            copy_poll_ms: CLIPBOARD_FALLBACK_POLL_MS,
+            clipboard_history: DEFAULT_CLIPBOARD_HISTORY,
+            max_chars: DEFAULT_MAX_SELECTION_CHARS,
+            truncation: TruncationMode::HeadTail,
+            truncate_at_lines: true,
+            app_prompts: Vec::new(),
+        }
     }
+}
 
-    #[cfg(not(target_os = "macos"))]
-    {
-        Err("Selected text capture is available only on macOS".to_string())
+impl SelectionSettings {
+    fn copy_wait(&self) -> Duration {
+        Duration::from_millis(self.copy_wait_ms.min(MAX_COPY_WAIT_MS))
+    }
+
+    fn copy_poll(&self) -> Duration {
+        Duration::from_millis(self.copy_poll_ms.clamp(1, MAX_COPY_WAIT_MS))
+    }
+
+    fn clipboard_history_len(&self) -> usize {
+        self.clipboard_history.clamp(1, MAX_CLIPBOARD_HISTORY)
+    }
+
+    fn max_chars(&self) -> usize {
+        self.max_chars
+            .clamp(MIN_SELECTION_CHARS, MAX_SELECTION_CHARS)
+    }
+
+    /// Cuts `text` down to the configured limit and mode.
+    pub fn truncate(&self, text: &str) -> Truncated {
+        truncate(
+            text,
+            self.max_chars(),
+            self.truncation,
+            self.truncate_at_lines,
+        )
     }
 }
 
-#[cfg(target_os = "macos")]
-fn capture_selected_text_macos() -> Result<String, String> {
-    ensure_accessibility_permission()?;
+pub fn default_strategies() -> Vec<SelectionStrategy> {
+    if cfg!(target_os = "macos") {
+        vec![
+            SelectionStrategy::Accessibility,
+            SelectionStrategy::SimulatedCopy,
+        ]
+    } else {
+        vec![SelectionStrategy::PrimarySelection]
+    }
+}
+
+/// One step of the capture chain.
+pub trait SelectionSource {
+    fn strategy(&self) -> SelectionStrategy;
 
-    let selected_text = capture_selected_text_via_accessibility()?;
-    if !selected_text.is_empty() {
-        return Ok(selected_text);
+    /// The selected text as this source sees it; empty when it has none.
+    /// Trimming happens in [`capture_with`], truncation in
+    /// [`SelectionSettings::truncate`].
+    fn read(&self) -> Result<String, String>;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapturedSelection {
+    pub text: String,
+    /// `None` when no source had any text.
+    pub source: Option<SelectionStrategy>,
+}
+
+/// Capture with the strategies from `settings` that this platform supports.
+pub fn capture_selected_text(settings: &SelectionSettings) -> Result<CapturedSelection, String> {
+    let sources = build_sources(settings);
+    if sources.is_empty() {
+        return Err("No selection capture strategy is available on this platform".to_string());
     }
+    capture_with(&sources)
+}
 
-    let fallback = match capture_selected_text_via_clipboard() {
-        Ok(value) => value,
-        Err(error) => {
-            eprintln!("clipboard fallback failed: {error}");
-            String::new()
+/// First non-empty text from `sources`, in order. A failing source does not
+/// stop the chain; its error is reported only if no later source has text.
+pub fn capture_with(sources: &[Box<dyn SelectionSource>]) -> Result<CapturedSelection, String> {
+    let mut first_error = None;
+    for source in sources {
+        match source.read() {
+            Ok(text) => {
+                let text = text.trim();
+                if !text.is_empty() {
+                    return Ok(CapturedSelection {
+                        text: text.to_string(),
+                        source: Some(source.strategy()),
+                    });
+                }
+            }
+            Err(error) => {
+                eprintln!("selection source {:?} failed: {error}", source.strategy());
+                first_error.get_or_insert(error);
+            }
         }
-    };
+    }
+
+    match first_error {
+        Some(error) => Err(error),
+        None => Ok(CapturedSelection {
+            text: String::new(),
+            source: None,
+        }),
+    }
+}
+
+/// Sources for the configured strategies, skipping duplicates and the ones
+/// this platform cannot provide.
+fn build_sources(settings: &SelectionSettings) -> Vec<Box<dyn SelectionSource>> {
+    let mut seen = Vec::new();
+    let mut sources = Vec::new();
+    for &strategy in &settings.strategies {
+        if seen.contains(&strategy) {
+            continue;
+        }
+        seen.push(strategy);
+        match platform_source(strategy, settings) {
+            Some(source) => sources.push(source),
+            None => eprintln!("selection strategy {strategy:?} is not supported here"),
+        }
+    }
+    sources
+}
+
+fn platform_source(
+    strategy: SelectionStrategy,
+    settings: &SelectionSettings,
+) -> Option<Box<dyn SelectionSource>> {
+    match strategy {
+        SelectionStrategy::Clipboard => Some(Box::new(ClipboardSource)),
+        SelectionStrategy::ClipboardHistory => Some(Box::new(ClipboardHistorySource {
+            limit: settings.clipboard_history_len(),
+        })),
+        #[cfg(target_os = "macos")]
+        SelectionStrategy::Accessibility => Some(Box::new(AccessibilitySource)),
+        #[cfg(target_os = "macos")]
+        SelectionStrategy::SimulatedCopy => Some(Box::new(SimulatedCopySource {
+            wait: settings.copy_wait(),
+            poll: settings.copy_poll(),
+        })),
+        #[cfg(all(unix, not(target_os = "macos")))]
+        SelectionStrategy::PrimarySelection => Some(Box::new(PrimarySelectionSource)),
+        #[allow(unreachable_patterns)]
+        _ => None,
+    }
+}
+
+/// Distinct clipboard texts, newest first.
+struct ClipboardHistory {
+    entries: VecDeque<String>,
+}
+
+impl ClipboardHistory {
+    const fn new() -> Self {
+        Self {
+            entries: VecDeque::new(),
+        }
+    }
+
+    fn push(&mut self, text: &str) {
+        let text = text.trim();
+        if text.is_empty() {
+            return;
+        }
+        self.entries.retain(|entry| entry != text);
+        self.entries.push_front(text.to_string());
+        self.entries.truncate(MAX_CLIPBOARD_HISTORY);
+    }
+
+    /// The newest `limit` entries separated by blank lines.
+    fn joined(&self, limit: usize) -> String {
+        let recent: Vec<&str> = self
+            .entries
+            .iter()
+            .take(limit)
+            .map(String::as_str)
+            .collect();
+        recent.join("\n\n")
+    }
+}
+
+fn remember_clipboard(text: &str) {
+    CLIPBOARD_HISTORY
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .push(text);
+}
+
+/// Record every text copied while `settings` use
+/// [`SelectionStrategy::ClipboardHistory`]. Call again when they change.
+pub fn watch_clipboard(settings: &SelectionSettings) {
+    static STARTED: Once = Once::new();
+    let enabled = settings
+        .strategies
+        .contains(&SelectionStrategy::ClipboardHistory);
+    WATCHING_CLIPBOARD.store(enabled, Ordering::SeqCst);
+    if enabled {
+        STARTED.call_once(|| {
+            thread::spawn(watch_clipboard_loop);
+        });
+    }
+}
+
+fn watch_clipboard_loop() {
+    let clipboard = clipboard::system();
+    let mut seen = SeenCopy::default();
+    let mut wait = CLIPBOARD_WATCH_MS;
+    loop {
+        thread::sleep(Duration::from_millis(wait));
+        if !WATCHING_CLIPBOARD.load(Ordering::SeqCst) {
+            continue;
+        }
+        match new_copy(clipboard.as_ref(), &mut seen) {
+            Ok(Some(text)) => {
+                remember_clipboard(&text);
+                wait = CLIPBOARD_WATCH_MS;
+            }
+            Ok(None) if seen.count.is_none() => {
+                wait = (wait * 2).min(MAX_CLIPBOARD_WATCH_MS);
+            }
+            Ok(None) => {}
+            Err(error) => {
+                eprintln!("clipboard history stopped: {error}");
+                WATCHING_CLIPBOARD.store(false, Ordering::SeqCst);
+            }
+        }
+    }
+}
 
-    Ok(normalize_selection_text(&fallback))
+/// Last copy the clipboard watcher looked at.
+#[derive(Debug, Default)]
+struct SeenCopy {
+    /// Change count, when the platform keeps one.
+    count: Option<i64>,
+    /// Text, compared instead when it does not.
+    text: Option<String>,
+}
+
+/// Text copied since `seen`, unless the owner concealed it. Without a change
+/// count the text is read every time and compared with the last one.
+fn new_copy(clipboard: &dyn Clipboard, seen: &mut SeenCopy) -> Result<Option<String>, String> {
+    let count = clipboard.change_count();
+    if count.is_some() && count == seen.count {
+        return Ok(None);
+    }
+    seen.count = count;
+    if clipboard.is_concealed() {
+        return Ok(None);
+    }
+    let text = clipboard.read_text()?;
+    if count.is_none() && text == seen.text {
+        return Ok(None);
+    }
+    seen.text.clone_from(&text);
+    Ok(text)
+}
+
+struct ClipboardSource;
+
+impl SelectionSource for ClipboardSource {
+    fn strategy(&self) -> SelectionStrategy {
+        SelectionStrategy::Clipboard
+    }
+
+    fn read(&self) -> Result<String, String> {
+        let clipboard = clipboard::system();
+        let text = clipboard.read_text()?.unwrap_or_default();
+        if !clipboard.is_concealed() {
+            remember_clipboard(&text);
+        }
+        Ok(text)
+    }
+}
+
+/// Entries are collected by [`watch_clipboard`] and whenever MacClaw reads
+/// the clipboard, including right before this source returns them.
+struct ClipboardHistorySource {
+    limit: usize,
+}
+
+impl SelectionSource for ClipboardHistorySource {
+    fn strategy(&self) -> SelectionStrategy {
+        SelectionStrategy::ClipboardHistory
+    }
+
+    fn read(&self) -> Result<String, String> {
+        let clipboard = clipboard::system();
+        if let Some(text) = new_copy(clipboard.as_ref(), &mut SeenCopy::default())? {
+            remember_clipboard(&text);
+        }
+        Ok(CLIPBOARD_HISTORY
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .joined(self.limit))
+    }
+}
+
+#[cfg(target_os = "macos")]
+struct AccessibilitySource;
+
+#[cfg(target_os = "macos")]
+impl SelectionSource for AccessibilitySource {
+    fn strategy(&self) -> SelectionStrategy {
+        SelectionStrategy::Accessibility
+    }
+
+    fn read(&self) -> Result<String, String> {
+        ensure_accessibility_permission()?;
+        capture_selected_text_via_accessibility()
+    }
+}
+
+#[cfg(target_os = "macos")]
+struct SimulatedCopySource {
+    wait: Duration,
+    poll: Duration,
+}
+
+#[cfg(target_os = "macos")]
+impl SelectionSource for SimulatedCopySource {
+    fn strategy(&self) -> SelectionStrategy {
+        SelectionStrategy::SimulatedCopy
+    }
+
+    fn read(&self) -> Result<String, String> {
+        // Posting key events needs the same permission.
+        ensure_accessibility_permission()?;
+        copy_selection_with(
+            clipboard::system().as_ref(),
+            trigger_copy_shortcut,
+            self.wait,
+            self.poll,
+        )
+    }
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+struct PrimarySelectionSource;
+
+#[cfg(all(unix, not(target_os = "macos")))]
+impl SelectionSource for PrimarySelectionSource {
+    fn strategy(&self) -> SelectionStrategy {
+        SelectionStrategy::PrimarySelection
+    }
+
+    fn read(&self) -> Result<String, String> {
+        clipboard::read_primary_selection()
+    }
 }
 
 #[cfg(target_os = "macos")]
@@ -92,110 +498,35 @@ fn capture_selected_text_via_accessibility() -> Result<String, String> {
         return Ok(String::new());
     };
 
-    Ok(normalize_selection_text(&selected_text))
+    Ok(selected_text)
 }
 
 #[cfg(target_os = "macos")]
-fn capture_selected_text_via_clipboard() -> Result<String, String> {
-    let previous_clipboard = read_clipboard_text().unwrap_or(None);
-
-    let captured_result = (|| -> Result<String, String> {
-        trigger_copy_shortcut()?;
-
-        let wait_deadline = Instant::now() + Duration::from_millis(CLIPBOARD_FALLBACK_WAIT_MS);
-        let mut latest = String::new();
-
-        while Instant::now() < wait_deadline {
-            latest = read_clipboard_text().unwrap_or(None).unwrap_or_default();
-            let changed = match previous_clipboard.as_ref() {
-                Some(previous_text) => latest != *previous_text,
-                None => !latest.is_empty(),
-            };
-            if changed {
-                break;
-            }
-            thread::sleep(Duration::from_millis(CLIPBOARD_FALLBACK_POLL_MS));
-        }
-
-        let captured = if latest.is_empty() {
-            read_clipboard_text().unwrap_or(None).unwrap_or_default()
-        } else {
-            latest
-        };
-
-        let unchanged = previous_clipboard
-            .as_ref()
-            .is_some_and(|previous_text| captured == *previous_text);
-        if captured.trim().is_empty() || unchanged {
-            return Ok(String::new());
-        }
-
-        Ok(captured)
-    })();
-
-    if let Some(previous_text) = previous_clipboard {
-        let _ = write_clipboard_text(&previous_text);
-    }
-    captured_result
+fn trigger_copy_shortcut() -> Result<(), String> {
+    press_key(KEYCODE_C, CGEventFlags::CGEventFlagCommand)
 }
 
+/// Posts a key press, with `flags` held, to the focused app.
 #[cfg(target_os = "macos")]
-fn trigger_copy_shortcut() -> Result<(), String> {
+pub(crate) fn press_key(keycode: CGKeyCode, flags: CGEventFlags) -> Result<(), String> {
     let source = CGEventSource::new(CGEventSourceStateID::CombinedSessionState)
         .map_err(|_| "failed to create keyboard event source".to_string())?;
 
-    let key_down = CGEvent::new_keyboard_event(source.clone(), KEYCODE_C, true)
+    let key_down = CGEvent::new_keyboard_event(source.clone(), keycode, true)
         .map_err(|_| "failed to create key-down event".to_string())?;
-    key_down.set_flags(CGEventFlags::CGEventFlagCommand);
+    key_down.set_flags(flags);
     key_down.post(CGEventTapLocation::HID);
 
     thread::sleep(Duration::from_millis(10));
 
-    let key_up = CGEvent::new_keyboard_event(source, KEYCODE_C, false)
+    let key_up = CGEvent::new_keyboard_event(source, keycode, false)
         .map_err(|_| "failed to create key-up event".to_string())?;
-    key_up.set_flags(CGEventFlags::CGEventFlagCommand);
+    key_up.set_flags(flags);
     key_up.post(CGEventTapLocation::HID);
 
     Ok(())
 }
 
-#[cfg(target_os = "macos")]
-fn read_clipboard_text() -> Result<Option<String>, String> {
-    let output = Command::new("/usr/bin/pbpaste")
-        .output()
-        .map_err(|error| format!("failed to read clipboard: {error}"))?;
-
-    if output.status.success() {
-        return Ok(Some(String::from_utf8_lossy(&output.stdout).to_string()));
-    }
-
-    // No textual clipboard format (e.g. image/file) or empty clipboard.
-    Ok(None)
-}
-
-#[cfg(target_os = "macos")]
-fn write_clipboard_text(value: &str) -> Result<(), String> {
-    let mut child = Command::new("/usr/bin/pbcopy")
-        .stdin(Stdio::piped())
-        .spawn()
-        .map_err(|error| format!("failed to spawn pbcopy: {error}"))?;
-
-    if let Some(stdin) = child.stdin.as_mut() {
-        stdin
-            .write_all(value.as_bytes())
-            .map_err(|error| format!("failed to write clipboard: {error}"))?;
-    }
-
-    let status = child
-        .wait()
-        .map_err(|error| format!("failed waiting pbcopy: {error}"))?;
-    if status.success() {
-        Ok(())
-    } else {
-        Err("pbcopy exited with non-zero status".to_string())
-    }
-}
-
 #[cfg(target_os = "macos")]
 fn ensure_accessibility_permission() -> Result<(), String> {
     let prompt_key = unsafe { CFString::wrap_under_get_rule(kAXTrustedCheckOptionPrompt) };
@@ -211,7 +542,7 @@ fn ensure_accessibility_permission() -> Result<(), String> {
 }
 
 #[cfg(target_os = "macos")]
-fn copy_attribute_ui_element(
+pub(crate) fn copy_attribute_ui_element(
     element: AXUIElementRef,
     attribute: &'static str,
 ) -> Result<Option<AXUIElementRef>, String> {
@@ -223,7 +554,7 @@ fn copy_attribute_ui_element(
 }
 
 #[cfg(target_os = "macos")]
-fn copy_attribute_string(
+pub(crate) fn copy_attribute_string(
     element: AXUIElementRef,
     attribute: &'static str,
 ) -> Result<Option<String>, String> {
@@ -240,7 +571,7 @@ fn copy_attribute_string(
 }
 
 #[cfg(target_os = "macos")]
-fn copy_attribute_value(
+pub(crate) fn copy_attribute_value(
     element: AXUIElementRef,
     attribute: &'static str,
 ) -> Result<Option<CFTypeRef>, String> {
@@ -268,15 +599,427 @@ fn copy_attribute_value(
     Err(format!("{} ({error})", error_string(error)))
 }
 
-fn normalize_selection_text(value: &str) -> String {
-    let trimmed = value.trim();
-    if trimmed.is_empty() {
-        return String::new();
+/// Copies the selection by running `press_copy` and waiting up to `wait`
+/// for the clipboard text to change. Everything that was on the clipboard
+/// before, not just its text, is put back afterwards.
+pub fn copy_selection_with(
+    clipboard: &dyn Clipboard,
+    press_copy: impl FnOnce() -> Result<(), String>,
+    wait: Duration,
+    poll: Duration,
+) -> Result<String, String> {
+    // Copying without a snapshot would lose what the user had copied.
+    let saved = clipboard
+        .snapshot()
+        .map_err(|e| format!("failed to save clipboard: {e}"))?;
+    let previous_clipboard = clipboard.read_text().unwrap_or(None);
+    if let Some(previous_text) = &previous_clipboard {
+        remember_clipboard(previous_text);
     }
 
-    if trimmed.chars().count() <= MAX_SELECTION_CHARS {
-        return trimmed.to_string();
+    let captured_result = (|| -> Result<String, String> {
+        press_copy()?;
+
+        let wait_deadline = Instant::now() + wait;
+        let mut latest = String::new();
+
+        while Instant::now() < wait_deadline {
+            latest = clipboard.read_text().unwrap_or(None).unwrap_or_default();
+            let changed = match previous_clipboard.as_ref() {
+                Some(previous_text) => latest != *previous_text,
+                None => !latest.is_empty(),
+            };
+            if changed {
+                break;
+            }
+            thread::sleep(poll);
+        }
+
+        let captured = if latest.is_empty() {
+            clipboard.read_text().unwrap_or(None).unwrap_or_default()
+        } else {
+            latest
+        };
+
+        let unchanged = previous_clipboard
+            .as_ref()
+            .is_some_and(|previous_text| captured == *previous_text);
+        if captured.trim().is_empty() || unchanged {
+            return Ok(String::new());
+        }
+
+        Ok(captured)
+    })();
+
+    if let Err(e) = clipboard.restore(&saved) {
+        eprintln!("failed to restore clipboard: {e}");
+    }
+    captured_result
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Truncated {
+    pub text: String,
+    pub truncated: bool,
+    pub original_chars: usize,
+}
+
+/// Keeps at most `limit` characters of `text` (marker included) according
+/// to `mode`. With `at_lines` a cut moves to a nearby line break, giving up
+/// at most half of the budget.
+pub fn truncate(text: &str, limit: usize, mode: TruncationMode, at_lines: bool) -> Truncated {
+    let original_chars = text.chars().count();
+    if original_chars <= limit {
+        return Truncated {
+            text: text.to_string(),
+            truncated: false,
+            original_chars,
+        };
+    }
+
+    // The real omitted count has at most as many digits as the total.
+    let marker_chars = omitted_marker(original_chars).chars().count();
+    let text = match mode {
+        TruncationMode::HeadTail if limit > marker_chars + 1 => {
+            let budget = limit - marker_chars;
+            let head = keep_head(text, budget / 2, at_lines);
+            let tail = keep_tail(text, budget - budget / 2, at_lines);
+            let omitted = original_chars - head.chars().count() - tail.chars().count();
+            format!("{head}{}{tail}", omitted_marker(omitted))
+        }
+        TruncationMode::Tail => keep_tail(text, limit, at_lines).to_string(),
+        _ => keep_head(text, limit, at_lines).to_string(),
+    };
+    Truncated {
+        text,
+        truncated: true,
+        original_chars,
+    }
+}
+
+fn omitted_marker(omitted: usize) -> String {
+    format!("\n[… {omitted} characters omitted …]\n")
+}
+
+fn keep_head(text: &str, budget: usize, at_lines: bool) -> &str {
+    let end = text
+        .char_indices()
+        .nth(budget)
+        .map_or(text.len(), |(index, _)| index);
+    let head = &text[..end];
+    if !at_lines || text[end..].starts_with('\n') {
+        return head;
     }
+    match head.rfind('\n') {
+        Some(newline) if head[..newline].chars().count() >= budget / 2 => &head[..newline],
+        _ => head,
+    }
+}
+
+fn keep_tail(text: &str, budget: usize, at_lines: bool) -> &str {
+    let skip = text.chars().count().saturating_sub(budget);
+    let start = text
+        .char_indices()
+        .nth(skip)
+        .map_or(text.len(), |(index, _)| index);
+    let tail = &text[start..];
+    if !at_lines || text[..start].ends_with('\n') {
+        return tail;
+    }
+    match tail.find('\n') {
+        Some(newline) if tail[newline + 1..].chars().count() >= budget / 2 => &tail[newline + 1..],
+        _ => tail,
+    }
+}
 
-    trimmed.chars().take(MAX_SELECTION_CHARS).collect()
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clipboard::{ClipboardItem, ClipboardSnapshot, MemoryClipboard};
+
+    struct FakeSource(SelectionStrategy, Result<String, String>);
+
+    impl SelectionSource for FakeSource {
+        fn strategy(&self) -> SelectionStrategy {
+            self.0
+        }
+
+        fn read(&self) -> Result<String, String> {
+            self.1.clone()
+        }
+    }
+
+    fn chain(sources: &[(SelectionStrategy, Result<&str, &str>)]) -> Vec<Box<dyn SelectionSource>> {
+        sources
+            .iter()
+            .map(|(strategy, result)| {
+                let result = result.map(str::to_string).map_err(str::to_string);
+                Box::new(FakeSource(*strategy, result)) as Box<dyn SelectionSource>
+            })
+            .collect()
+    }
+
+    #[test]
+    fn first_source_with_text_wins() {
+        let sources = chain(&[
+            (SelectionStrategy::Accessibility, Ok("  \n")),
+            (SelectionStrategy::SimulatedCopy, Err("denied")),
+            (SelectionStrategy::Clipboard, Ok("  echo hi\n")),
+            (SelectionStrategy::ClipboardHistory, Ok("older")),
+        ]);
+
+        assert_eq!(
+            capture_with(&sources),
+            Ok(CapturedSelection {
+                text: "echo hi".into(),
+                source: Some(SelectionStrategy::Clipboard),
+            })
+        );
+    }
+
+    #[test]
+    fn errors_surface_only_without_text() {
+        let sources = chain(&[
+            (SelectionStrategy::Accessibility, Err("grant access")),
+            (SelectionStrategy::SimulatedCopy, Err("denied")),
+            (SelectionStrategy::Clipboard, Ok("")),
+        ]);
+        assert_eq!(capture_with(&sources), Err("grant access".to_string()));
+
+        let empty = chain(&[(SelectionStrategy::Clipboard, Ok(" "))]);
+        assert_eq!(
+            capture_with(&empty),
+            Ok(CapturedSelection {
+                text: String::new(),
+                source: None,
+            })
+        );
+    }
+
+    fn lines(count: usize) -> String {
+        (1..=count)
+            .map(|line| format!("line {line:02}"))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn truncation_keeps_the_chosen_end() {
+        let text = "é".repeat(50);
+        for mode in [TruncationMode::Head, TruncationMode::Tail] {
+            let cut = truncate(&text, 20, mode, true);
+            assert_eq!(cut.text, "é".repeat(20));
+            assert!(cut.truncated);
+            assert_eq!(cut.original_chars, 50);
+        }
+
+        let short = truncate("short", 20, TruncationMode::Head, true);
+        assert_eq!((short.text.as_str(), short.truncated), ("short", false));
+    }
+
+    #[test]
+    fn truncation_prefers_line_breaks() {
+        // Ten 7-char lines joined by newlines: 79 chars.
+        let text = lines(10);
+
+        let head = truncate(&text, 20, TruncationMode::Head, true);
+        assert_eq!(head.text, "line 01\nline 02");
+        let tail = truncate(&text, 20, TruncationMode::Tail, true);
+        assert_eq!(tail.text, "line 09\nline 10");
+        let raw = truncate(&text, 20, TruncationMode::Tail, false);
+        assert_eq!(raw.text, "e 08\nline 09\nline 10");
+    }
+
+    #[test]
+    fn head_tail_marks_the_gap() {
+        let text = lines(20);
+
+        let cut = truncate(&text, 70, TruncationMode::HeadTail, true);
+
+        assert_eq!(
+            cut.text,
+            "line 01\nline 02\n[… 129 characters omitted …]\nline 19\nline 20"
+        );
+        assert!(cut.text.chars().count() <= 70);
+        assert_eq!(cut.original_chars, 159);
+
+        // Too small for the marker: plain head.
+        let tiny = truncate(&text, 10, TruncationMode::HeadTail, false);
+        assert_eq!(tiny.text, "line 01\nli");
+    }
+
+    #[test]
+    fn capture_trims_without_truncating() {
+        let long = format!("  {}\n", "é".repeat(DEFAULT_MAX_SELECTION_CHARS + 5));
+        let sources = chain(&[(SelectionStrategy::Clipboard, Ok(long.as_str()))]);
+
+        let captured = capture_with(&sources).unwrap();
+        assert_eq!(captured.text, long.trim());
+
+        let settings = SelectionSettings::default();
+        let cut = settings.truncate(&captured.text);
+        assert!(cut.text.chars().count() <= DEFAULT_MAX_SELECTION_CHARS);
+        assert!(cut.text.contains("characters omitted"));
+    }
+
+    #[test]
+    fn settings_default_and_clamp() {
+        let settings: SelectionSettings =
+            serde_json::from_str(r#"{"copyWaitMs": 60000, "copyPollMs": 0}"#).unwrap();
+
+        assert_eq!(settings.strategies, default_strategies());
+        assert_eq!(
+            settings.copy_wait(),
+            Duration::from_millis(MAX_COPY_WAIT_MS)
+        );
+        assert_eq!(settings.copy_poll(), Duration::from_millis(1));
+        assert_eq!(settings.clipboard_history_len(), DEFAULT_CLIPBOARD_HISTORY);
+        assert_eq!(settings.max_chars(), DEFAULT_MAX_SELECTION_CHARS);
+        assert_eq!(settings.truncation, TruncationMode::HeadTail);
+        let tiny = SelectionSettings {
+            max_chars: 1,
+            ..SelectionSettings::default()
+        };
+        assert_eq!(tiny.max_chars(), MIN_SELECTION_CHARS);
+        assert_eq!(
+            serde_json::to_value(SelectionStrategy::PrimarySelection).unwrap(),
+            "primary_selection"
+        );
+    }
+
+    #[test]
+    fn unsupported_and_repeated_strategies_are_skipped() {
+        let settings = SelectionSettings {
+            strategies: vec![
+                SelectionStrategy::Clipboard,
+                SelectionStrategy::Clipboard,
+                SelectionStrategy::ClipboardHistory,
+            ],
+            ..SelectionSettings::default()
+        };
+        let strategies: Vec<SelectionStrategy> = build_sources(&settings)
+            .iter()
+            .map(|source| source.strategy())
+            .collect();
+        assert_eq!(
+            strategies,
+            [
+                SelectionStrategy::Clipboard,
+                SelectionStrategy::ClipboardHistory
+            ]
+        );
+    }
+
+    fn rich_clipboard() -> ClipboardSnapshot {
+        ClipboardSnapshot {
+            items: vec![
+                ClipboardItem {
+                    representations: vec![
+                        ("public.rtf".into(), b"{\\rtf1 hi}".to_vec()),
+                        (MemoryClipboard::TEXT.into(), b"hi".to_vec()),
+                    ],
+                },
+                ClipboardItem {
+                    representations: vec![("public.png".into(), vec![0x89, b'P', b'N', b'G'])],
+                },
+            ],
+        }
+    }
+
+    fn copy(clipboard: &MemoryClipboard, copied: Option<&str>) -> Result<String, String> {
+        copy_selection_with(
+            clipboard,
+            || match copied {
+                Some(text) => clipboard.write_text(text),
+                None => Err("no key events".to_string()),
+            },
+            Duration::from_millis(20),
+            Duration::from_millis(1),
+        )
+    }
+
+    #[test]
+    fn simulated_copy_restores_every_representation() {
+        let clipboard = MemoryClipboard::new(rich_clipboard());
+
+        assert_eq!(copy(&clipboard, Some("selected")).unwrap(), "selected");
+        assert_eq!(clipboard.contents(), rich_clipboard());
+
+        assert_eq!(copy(&clipboard, None), Err("no key events".to_string()));
+        assert_eq!(clipboard.contents(), rich_clipboard());
+    }
+
+    #[test]
+    fn simulated_copy_ignores_unchanged_clipboard() {
+        let clipboard = MemoryClipboard::new(rich_clipboard());
+        assert_eq!(copy(&clipboard, Some("hi")).unwrap(), "");
+
+        let empty = MemoryClipboard::default();
+        assert_eq!(copy(&empty, Some("selected")).unwrap(), "selected");
+        assert!(empty.contents().is_empty());
+    }
+
+    #[test]
+    fn clipboard_history_keeps_distinct_recent_texts() {
+        let mut history = ClipboardHistory::new();
+        for text in ["one", "two", " ", "one\n", "three"] {
+            history.push(text);
+        }
+
+        assert_eq!(history.joined(2), "three\n\none");
+        assert_eq!(history.joined(10), "three\n\none\n\ntwo");
+    }
+
+    #[test]
+    fn watcher_reads_only_new_copies() {
+        let clipboard = MemoryClipboard::default();
+        let mut seen = SeenCopy::default();
+        assert_eq!(new_copy(&clipboard, &mut seen), Ok(None));
+
+        clipboard.write_text("one").unwrap();
+        assert_eq!(new_copy(&clipboard, &mut seen), Ok(Some("one".into())));
+        assert_eq!(new_copy(&clipboard, &mut seen), Ok(None));
+        clipboard.write_text("two").unwrap();
+        assert_eq!(new_copy(&clipboard, &mut seen), Ok(Some("two".into())));
+    }
+
+    /// Clipboard without a change count or with concealed contents.
+    struct PlainClipboard {
+        inner: MemoryClipboard,
+        concealed: bool,
+    }
+
+    impl Clipboard for PlainClipboard {
+        fn read_text(&self) -> Result<Option<String>, String> {
+            self.inner.read_text()
+        }
+        fn write_text(&self, text: &str) -> Result<(), String> {
+            self.inner.write_text(text)
+        }
+        fn snapshot(&self) -> Result<ClipboardSnapshot, String> {
+            self.inner.snapshot()
+        }
+        fn restore(&self, snapshot: &ClipboardSnapshot) -> Result<(), String> {
+            self.inner.restore(snapshot)
+        }
+        fn is_concealed(&self) -> bool {
+            self.concealed
+        }
+    }
+
+    #[test]
+    fn watcher_without_change_count_compares_texts() {
+        let mut clipboard = PlainClipboard {
+            inner: MemoryClipboard::default(),
+            concealed: false,
+        };
+        let mut seen = SeenCopy::default();
+        clipboard.write_text("one").unwrap();
+        assert_eq!(new_copy(&clipboard, &mut seen), Ok(Some("one".into())));
+        assert_eq!(new_copy(&clipboard, &mut seen), Ok(None));
+
+        clipboard.write_text("hunter2").unwrap();
+        clipboard.concealed = true;
+        assert_eq!(new_copy(&clipboard, &mut seen), Ok(None));
+    }
 }
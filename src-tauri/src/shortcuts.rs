@@ -0,0 +1,366 @@
+//! Global shortcut bindings: which accelerator triggers which action.
+//! The OS side lives in `lib.rs` behind [`ShortcutRegistrar`]; this module
+//! holds the data persisted in `Credentials` and the validation/rollback
+//! rules, so both can be tested without Tauri.
+
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+pub const DEFAULT_TOGGLE_SHORTCUTS: [&str; 3] =
+    ["CmdOrCtrl+Shift+Space", "CmdOrCtrl+Shift+K", "Alt+Space"];
+pub const DEFAULT_SELECTION_SHORTCUT: &str = "CmdOrCtrl+Shift+L";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ShortcutAction {
+    /// Show the panel, hide it, or restore a background response.
+    TogglePanel,
+    /// Capture the selected text and open the panel prefilled with it.
+    CaptureSelection,
+    /// Bring back a response that is streaming in the notch.
+    RestoreNotch,
+    /// Activate a named gateway profile and reconnect.
+    OpenProfile { profile: String },
+    /// Send a saved prompt, applied to the current selection if any.
+    RunPrompt { prompt: String },
+    /// Abort the response that is currently streaming.
+    AbortStreaming,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShortcutBinding {
+    pub accelerator: String,
+    pub action: ShortcutAction,
+}
+
+impl ShortcutBinding {
+    pub fn new(accelerator: &str, action: ShortcutAction) -> Self {
+        Self {
+            accelerator: accelerator.to_string(),
+            action,
+        }
+    }
+}
+
+pub fn default_bindings() -> Vec<ShortcutBinding> {
+    DEFAULT_TOGGLE_SHORTCUTS
+        .iter()
+        .map(|accelerator| ShortcutBinding::new(accelerator, ShortcutAction::TogglePanel))
+        .chain([ShortcutBinding::new(
+            DEFAULT_SELECTION_SHORTCUT,
+            ShortcutAction::CaptureSelection,
+        )])
+        .collect()
+}
+
+/// Why a binding is not active.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum BindingProblem {
+    /// The accelerator is not a valid shortcut.
+    Parse { message: String },
+    /// Same key combination as the binding at `index`, which wins.
+    Duplicate { index: usize },
+    /// The OS refused the shortcut, usually because another app owns it.
+    Registration { message: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BindingStatus {
+    pub accelerator: String,
+    pub action: ShortcutAction,
+    pub problem: Option<BindingProblem>,
+}
+
+/// Outcome of applying a shortcut set, one status per requested binding.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShortcutReport {
+    pub bindings: Vec<BindingStatus>,
+    /// The requested set is now active. When false the previous set is.
+    pub applied: bool,
+    /// The requested set was registered, failed, and the previous set was
+    /// registered again.
+    pub rolled_back: bool,
+    pub error: Option<String>,
+}
+
+impl ShortcutReport {
+    fn has_working_toggle(&self) -> bool {
+        self.bindings
+            .iter()
+            .any(|status| status.problem.is_none() && status.action == ShortcutAction::TogglePanel)
+    }
+}
+
+/// OS shortcut registration. `Key` identifies a key combination, so two
+/// accelerators spelled differently but parsing to the same key collide.
+pub trait ShortcutRegistrar {
+    type Key: Eq + Hash + Clone;
+
+    fn parse(&self, accelerator: &str) -> Result<Self::Key, String>;
+    fn unregister_all(&mut self);
+    fn register(&mut self, key: Self::Key, action: ShortcutAction) -> Result<(), String>;
+}
+
+/// Parse every binding and flag duplicates. Returns the statuses and the
+/// keys of the bindings that are worth registering.
+fn validate<R: ShortcutRegistrar>(
+    registrar: &R,
+    bindings: &[ShortcutBinding],
+) -> (Vec<BindingStatus>, Vec<(usize, R::Key)>) {
+    let mut seen: HashMap<R::Key, usize> = HashMap::new();
+    let mut valid = Vec::new();
+    let statuses = bindings
+        .iter()
+        .enumerate()
+        .map(|(index, binding)| {
+            let problem = match registrar.parse(&binding.accelerator) {
+                Err(message) => Some(BindingProblem::Parse { message }),
+                Ok(key) => match seen.entry(key.clone()) {
+                    Entry::Occupied(first) => Some(BindingProblem::Duplicate {
+                        index: *first.get(),
+                    }),
+                    Entry::Vacant(entry) => {
+                        entry.insert(index);
+                        valid.push((index, key));
+                        None
+                    }
+                },
+            };
+            BindingStatus {
+                accelerator: binding.accelerator.clone(),
+                action: binding.action.clone(),
+                problem,
+            }
+        })
+        .collect();
+    (statuses, valid)
+}
+
+fn register_valid<R: ShortcutRegistrar>(
+    registrar: &mut R,
+    bindings: &[ShortcutBinding],
+) -> ShortcutReport {
+    let (mut statuses, valid) = validate(registrar, bindings);
+    for (index, key) in valid {
+        if let Err(message) = registrar.register(key, bindings[index].action.clone()) {
+            statuses[index].problem = Some(BindingProblem::Registration { message });
+        }
+    }
+    ShortcutReport {
+        bindings: statuses,
+        ..ShortcutReport::default()
+    }
+}
+
+/// Replace the registered shortcuts with `bindings`.
+///
+/// A set without a parsable toggle binding is refused before anything is
+/// unregistered. If the OS rejects every toggle binding, `last_good` is
+/// registered again so the panel always stays reachable.
+pub fn apply_bindings<R: ShortcutRegistrar>(
+    registrar: &mut R,
+    bindings: &[ShortcutBinding],
+    last_good: &[ShortcutBinding],
+) -> ShortcutReport {
+    let (statuses, _) = validate(registrar, bindings);
+    let checked = ShortcutReport {
+        bindings: statuses,
+        ..ShortcutReport::default()
+    };
+    if !checked.has_working_toggle() {
+        return ShortcutReport {
+            error: Some("No working toggle panel shortcut; keeping the current shortcuts".into()),
+            ..checked
+        };
+    }
+
+    registrar.unregister_all();
+    let mut report = register_valid(registrar, bindings);
+    if report.has_working_toggle() {
+        report.applied = true;
+        return report;
+    }
+
+    registrar.unregister_all();
+    let restored = register_valid(registrar, last_good);
+    report.rolled_back = true;
+    report.error = Some(if restored.has_working_toggle() {
+        "No toggle panel shortcut could be registered; restored the previous shortcuts".into()
+    } else {
+        "No toggle panel shortcut could be registered, not even the previous ones".into()
+    });
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Keys are upper-cased accelerators; anything containing "Bad" fails
+    /// to parse and anything in `taken` is owned by another app.
+    #[derive(Default)]
+    struct FakeRegistrar {
+        taken: Vec<String>,
+        registered: Vec<(String, ShortcutAction)>,
+    }
+
+    impl ShortcutRegistrar for FakeRegistrar {
+        type Key = String;
+
+        fn parse(&self, accelerator: &str) -> Result<String, String> {
+            if accelerator.contains("Bad") {
+                return Err(format!("unknown key in {accelerator}"));
+            }
+            Ok(accelerator.to_uppercase())
+        }
+
+        fn unregister_all(&mut self) {
+            self.registered.clear();
+        }
+
+        fn register(&mut self, key: String, action: ShortcutAction) -> Result<(), String> {
+            if self.taken.contains(&key) {
+                return Err(format!("{key} is already registered"));
+            }
+            self.registered.push((key, action));
+            Ok(())
+        }
+    }
+
+    fn toggle(accelerator: &str) -> ShortcutBinding {
+        ShortcutBinding::new(accelerator, ShortcutAction::TogglePanel)
+    }
+
+    fn registered_keys(registrar: &FakeRegistrar) -> Vec<&str> {
+        registrar
+            .registered
+            .iter()
+            .map(|(key, _)| key.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn actions_use_tagged_json() {
+        let binding = ShortcutBinding::new(
+            "Alt+P",
+            ShortcutAction::OpenProfile {
+                profile: "Prod".into(),
+            },
+        );
+        let value = serde_json::to_value(&binding).unwrap();
+        assert_eq!(
+            value,
+            json!({ "accelerator": "Alt+P", "action": { "type": "open_profile", "profile": "Prod" } })
+        );
+        assert_eq!(
+            serde_json::from_value::<ShortcutBinding>(value).unwrap(),
+            binding
+        );
+    }
+
+    #[test]
+    fn defaults_include_toggles_and_selection() {
+        let bindings = default_bindings();
+        assert_eq!(bindings.len(), 4);
+        assert_eq!(bindings[3].action, ShortcutAction::CaptureSelection);
+        assert_eq!(bindings[3].accelerator, DEFAULT_SELECTION_SHORTCUT);
+    }
+
+    #[test]
+    fn reports_parse_errors_and_duplicates() {
+        let mut registrar = FakeRegistrar::default();
+        let bindings = [
+            toggle("Alt+A"),
+            ShortcutBinding::new("Alt+Bad", ShortcutAction::AbortStreaming),
+            ShortcutBinding::new("alt+a", ShortcutAction::CaptureSelection),
+        ];
+
+        let report = apply_bindings(&mut registrar, &bindings, &default_bindings());
+
+        assert!(report.applied);
+        assert_eq!(report.bindings[0].problem, None);
+        assert!(matches!(
+            report.bindings[1].problem,
+            Some(BindingProblem::Parse { .. })
+        ));
+        assert_eq!(
+            report.bindings[2].problem,
+            Some(BindingProblem::Duplicate { index: 0 })
+        );
+        assert_eq!(registered_keys(&registrar), ["ALT+A"]);
+    }
+
+    #[test]
+    fn refuses_set_without_toggle_and_keeps_current() {
+        let mut registrar = FakeRegistrar::default();
+        apply_bindings(&mut registrar, &[toggle("Alt+A")], &[]);
+
+        let report = apply_bindings(
+            &mut registrar,
+            &[
+                toggle("Alt+Bad"),
+                ShortcutBinding::new("Alt+L", ShortcutAction::CaptureSelection),
+            ],
+            &[toggle("Alt+A")],
+        );
+
+        assert!(!report.applied);
+        assert!(!report.rolled_back);
+        assert!(report.error.is_some());
+        assert_eq!(registered_keys(&registrar), ["ALT+A"]);
+    }
+
+    #[test]
+    fn os_failure_reported_per_binding() {
+        let mut registrar = FakeRegistrar {
+            taken: vec!["ALT+L".into()],
+            ..FakeRegistrar::default()
+        };
+        let bindings = [
+            toggle("Alt+A"),
+            ShortcutBinding::new("Alt+L", ShortcutAction::CaptureSelection),
+        ];
+
+        let report = apply_bindings(&mut registrar, &bindings, &[]);
+
+        assert!(report.applied);
+        assert!(matches!(
+            report.bindings[1].problem,
+            Some(BindingProblem::Registration { .. })
+        ));
+        assert_eq!(registered_keys(&registrar), ["ALT+A"]);
+    }
+
+    #[test]
+    fn rolls_back_when_no_toggle_registers() {
+        let mut registrar = FakeRegistrar {
+            taken: vec!["ALT+SPACE".into()],
+            ..FakeRegistrar::default()
+        };
+        let last_good = [toggle("Alt+A"), toggle("Alt+B")];
+
+        let report = apply_bindings(
+            &mut registrar,
+            &[
+                toggle("Alt+Space"),
+                ShortcutBinding::new("Alt+X", ShortcutAction::AbortStreaming),
+            ],
+            &last_good,
+        );
+
+        assert!(!report.applied);
+        assert!(report.rolled_back);
+        assert!(matches!(
+            report.bindings[0].problem,
+            Some(BindingProblem::Registration { .. })
+        ));
+        assert_eq!(registered_keys(&registrar), ["ALT+A", "ALT+B"]);
+    }
+}
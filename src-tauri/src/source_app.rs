@@ -0,0 +1,511 @@
+//! The application a selection was captured from: its name and identifier,
+//! the focused window's title and, when the app exposes one, the document
+//! path or page URL. Prompts mention it and per-app prompt defaults key on
+//! it.
+
+use crate::placement::Rect;
+#[cfg(target_os = "macos")]
+use crate::selection::{copy_attribute_string, copy_attribute_ui_element, copy_attribute_value};
+#[cfg(target_os = "macos")]
+use accessibility_sys::{
+    kAXDocumentAttribute, kAXFocusedUIElementAttribute, kAXFocusedWindowAttribute,
+    kAXParentAttribute, kAXPositionAttribute, kAXSizeAttribute, kAXTitleAttribute, kAXURLAttribute,
+    kAXValueTypeCGPoint, kAXValueTypeCGSize, AXUIElementCreateApplication, AXUIElementRef,
+    AXValueGetValue, AXValueRef, AXValueType,
+};
+#[cfg(target_os = "macos")]
+use core_foundation::{
+    base::{CFType, CFTypeRef, TCFType},
+    string::CFString,
+    url::CFURL,
+};
+#[cfg(target_os = "macos")]
+use core_graphics::geometry::{CGPoint, CGSize};
+#[cfg(target_os = "macos")]
+use objc2_app_kit::NSWorkspace;
+use serde::{Deserialize, Serialize};
+#[cfg(all(unix, not(target_os = "macos")))]
+use std::process::{Command, Stdio};
+
+/// Parents checked above the focused element for a web area's URL.
+#[cfg(target_os = "macos")]
+const MAX_PARENT_DEPTH: usize = 32;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceApp {
+    pub name: String,
+    /// Bundle identifier on macOS, `WM_CLASS` instance on X11.
+    pub app_id: Option<String>,
+    pub window_title: Option<String>,
+    /// File shown in the focused window.
+    pub document_path: Option<String>,
+    /// Page shown in the focused window (browsers).
+    pub url: Option<String>,
+    /// Handle for bringing the app back to the front.
+    #[serde(skip)]
+    pub focus: Option<FocusTarget>,
+}
+
+/// What to activate to give focus back to a [`SourceApp`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FocusTarget {
+    /// macOS process id.
+    Process(i32),
+    /// X11 window id, e.g. `0x3a00007`.
+    X11Window(String),
+}
+
+impl SourceApp {
+    /// Files the location under `document_path` or `url`.
+    fn set_location(&mut self, location: &str) {
+        match file_url_path(location) {
+            Some(path) => self.document_path = Some(path),
+            None if location.contains("://") => self.url = Some(location.to_string()),
+            None => {}
+        }
+    }
+
+    fn has_location(&self) -> bool {
+        self.document_path.is_some() || self.url.is_some()
+    }
+
+    /// Whether a per-app rule names this app, by name or identifier.
+    fn matches(&self, app: &str) -> bool {
+        let app = app.trim();
+        !app.is_empty()
+            && (self.name.eq_ignore_ascii_case(app)
+                || self
+                    .app_id
+                    .as_deref()
+                    .is_some_and(|id| id.eq_ignore_ascii_case(app)))
+    }
+}
+
+/// Prompt prefilled when capturing from a given app.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppPrompt {
+    /// App name or identifier, compared case-insensitively.
+    pub app: String,
+    pub prompt: String,
+}
+
+/// First non-empty prompt whose rule matches `source`.
+pub fn app_prompt<'a>(rules: &'a [AppPrompt], source: &SourceApp) -> Option<&'a str> {
+    rules
+        .iter()
+        .find(|rule| !rule.prompt.trim().is_empty() && source.matches(&rule.app))
+        .map(|rule| rule.prompt.as_str())
+}
+
+/// Path of a `file://` URL, percent-decoded.
+fn file_url_path(location: &str) -> Option<String> {
+    let rest = location.strip_prefix("file://")?;
+    let path = rest.strip_prefix("localhost").unwrap_or(rest);
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let escaped = (bytes[index] == b'%')
+            .then(|| path.get(index + 1..index + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            None => {
+                decoded.push(bytes[index]);
+                index += 1;
+            }
+        }
+    }
+    Some(String::from_utf8_lossy(&decoded).into_owned())
+}
+
+/// The app in front when the shortcut fired. Window details need the
+/// Accessibility permission the selection capture already asks for.
+#[cfg(target_os = "macos")]
+pub fn frontmost_app() -> Option<SourceApp> {
+    let app = NSWorkspace::sharedWorkspace().frontmostApplication()?;
+    // With the panel open MacClaw itself is in front; that says nothing.
+    if app.processIdentifier() == std::process::id() as i32 {
+        return None;
+    }
+    let mut source = SourceApp {
+        name: app
+            .localizedName()
+            .map(|name| name.to_string())
+            .unwrap_or_default(),
+        app_id: app.bundleIdentifier().map(|id| id.to_string()),
+        focus: Some(FocusTarget::Process(app.processIdentifier())),
+        ..SourceApp::default()
+    };
+    if let Err(e) = describe_window(app.processIdentifier(), &mut source) {
+        eprintln!("source app window lookup failed: {e}");
+    }
+    Some(source)
+}
+
+#[cfg(target_os = "macos")]
+fn describe_window(pid: i32, source: &mut SourceApp) -> Result<(), String> {
+    let app = unsafe { AXUIElementCreateApplication(pid) };
+    if app.is_null() {
+        return Ok(());
+    }
+    let _app_guard = unsafe { CFType::wrap_under_create_rule(app as CFTypeRef) };
+
+    if let Some(window) = copy_attribute_ui_element(app, kAXFocusedWindowAttribute)? {
+        let _window_guard = unsafe { CFType::wrap_under_create_rule(window as CFTypeRef) };
+        source.window_title =
+            copy_attribute_string(window, kAXTitleAttribute)?.filter(|title| !title.is_empty());
+        if let Some(document) = copy_attribute_location(window, kAXDocumentAttribute)? {
+            source.set_location(&document);
+        }
+    }
+
+    if !source.has_location() {
+        if let Some(url) = focused_web_url(app)? {
+            source.set_location(&url);
+        }
+    }
+    Ok(())
+}
+
+/// Browsers expose the page URL on the web area around the focused element.
+#[cfg(target_os = "macos")]
+fn focused_web_url(app: AXUIElementRef) -> Result<Option<String>, String> {
+    let Some(mut element) = copy_attribute_ui_element(app, kAXFocusedUIElementAttribute)? else {
+        return Ok(None);
+    };
+    for _ in 0..MAX_PARENT_DEPTH {
+        let _guard = unsafe { CFType::wrap_under_create_rule(element as CFTypeRef) };
+        if let Some(url) = copy_attribute_location(element, kAXURLAttribute)? {
+            return Ok(Some(url));
+        }
+        match copy_attribute_ui_element(element, kAXParentAttribute)? {
+            Some(parent) => element = parent,
+            None => break,
+        }
+    }
+    Ok(None)
+}
+
+/// An attribute holding a URL, as a CFURL or a string.
+#[cfg(target_os = "macos")]
+fn copy_attribute_location(
+    element: AXUIElementRef,
+    attribute: &'static str,
+) -> Result<Option<String>, String> {
+    let Some(raw_value) = copy_attribute_value(element, attribute)? else {
+        return Ok(None);
+    };
+
+    let value = unsafe { CFType::wrap_under_create_rule(raw_value) };
+    if let Some(url) = value.downcast::<CFURL>() {
+        return Ok(Some(url.get_string().to_string()));
+    }
+    Ok(value.downcast::<CFString>().map(|text| text.to_string()))
+}
+
+/// Frame of the focused window of the app in front, in screen points.
+#[cfg(target_os = "macos")]
+pub fn focused_window_frame() -> Option<Rect> {
+    let app = NSWorkspace::sharedWorkspace().frontmostApplication()?;
+    if app.processIdentifier() == std::process::id() as i32 {
+        return None;
+    }
+    let app = unsafe { AXUIElementCreateApplication(app.processIdentifier()) };
+    if app.is_null() {
+        return None;
+    }
+    let _app_guard = unsafe { CFType::wrap_under_create_rule(app as CFTypeRef) };
+    let window = copy_attribute_ui_element(app, kAXFocusedWindowAttribute).ok()??;
+    let _window_guard = unsafe { CFType::wrap_under_create_rule(window as CFTypeRef) };
+
+    let mut origin = CGPoint::new(0.0, 0.0);
+    let mut size = CGSize::new(0.0, 0.0);
+    copy_attribute_struct(
+        window,
+        kAXPositionAttribute,
+        kAXValueTypeCGPoint,
+        &mut origin,
+    )?;
+    copy_attribute_struct(window, kAXSizeAttribute, kAXValueTypeCGSize, &mut size)?;
+    Some(Rect {
+        x: origin.x.round() as i32,
+        y: origin.y.round() as i32,
+        width: size.width.max(0.0).round() as u32,
+        height: size.height.max(0.0).round() as u32,
+    })
+}
+
+/// Reads an AXValue attribute (point, size) into `out`.
+#[cfg(target_os = "macos")]
+fn copy_attribute_struct<T>(
+    element: AXUIElementRef,
+    attribute: &'static str,
+    kind: AXValueType,
+    out: &mut T,
+) -> Option<()> {
+    let raw_value = copy_attribute_value(element, attribute).ok()??;
+    let _guard = unsafe { CFType::wrap_under_create_rule(raw_value) };
+    let copied = unsafe { AXValueGetValue(raw_value as AXValueRef, kind, out as *mut T as *mut _) };
+    copied.then_some(())
+}
+
+/// The X11 active window. Wayland has no portable way to ask, so there is
+/// nothing to report there.
+#[cfg(all(unix, not(target_os = "macos")))]
+pub fn frontmost_app() -> Option<SourceApp> {
+    let window = active_window()?;
+    let props = x11_tool(
+        "xprop",
+        &[
+            "-id",
+            &window,
+            "_NET_WM_NAME",
+            "WM_NAME",
+            "WM_CLASS",
+            "_NET_WM_PID",
+        ],
+    )?;
+    // Our own panel is no place to write an answer back to.
+    if parse_window_pid(&props) == Some(std::process::id()) {
+        return None;
+    }
+    let mut source = parse_window_props(&props)?;
+    source.focus = Some(FocusTarget::X11Window(window));
+    Some(source)
+}
+
+/// Frame of the X11 active window, in pixels.
+#[cfg(all(unix, not(target_os = "macos")))]
+pub fn focused_window_frame() -> Option<Rect> {
+    let window = active_window()?;
+    let props = x11_tool("xprop", &["-id", &window, "_NET_WM_PID"])?;
+    if parse_window_pid(&props) == Some(std::process::id()) {
+        return None;
+    }
+    parse_window_geometry(&x11_tool("xwininfo", &["-id", &window])?)
+}
+
+#[cfg(not(unix))]
+pub fn frontmost_app() -> Option<SourceApp> {
+    None
+}
+
+#[cfg(not(unix))]
+pub fn focused_window_frame() -> Option<Rect> {
+    None
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn active_window() -> Option<String> {
+    let is_set = |key| std::env::var_os(key).is_some_and(|value| !value.is_empty());
+    if is_set("WAYLAND_DISPLAY") || !is_set("DISPLAY") {
+        return None;
+    }
+    parse_active_window(&x11_tool("xprop", &["-root", "_NET_ACTIVE_WINDOW"])?)
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn x11_tool(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .inspect_err(|e| eprintln!("source app lookup failed: {program}: {e}"))
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Window id from `_NET_ACTIVE_WINDOW(WINDOW): window id # 0x3a00007`.
+#[cfg_attr(not(all(unix, not(target_os = "macos"))), allow(dead_code))]
+fn parse_active_window(output: &str) -> Option<String> {
+    let id = output.split('#').nth(1)?.split(',').next()?.trim();
+    (id.starts_with("0x") && id != "0x0").then(|| id.to_string())
+}
+
+/// App from `xprop -id` lines such as `WM_CLASS(STRING) = "code", "Code"`.
+#[cfg_attr(not(all(unix, not(target_os = "macos"))), allow(dead_code))]
+fn parse_window_props(output: &str) -> Option<SourceApp> {
+    let mut source = SourceApp::default();
+    let mut legacy_title = None;
+    for line in output.lines() {
+        let Some((key, value)) = line.split_once(" = ") else {
+            continue;
+        };
+        let strings = quoted_strings(value);
+        match key.split('(').next().unwrap_or_default() {
+            "_NET_WM_NAME" => source.window_title = strings.into_iter().next(),
+            "WM_NAME" => legacy_title = strings.into_iter().next(),
+            "WM_CLASS" => {
+                let mut strings = strings.into_iter();
+                source.app_id = strings.next();
+                source.name = strings.next().or_else(|| source.app_id.clone())?;
+            }
+            _ => {}
+        }
+    }
+    source.window_title = source.window_title.or(legacy_title);
+    (!source.name.is_empty()).then_some(source)
+}
+
+/// Owner from `_NET_WM_PID(CARDINAL) = 4242`.
+#[cfg_attr(not(all(unix, not(target_os = "macos"))), allow(dead_code))]
+fn parse_window_pid(output: &str) -> Option<u32> {
+    output
+        .lines()
+        .find_map(|line| line.strip_prefix("_NET_WM_PID(CARDINAL) = "))
+        .and_then(|pid| pid.trim().parse().ok())
+}
+
+/// Frame from `xwininfo` lines such as `  Absolute upper-left X:  1920`.
+#[cfg_attr(not(all(unix, not(target_os = "macos"))), allow(dead_code))]
+fn parse_window_geometry(output: &str) -> Option<Rect> {
+    let field = |name: &str| {
+        output
+            .lines()
+            .find_map(|line| line.trim().strip_prefix(name))
+            .and_then(|value| value.trim().parse::<i64>().ok())
+    };
+    Some(Rect {
+        x: field("Absolute upper-left X:")?.try_into().ok()?,
+        y: field("Absolute upper-left Y:")?.try_into().ok()?,
+        width: field("Width:")?.try_into().ok()?,
+        height: field("Height:")?.try_into().ok()?,
+    })
+}
+
+/// The `"..."` values of an xprop line, unescaped.
+#[cfg_attr(not(all(unix, not(target_os = "macos"))), allow(dead_code))]
+fn quoted_strings(value: &str) -> Vec<String> {
+    let mut strings = Vec::new();
+    let mut chars = value.chars();
+    while chars.any(|c| c == '"') {
+        let mut text = String::new();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => text.extend(chars.next()),
+                '"' => break,
+                _ => text.push(c),
+            }
+        }
+        strings.push(text);
+    }
+    strings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn terminal() -> SourceApp {
+        SourceApp {
+            name: "Terminal".into(),
+            app_id: Some("com.apple.Terminal".into()),
+            ..SourceApp::default()
+        }
+    }
+
+    #[test]
+    fn locations_split_into_paths_and_urls() {
+        let mut source = terminal();
+        source.set_location("file:///Users/me/My%20Notes/todo.md");
+        assert_eq!(
+            source.document_path.as_deref(),
+            Some("/Users/me/My Notes/todo.md")
+        );
+
+        let mut source = terminal();
+        source.set_location("https://example.com/a?b=c%20d");
+        assert_eq!(source.url.as_deref(), Some("https://example.com/a?b=c%20d"));
+        source.set_location("untitled");
+        assert!(source.document_path.is_none());
+
+        assert_eq!(
+            file_url_path("file://localhost/tmp/%E2%9C%93%2"),
+            Some("/tmp/✓%2".into())
+        );
+    }
+
+    #[test]
+    fn app_prompts_match_name_or_identifier() {
+        let rules = vec![
+            AppPrompt {
+                app: "terminal".into(),
+                prompt: " ".into(),
+            },
+            AppPrompt {
+                app: "Safari".into(),
+                prompt: "Summarize this page".into(),
+            },
+            AppPrompt {
+                app: "COM.APPLE.TERMINAL".into(),
+                prompt: "Explain this error".into(),
+            },
+        ];
+
+        assert_eq!(app_prompt(&rules, &terminal()), Some("Explain this error"));
+        let other = SourceApp {
+            name: "Notes".into(),
+            ..SourceApp::default()
+        };
+        assert_eq!(app_prompt(&rules, &other), None);
+    }
+
+    #[test]
+    fn parses_xprop_output() {
+        assert_eq!(
+            parse_active_window("_NET_ACTIVE_WINDOW(WINDOW): window id # 0x3a00007\n"),
+            Some("0x3a00007".into())
+        );
+        assert_eq!(
+            parse_active_window("_NET_ACTIVE_WINDOW(WINDOW): window id # 0x0"),
+            None
+        );
+
+        let props = "_NET_WM_NAME(UTF8_STRING) = \"main.rs - \\\"MacClaw\\\" - Code\"\n\
+                     WM_NAME(STRING) = \"legacy\"\n\
+                     WM_CLASS(STRING) = \"code\", \"Code\"\n";
+        assert_eq!(
+            parse_window_props(props),
+            Some(SourceApp {
+                name: "Code".into(),
+                app_id: Some("code".into()),
+                window_title: Some("main.rs - \"MacClaw\" - Code".into()),
+                ..SourceApp::default()
+            })
+        );
+        assert_eq!(parse_window_props("WM_CLASS:  not found.\n"), None);
+        assert_eq!(
+            parse_window_pid("_NET_WM_PID(CARDINAL) = 4242\n"),
+            Some(4242)
+        );
+        assert_eq!(parse_window_pid(props), None);
+    }
+
+    #[test]
+    fn parses_xwininfo_geometry() {
+        let output = "xwininfo: Window id: 0x3a00007 \"main.rs - Code\"\n\n  \
+                      Absolute upper-left X:  1920\n  Absolute upper-left Y:  -24\n  \
+                      Relative upper-left X:  0\n  Width: 1280\n  Height: 800\n";
+        assert_eq!(
+            parse_window_geometry(output),
+            Some(Rect {
+                x: 1920,
+                y: -24,
+                width: 1280,
+                height: 800
+            })
+        );
+        assert_eq!(parse_window_geometry("xwininfo: error"), None);
+    }
+}
@@ -0,0 +1,359 @@
+//! The few window operations the panel and notch logic needs, behind traits
+//! so that logic runs against a recording fake in tests instead of real
+//! Tauri windows.
+
+use serde::Serialize;
+use serde_json::Value;
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+
+use crate::credentials::{self, load_credentials};
+use crate::notch::NotchSettings;
+use crate::panel::{Panel, PANEL};
+use crate::placement::{Monitor, PanelLayout, PanelSettings, Rect};
+use crate::source_app;
+
+pub trait WindowController {
+    fn show(&self);
+    fn hide(&self);
+    /// Resize to `width` x `height` logical points.
+    fn set_size(&self, width: f64, height: f64);
+    /// Move the top-left corner to physical `x`, `y`.
+    fn set_position(&self, x: i32, y: i32);
+    fn center(&self);
+    fn focus(&self);
+    /// Send `event` to every webview.
+    fn emit(&self, event: &str, payload: Value);
+}
+
+/// Finds windows by label, owns the panel state they share and describes
+/// the screens around them. Positions are in physical pixels.
+pub trait WindowHost {
+    fn window(&self, label: &str) -> Option<Box<dyn WindowController>>;
+    fn panel(&self) -> &Panel;
+    fn panel_settings(&self) -> PanelSettings;
+    fn monitors(&self) -> Vec<Monitor>;
+    fn cursor(&self) -> Option<(i32, i32)>;
+    /// Focused window of the app in front, if it is not ours.
+    fn focused_window(&self) -> Option<Rect>;
+    /// Persist where the panel sits on each display.
+    fn save_layout(&self, layout: PanelLayout);
+    fn notch_settings(&self) -> NotchSettings;
+    /// Run `task` on another thread after `delay`.
+    fn after(&self, delay: Duration, task: HostTask);
+}
+
+/// Work scheduled with [`WindowHost::after`].
+pub type HostTask = Box<dyn FnOnce(&dyn WindowHost) + Send>;
+
+/// Emit a serializable payload through `window`.
+pub fn emit(window: &dyn WindowController, event: &str, payload: impl Serialize) {
+    match serde_json::to_value(payload) {
+        Ok(payload) => window.emit(event, payload),
+        Err(error) => eprintln!("failed to serialize {event} payload: {error}"),
+    }
+}
+
+impl WindowController for tauri::WebviewWindow {
+    fn show(&self) {
+        let _ = tauri::WebviewWindow::show(self);
+    }
+
+    fn hide(&self) {
+        let _ = tauri::WebviewWindow::hide(self);
+    }
+
+    fn set_size(&self, width: f64, height: f64) {
+        let _ = tauri::WebviewWindow::set_size(
+            self,
+            tauri::Size::Logical(tauri::LogicalSize::new(width, height)),
+        );
+    }
+
+    fn set_position(&self, x: i32, y: i32) {
+        let _ = tauri::WebviewWindow::set_position(
+            self,
+            tauri::Position::Physical(tauri::PhysicalPosition::new(x, y)),
+        );
+    }
+
+    fn center(&self) {
+        let _ = tauri::WebviewWindow::center(self);
+    }
+
+    fn focus(&self) {
+        let _ = self.set_focus();
+    }
+
+    fn emit(&self, event: &str, payload: Value) {
+        let _ = Emitter::emit(self, event, payload);
+    }
+}
+
+impl WindowHost for tauri::AppHandle {
+    fn window(&self, label: &str) -> Option<Box<dyn WindowController>> {
+        self.get_webview_window(label)
+            .map(|window| Box::new(window) as Box<dyn WindowController>)
+    }
+
+    fn panel(&self) -> &Panel {
+        &PANEL
+    }
+
+    fn panel_settings(&self) -> PanelSettings {
+        load_credentials()
+            .map(|creds| creds.panel)
+            .unwrap_or_default()
+    }
+
+    fn monitors(&self) -> Vec<Monitor> {
+        let primary = self
+            .primary_monitor()
+            .ok()
+            .flatten()
+            .and_then(|monitor| monitor.name().cloned());
+        let monitors = self.available_monitors().unwrap_or_else(|e| {
+            eprintln!("failed to list displays: {e}");
+            Vec::new()
+        });
+        monitors
+            .iter()
+            .map(|monitor| {
+                let name = monitor.name().cloned().unwrap_or_default();
+                let work_area = monitor.work_area();
+                Monitor {
+                    primary: primary.as_ref() == Some(&name),
+                    frame: pixel_rect(*monitor.position(), *monitor.size()),
+                    work_area: pixel_rect(work_area.position, work_area.size),
+                    scale: monitor.scale_factor(),
+                    name,
+                }
+            })
+            .collect()
+    }
+
+    fn cursor(&self) -> Option<(i32, i32)> {
+        let position = self.cursor_position().ok()?;
+        Some((position.x.round() as i32, position.y.round() as i32))
+    }
+
+    fn focused_window(&self) -> Option<Rect> {
+        let frame = source_app::focused_window_frame()?;
+        // Accessibility reports points; the displays are in pixels.
+        #[cfg(target_os = "macos")]
+        let frame = crate::placement::points_to_pixels(&self.monitors(), frame);
+        Some(frame)
+    }
+
+    fn save_layout(&self, layout: PanelLayout) {
+        if let Err(error) = credentials::save_panel_layout(layout) {
+            eprintln!("failed to save panel layout: {error}");
+        }
+    }
+
+    fn notch_settings(&self) -> NotchSettings {
+        load_credentials()
+            .map(|creds| creds.notch)
+            .unwrap_or_default()
+    }
+
+    fn after(&self, delay: Duration, task: HostTask) {
+        let app = self.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(delay);
+            task(&app);
+        });
+    }
+}
+
+fn pixel_rect(position: tauri::PhysicalPosition<i32>, size: tauri::PhysicalSize<u32>) -> Rect {
+    Rect {
+        x: position.x,
+        y: position.y,
+        width: size.width,
+        height: size.height,
+    }
+}
+
+#[cfg(test)]
+pub mod testing {
+    //! A host whose windows record every call in one shared log.
+
+    use super::*;
+    use std::sync::{Arc, Mutex, PoisonError};
+
+    #[derive(Default)]
+    struct Log {
+        calls: Vec<String>,
+        events: Vec<(String, Value)>,
+    }
+
+    #[derive(Clone)]
+    pub struct RecordingWindow {
+        label: String,
+        log: Arc<Mutex<Log>>,
+    }
+
+    impl RecordingWindow {
+        fn record(&self, call: String) {
+            let mut log = self.log.lock().unwrap_or_else(PoisonError::into_inner);
+            log.calls.push(format!("{} {call}", self.label));
+        }
+    }
+
+    impl WindowController for RecordingWindow {
+        fn show(&self) {
+            self.record("show".into());
+        }
+
+        fn hide(&self) {
+            self.record("hide".into());
+        }
+
+        fn set_size(&self, width: f64, height: f64) {
+            self.record(format!("size {width}x{height}"));
+        }
+
+        fn set_position(&self, x: i32, y: i32) {
+            self.record(format!("move {x},{y}"));
+        }
+
+        fn center(&self) {
+            self.record("center".into());
+        }
+
+        fn focus(&self) {
+            self.record("focus".into());
+        }
+
+        fn emit(&self, event: &str, payload: Value) {
+            self.record(format!("emit {event}"));
+            let mut log = self.log.lock().unwrap_or_else(PoisonError::into_inner);
+            log.events.push((event.to_string(), payload));
+        }
+    }
+
+    /// The panel and notch windows with their own panel state.
+    pub struct FakeHost {
+        windows: Vec<RecordingWindow>,
+        log: Arc<Mutex<Log>>,
+        panel: Panel,
+        pub settings: PanelSettings,
+        pub monitors: Vec<Monitor>,
+        pub cursor: Option<(i32, i32)>,
+        pub focused_window: Option<Rect>,
+        pub notch: NotchSettings,
+        saved_layout: Mutex<Option<PanelLayout>>,
+        tasks: Mutex<Vec<(Duration, HostTask)>>,
+    }
+
+    impl FakeHost {
+        pub fn new(labels: &[&str]) -> Self {
+            let log = Arc::new(Mutex::new(Log::default()));
+            let windows = labels
+                .iter()
+                .map(|label| RecordingWindow {
+                    label: label.to_string(),
+                    log: log.clone(),
+                })
+                .collect();
+            Self {
+                windows,
+                log,
+                panel: Panel::new(),
+                settings: PanelSettings::default(),
+                monitors: Vec::new(),
+                cursor: None,
+                focused_window: None,
+                notch: NotchSettings::default(),
+                saved_layout: Mutex::new(None),
+                tasks: Mutex::new(Vec::new()),
+            }
+        }
+
+        /// Calls made so far, as "<label> <call>", and clears the log.
+        pub fn take_calls(&self) -> Vec<String> {
+            let mut log = self.log.lock().unwrap_or_else(PoisonError::into_inner);
+            std::mem::take(&mut log.calls)
+        }
+
+        /// Payload of the last `event` emitted.
+        pub fn payload(&self, event: &str) -> Option<Value> {
+            let log = self.log.lock().unwrap_or_else(PoisonError::into_inner);
+            log.events
+                .iter()
+                .rev()
+                .find(|(name, _)| name == event)
+                .map(|(_, payload)| payload.clone())
+        }
+
+        /// Layout passed to the last `save_layout`, and clears it.
+        pub fn take_saved_layout(&self) -> Option<PanelLayout> {
+            let mut saved = self
+                .saved_layout
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            saved.take()
+        }
+
+        /// Run the tasks passed to `after` so far, without waiting, and
+        /// return their delays.
+        pub fn run_tasks(&self) -> Vec<Duration> {
+            let tasks =
+                std::mem::take(&mut *self.tasks.lock().unwrap_or_else(PoisonError::into_inner));
+            tasks
+                .into_iter()
+                .map(|(delay, task)| {
+                    task(self);
+                    delay
+                })
+                .collect()
+        }
+    }
+
+    impl WindowHost for FakeHost {
+        fn window(&self, label: &str) -> Option<Box<dyn WindowController>> {
+            self.windows
+                .iter()
+                .find(|window| window.label == label)
+                .map(|window| Box::new(window.clone()) as Box<dyn WindowController>)
+        }
+
+        fn panel(&self) -> &Panel {
+            &self.panel
+        }
+
+        fn panel_settings(&self) -> PanelSettings {
+            self.settings.clone()
+        }
+
+        fn monitors(&self) -> Vec<Monitor> {
+            self.monitors.clone()
+        }
+
+        fn cursor(&self) -> Option<(i32, i32)> {
+            self.cursor
+        }
+
+        fn focused_window(&self) -> Option<Rect> {
+            self.focused_window
+        }
+
+        fn save_layout(&self, layout: PanelLayout) {
+            let mut saved = self
+                .saved_layout
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            *saved = Some(layout);
+        }
+
+        fn notch_settings(&self) -> NotchSettings {
+            self.notch.clone()
+        }
+
+        fn after(&self, delay: Duration, task: HostTask) {
+            let mut tasks = self.tasks.lock().unwrap_or_else(PoisonError::into_inner);
+            tasks.push((delay, task));
+        }
+    }
+}
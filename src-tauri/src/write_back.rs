@@ -0,0 +1,551 @@
+//! Writes an answer back into the app a selection came from. Focus goes
+//! back to that app, the text is pasted over the selection or after it, and
+//! the clipboard is restored afterwards. When the clipboard cannot be saved
+//! the text is typed instead, so the user's copy is never lost.
+
+use crate::clipboard::{self, Clipboard};
+#[cfg(all(unix, not(target_os = "macos")))]
+use crate::clipboard::{env_is_set, run_call, ToolCall};
+#[cfg(target_os = "macos")]
+use crate::selection::press_key;
+use crate::source_app::FocusTarget;
+#[cfg(target_os = "macos")]
+use core_graphics::{
+    event::{CGEvent, CGEventFlags, CGEventTapLocation, CGKeyCode},
+    event_source::{CGEventSource, CGEventSourceStateID},
+};
+#[cfg(target_os = "macos")]
+use objc2_app_kit::{NSApplicationActivationOptions, NSRunningApplication};
+use serde::{Deserialize, Serialize};
+#[cfg(all(unix, not(target_os = "macos")))]
+use std::io;
+use std::sync::{Mutex, PoisonError};
+use std::thread;
+use std::time::Duration;
+
+/// Time for the target app to take focus, and to read the clipboard after
+/// the paste keystroke before it is restored.
+const SETTLE_MS: u64 = 150;
+/// UTF-16 units per synthesized typing event; longer strings get cut off.
+#[cfg(target_os = "macos")]
+const MAX_TYPED_UNITS: usize = 20;
+
+#[cfg(target_os = "macos")]
+const KEYCODE_V: CGKeyCode = 9;
+#[cfg(target_os = "macos")]
+const KEYCODE_RIGHT_ARROW: CGKeyCode = 124;
+#[cfg(not(unix))]
+const UNSUPPORTED: &str = "Writing back is not supported on this platform";
+
+/// App focused when the selection shortcut last fired.
+static LAST_TARGET: Mutex<Option<FocusTarget>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WriteMode {
+    /// Paste over the text that is still selected.
+    Replace,
+    /// Paste after the selection, leaving it in place.
+    Insert,
+}
+
+/// Keyboard and window control of the running session.
+pub trait Desktop {
+    /// Brings the app or window back to the front.
+    fn activate(&self, target: &FocusTarget) -> Result<(), String>;
+    /// Moves the caret to the end of the selection.
+    fn collapse_selection(&self) -> Result<(), String>;
+    fn press_paste(&self) -> Result<(), String>;
+    fn type_text(&self, text: &str) -> Result<(), String>;
+}
+
+pub fn remember(target: FocusTarget) {
+    *LAST_TARGET.lock().unwrap_or_else(PoisonError::into_inner) = Some(target);
+}
+
+fn last_target() -> Option<FocusTarget> {
+    LAST_TARGET
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+}
+
+/// Writes `text` into the app the last selection came from.
+pub fn write_back(text: &str, mode: WriteMode) -> Result<(), String> {
+    write_back_with(
+        clipboard::system().as_ref(),
+        system().as_ref(),
+        last_target().as_ref(),
+        text,
+        mode,
+        Duration::from_millis(SETTLE_MS),
+    )
+}
+
+/// Focuses `target` (when known) and pastes `text` through `clipboard`,
+/// restoring its previous contents afterwards.
+pub fn write_back_with(
+    clipboard: &dyn Clipboard,
+    desktop: &dyn Desktop,
+    target: Option<&FocusTarget>,
+    text: &str,
+    mode: WriteMode,
+    settle: Duration,
+) -> Result<(), String> {
+    if text.is_empty() {
+        return Err("Nothing to write back".to_string());
+    }
+    if let Some(target) = target {
+        desktop.activate(target)?;
+        thread::sleep(settle);
+    }
+    if mode == WriteMode::Insert {
+        desktop.collapse_selection()?;
+    }
+
+    let snapshot = match clipboard.snapshot() {
+        Ok(snapshot) => snapshot,
+        Err(e) => {
+            eprintln!("write back: typing instead, failed to save clipboard: {e}");
+            return desktop.type_text(text);
+        }
+    };
+    if let Err(e) = clipboard.write_text(text) {
+        eprintln!("write back: typing instead, failed to write clipboard: {e}");
+        return desktop.type_text(text);
+    }
+
+    let pasted = desktop.press_paste();
+    // Apps read the clipboard some time after the keystroke.
+    thread::sleep(settle);
+    if let Err(e) = clipboard.restore(&snapshot) {
+        eprintln!("failed to restore clipboard: {e}");
+    }
+    pasted
+}
+
+/// Splits `text` into pieces of at most `max_units` UTF-16 units without
+/// separating surrogate pairs.
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn typing_chunks(text: &str, max_units: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut chunk = String::new();
+    let mut units = 0;
+    for c in text.chars() {
+        if units + c.len_utf16() > max_units && !chunk.is_empty() {
+            chunks.push(std::mem::take(&mut chunk));
+            units = 0;
+        }
+        chunk.push(c);
+        units += c.len_utf16();
+    }
+    if !chunk.is_empty() {
+        chunks.push(chunk);
+    }
+    chunks
+}
+
+/// Keyboard and windows of the running session.
+#[cfg(target_os = "macos")]
+pub fn system() -> Box<dyn Desktop> {
+    Box::new(MacDesktop)
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+pub fn system() -> Box<dyn Desktop> {
+    Box::new(ToolDesktop::detect())
+}
+
+#[cfg(not(unix))]
+pub fn system() -> Box<dyn Desktop> {
+    Box::new(Unsupported)
+}
+
+/// AppKit activation and synthesized key events.
+#[cfg(target_os = "macos")]
+struct MacDesktop;
+
+#[cfg(target_os = "macos")]
+impl Desktop for MacDesktop {
+    fn activate(&self, target: &FocusTarget) -> Result<(), String> {
+        let FocusTarget::Process(pid) = target else {
+            return Ok(());
+        };
+        let app = NSRunningApplication::runningApplicationWithProcessIdentifier(*pid)
+            .ok_or_else(|| "The app the text was selected in has quit".to_string())?;
+        if app.activateWithOptions(NSApplicationActivationOptions::empty()) {
+            Ok(())
+        } else {
+            Err("failed to bring the source app to the front".to_string())
+        }
+    }
+
+    fn collapse_selection(&self) -> Result<(), String> {
+        press_key(KEYCODE_RIGHT_ARROW, CGEventFlags::CGEventFlagNull)
+    }
+
+    fn press_paste(&self) -> Result<(), String> {
+        press_key(KEYCODE_V, CGEventFlags::CGEventFlagCommand)
+    }
+
+    fn type_text(&self, text: &str) -> Result<(), String> {
+        let source = CGEventSource::new(CGEventSourceStateID::CombinedSessionState)
+            .map_err(|_| "failed to create keyboard event source".to_string())?;
+        for chunk in typing_chunks(text, MAX_TYPED_UNITS) {
+            for key_down in [true, false] {
+                let event = CGEvent::new_keyboard_event(source.clone(), 0, key_down)
+                    .map_err(|_| "failed to create typing event".to_string())?;
+                event.set_string(&chunk);
+                event.post(CGEventTapLocation::HID);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Command-line tool that synthesizes input.
+#[cfg(all(unix, not(target_os = "macos")))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum KeyTool {
+    /// X11.
+    Xdotool,
+    /// Wayland compositors with the virtual keyboard protocol.
+    Wtype,
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+impl KeyTool {
+    fn for_session(is_set: impl Fn(&str) -> bool) -> Option<Self> {
+        if is_set("WAYLAND_DISPLAY") {
+            Some(Self::Wtype)
+        } else if is_set("DISPLAY") {
+            Some(Self::Xdotool)
+        } else {
+            None
+        }
+    }
+
+    fn package(self) -> &'static str {
+        match self {
+            Self::Xdotool => "xdotool",
+            Self::Wtype => "wtype",
+        }
+    }
+
+    /// Wayland does not let clients raise other windows.
+    fn activate(self, target: &FocusTarget) -> Option<ToolCall> {
+        match (self, target) {
+            (Self::Xdotool, FocusTarget::X11Window(id)) => {
+                Some(ToolCall::new("xdotool", &["windowactivate", "--sync", id]))
+            }
+            _ => None,
+        }
+    }
+
+    fn press(self, xdotool_key: &str, wtype_args: &[&str]) -> ToolCall {
+        match self {
+            Self::Xdotool => ToolCall::new("xdotool", &["key", "--clearmodifiers", xdotool_key]),
+            Self::Wtype => ToolCall::new("wtype", wtype_args),
+        }
+    }
+
+    fn collapse_selection(self) -> ToolCall {
+        self.press("Right", &["-k", "Right"])
+    }
+
+    fn paste(self) -> ToolCall {
+        self.press("ctrl+v", &["-M", "ctrl", "v", "-m", "ctrl"])
+    }
+
+    fn type_text(self, text: &str) -> ToolCall {
+        match self {
+            Self::Xdotool => ToolCall::new("xdotool", &["type", "--clearmodifiers", "--", text]),
+            Self::Wtype => ToolCall::new("wtype", &["--", text]),
+        }
+    }
+}
+
+/// Input through xdotool on X11 or wtype on Wayland.
+#[cfg(all(unix, not(target_os = "macos")))]
+struct ToolDesktop {
+    tool: Option<KeyTool>,
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+impl ToolDesktop {
+    fn detect() -> Self {
+        Self {
+            tool: KeyTool::for_session(env_is_set),
+        }
+    }
+
+    fn run(&self, call: impl FnOnce(KeyTool) -> Option<ToolCall>) -> Result<(), String> {
+        let tool = self
+            .tool
+            .ok_or_else(|| "No X11 or Wayland display to write back to".to_string())?;
+        let Some(call) = call(tool) else {
+            return Ok(());
+        };
+        match run_call(&call, None) {
+            Ok(output) if output.success => Ok(()),
+            Ok(_) => Err(format!("{} failed", call.program)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                Err(format!("Install {} to write answers back", tool.package()))
+            }
+            Err(e) => Err(format!("failed to run {}: {e}", call.program)),
+        }
+    }
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+impl Desktop for ToolDesktop {
+    fn activate(&self, target: &FocusTarget) -> Result<(), String> {
+        self.run(|tool| tool.activate(target))
+    }
+
+    fn collapse_selection(&self) -> Result<(), String> {
+        self.run(|tool| Some(tool.collapse_selection()))
+    }
+
+    fn press_paste(&self) -> Result<(), String> {
+        self.run(|tool| Some(tool.paste()))
+    }
+
+    fn type_text(&self, text: &str) -> Result<(), String> {
+        self.run(|tool| Some(tool.type_text(text)))
+    }
+}
+
+#[cfg(not(unix))]
+struct Unsupported;
+
+#[cfg(not(unix))]
+impl Desktop for Unsupported {
+    fn activate(&self, _target: &FocusTarget) -> Result<(), String> {
+        Err(UNSUPPORTED.to_string())
+    }
+
+    fn collapse_selection(&self) -> Result<(), String> {
+        Err(UNSUPPORTED.to_string())
+    }
+
+    fn press_paste(&self) -> Result<(), String> {
+        Err(UNSUPPORTED.to_string())
+    }
+
+    fn type_text(&self, _text: &str) -> Result<(), String> {
+        Err(UNSUPPORTED.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clipboard::{ClipboardItem, ClipboardSnapshot, MemoryClipboard};
+
+    /// Records what it was asked to do, including what the clipboard held
+    /// at paste time.
+    struct RecordingDesktop<'a> {
+        clipboard: &'a dyn Clipboard,
+        events: Mutex<Vec<String>>,
+        paste_error: Option<&'static str>,
+    }
+
+    impl<'a> RecordingDesktop<'a> {
+        fn new(clipboard: &'a dyn Clipboard) -> Self {
+            Self {
+                clipboard,
+                events: Mutex::new(Vec::new()),
+                paste_error: None,
+            }
+        }
+
+        fn record(&self, event: String) {
+            self.events.lock().unwrap().push(event);
+        }
+
+        fn events(&self) -> Vec<String> {
+            self.events.lock().unwrap().clone()
+        }
+    }
+
+    impl Desktop for RecordingDesktop<'_> {
+        fn activate(&self, target: &FocusTarget) -> Result<(), String> {
+            self.record(format!("activate {target:?}"));
+            Ok(())
+        }
+
+        fn collapse_selection(&self) -> Result<(), String> {
+            self.record("collapse".to_string());
+            Ok(())
+        }
+
+        fn press_paste(&self) -> Result<(), String> {
+            let text = self.clipboard.read_text()?.unwrap_or_default();
+            self.record(format!("paste {text}"));
+            self.paste_error.map_or(Ok(()), |e| Err(e.to_string()))
+        }
+
+        fn type_text(&self, text: &str) -> Result<(), String> {
+            self.record(format!("type {text}"));
+            Ok(())
+        }
+    }
+
+    /// Clipboard whose owner cannot be read.
+    struct BrokenClipboard;
+
+    impl Clipboard for BrokenClipboard {
+        fn read_text(&self) -> Result<Option<String>, String> {
+            Err("broken".to_string())
+        }
+
+        fn write_text(&self, _text: &str) -> Result<(), String> {
+            Err("broken".to_string())
+        }
+
+        fn snapshot(&self) -> Result<ClipboardSnapshot, String> {
+            Err("broken".to_string())
+        }
+
+        fn restore(&self, _snapshot: &ClipboardSnapshot) -> Result<(), String> {
+            Err("broken".to_string())
+        }
+    }
+
+    fn copied_image() -> ClipboardSnapshot {
+        ClipboardSnapshot {
+            items: vec![ClipboardItem {
+                representations: vec![("image/png".to_string(), vec![0x89, b'P'])],
+            }],
+        }
+    }
+
+    #[test]
+    fn replace_pastes_into_the_source_app_and_restores_the_clipboard() {
+        let clipboard = MemoryClipboard::new(copied_image());
+        let desktop = RecordingDesktop::new(&clipboard);
+        let target = FocusTarget::Process(42);
+
+        write_back_with(
+            &clipboard,
+            &desktop,
+            Some(&target),
+            "fixed text",
+            WriteMode::Replace,
+            Duration::ZERO,
+        )
+        .unwrap();
+
+        assert_eq!(
+            desktop.events(),
+            ["activate Process(42)", "paste fixed text"]
+        );
+        assert_eq!(clipboard.contents(), copied_image());
+    }
+
+    #[test]
+    fn insert_moves_past_the_selection_first() {
+        let clipboard = MemoryClipboard::default();
+        let desktop = RecordingDesktop::new(&clipboard);
+
+        write_back_with(
+            &clipboard,
+            &desktop,
+            None,
+            "answer",
+            WriteMode::Insert,
+            Duration::ZERO,
+        )
+        .unwrap();
+
+        assert_eq!(desktop.events(), ["collapse", "paste answer"]);
+        assert!(clipboard.contents().is_empty());
+    }
+
+    #[test]
+    fn failed_paste_still_restores_the_clipboard() {
+        let clipboard = MemoryClipboard::new(copied_image());
+        let mut desktop = RecordingDesktop::new(&clipboard);
+        desktop.paste_error = Some("no permission");
+
+        let result = write_back_with(
+            &clipboard,
+            &desktop,
+            None,
+            "answer",
+            WriteMode::Replace,
+            Duration::ZERO,
+        );
+
+        assert_eq!(result, Err("no permission".to_string()));
+        assert_eq!(clipboard.contents(), copied_image());
+    }
+
+    #[test]
+    fn unusable_clipboard_falls_back_to_typing() {
+        let clipboard = BrokenClipboard;
+        let desktop = RecordingDesktop::new(&clipboard);
+        let target = FocusTarget::X11Window("0x3a00007".to_string());
+
+        write_back_with(
+            &clipboard,
+            &desktop,
+            Some(&target),
+            "answer",
+            WriteMode::Replace,
+            Duration::ZERO,
+        )
+        .unwrap();
+
+        assert_eq!(
+            desktop.events(),
+            ["activate X11Window(\"0x3a00007\")", "type answer"]
+        );
+        assert!(write_back_with(
+            &clipboard,
+            &desktop,
+            None,
+            "",
+            WriteMode::Replace,
+            Duration::ZERO
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn typing_chunks_keep_surrogate_pairs_together() {
+        assert_eq!(typing_chunks("abcdef", 4), ["abcd", "ef"]);
+        assert_eq!(typing_chunks("abc😀d", 4), ["abc", "😀d"]);
+        assert!(typing_chunks("", 4).is_empty());
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    #[test]
+    fn key_tools_follow_the_session() {
+        assert_eq!(
+            KeyTool::for_session(|key| key == "DISPLAY"),
+            Some(KeyTool::Xdotool)
+        );
+        assert_eq!(KeyTool::for_session(|_| true), Some(KeyTool::Wtype));
+        assert_eq!(KeyTool::for_session(|_| false), None);
+
+        let window = FocusTarget::X11Window("0x1".to_string());
+        assert_eq!(
+            KeyTool::Xdotool.activate(&window),
+            Some(ToolCall::new(
+                "xdotool",
+                &["windowactivate", "--sync", "0x1"]
+            ))
+        );
+        assert_eq!(KeyTool::Wtype.activate(&window), None);
+        assert_eq!(
+            KeyTool::Wtype.paste(),
+            ToolCall::new("wtype", &["-M", "ctrl", "v", "-m", "ctrl"])
+        );
+        assert_eq!(
+            KeyTool::Xdotool.type_text("-n hi"),
+            ToolCall::new("xdotool", &["type", "--clearmodifiers", "--", "-n hi"])
+        );
+    }
+}
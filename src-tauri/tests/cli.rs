@@ -0,0 +1,123 @@
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use macclaw_lib::cli::{build_prompt, run_prompt};
+use macclaw_lib::credentials::Credentials;
+use macclaw_lib::mock_gateway::{Fault, MockGateway, MockGatewayConfig};
+
+/// Stdout stand-in the test can read after `run_prompt` took ownership.
+#[derive(Clone, Default)]
+struct SharedOutput(Arc<Mutex<Vec<u8>>>);
+
+impl Write for SharedOutput {
+    fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(bytes)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl SharedOutput {
+    fn text(&self) -> String {
+        String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+    }
+}
+
+async fn start(config: MockGatewayConfig) -> MockGateway {
+    MockGateway::start(MockGatewayConfig {
+        chunk_delay: Duration::from_millis(5),
+        ..config
+    })
+    .await
+    .expect("mock gateway starts")
+}
+
+fn settings(gateway: &MockGateway) -> Credentials {
+    Credentials {
+        gateway_url: gateway.url(),
+        token: "secret".into(),
+        ..Credentials::default()
+    }
+}
+
+#[tokio::test]
+async fn streams_answer_to_output() {
+    let gateway = start(MockGatewayConfig {
+        token: Some("secret".into()),
+        replies: vec!["Looks good to me".into()],
+        chunks: 4,
+        ..MockGatewayConfig::default()
+    })
+    .await;
+    let output = SharedOutput::default();
+
+    run_prompt(settings(&gateway), "review this", output.clone())
+        .await
+        .unwrap();
+
+    assert_eq!(output.text(), "Looks good to me\n");
+}
+
+#[tokio::test]
+async fn sends_piped_input_with_instruction() {
+    // With no scripted replies the mock answers "Echo: <prompt>".
+    let gateway = start(MockGatewayConfig::default()).await;
+    let output = SharedOutput::default();
+    let prompt = build_prompt("review this", "+ fn main() {}\n").unwrap();
+
+    run_prompt(settings(&gateway), &prompt, output.clone())
+        .await
+        .unwrap();
+
+    assert_eq!(output.text(), format!("Echo: {prompt}\n"));
+}
+
+#[tokio::test]
+async fn chat_error_fails_the_run() {
+    let gateway = start(MockGatewayConfig {
+        fault: Some(Fault::ChatError("model overloaded".into())),
+        ..MockGatewayConfig::default()
+    })
+    .await;
+
+    let error = run_prompt(settings(&gateway), "hi", SharedOutput::default())
+        .await
+        .unwrap_err();
+
+    assert_eq!(error, "model overloaded");
+}
+
+#[tokio::test]
+async fn dropped_connection_fails_the_run() {
+    let gateway = start(MockGatewayConfig {
+        replies: vec!["a long answer that never finishes".into()],
+        chunks: 8,
+        fault: Some(Fault::DisconnectAfter(2)),
+        ..MockGatewayConfig::default()
+    })
+    .await;
+    let output = SharedOutput::default();
+
+    let result = run_prompt(settings(&gateway), "hi", output.clone()).await;
+
+    assert!(result.is_err());
+    assert!(!output.text().is_empty());
+}
+
+#[tokio::test]
+async fn rejected_token_fails_before_sending() {
+    let gateway = start(MockGatewayConfig {
+        token: Some("other".into()),
+        ..MockGatewayConfig::default()
+    })
+    .await;
+
+    assert!(
+        run_prompt(settings(&gateway), "hi", SharedOutput::default())
+            .await
+            .is_err()
+    );
+}
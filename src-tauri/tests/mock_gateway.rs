@@ -0,0 +1,259 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use macclaw_lib::credentials::Credentials;
+use macclaw_lib::gateway::{BotEvent, ChatRun, ConnectionState, GatewayClient, GatewaySink};
+use macclaw_lib::mock_gateway::{Fault, MockGateway, MockGatewayConfig};
+
+#[derive(Default)]
+struct RecordingSink {
+    states: Mutex<Vec<ConnectionState>>,
+    events: Mutex<Vec<BotEvent>>,
+}
+
+impl GatewaySink for RecordingSink {
+    fn on_state(&self, state: ConnectionState, _note: Option<&str>) {
+        self.states.lock().unwrap().push(state);
+    }
+
+    fn on_event(&self, _run: &ChatRun, event: BotEvent) {
+        self.events.lock().unwrap().push(event);
+    }
+}
+
+impl RecordingSink {
+    /// Wait until `done` matches the recorded events, or give up after 2s.
+    async fn wait_until(&self, done: impl Fn(&[BotEvent]) -> bool) -> Vec<BotEvent> {
+        for _ in 0..200 {
+            if done(&self.events.lock().unwrap()) {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        self.events.lock().unwrap().clone()
+    }
+
+    async fn wait_for_state(&self, state: ConnectionState) -> bool {
+        for _ in 0..200 {
+            if self.states.lock().unwrap().last() == Some(&state) {
+                return true;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        false
+    }
+}
+
+async fn start(config: MockGatewayConfig) -> MockGateway {
+    MockGateway::start(MockGatewayConfig {
+        chunk_delay: Duration::from_millis(5),
+        ..config
+    })
+    .await
+    .expect("mock gateway starts")
+}
+
+fn settings(gateway: &MockGateway, token: &str) -> Credentials {
+    Credentials {
+        gateway_url: gateway.url(),
+        token: token.into(),
+        ..Credentials::default()
+    }
+}
+
+fn is_terminal(event: &BotEvent) -> bool {
+    matches!(
+        event,
+        BotEvent::AssistantDone | BotEvent::Error { .. } | BotEvent::Aborted
+    )
+}
+
+#[tokio::test]
+async fn streams_scripted_reply_after_token_auth() {
+    let gateway = start(MockGatewayConfig {
+        token: Some("secret".into()),
+        replies: vec!["Hello from the mock".into()],
+        chunks: 3,
+        ..MockGatewayConfig::default()
+    })
+    .await;
+
+    let sink = Arc::new(RecordingSink::default());
+    let client = GatewayClient::new(sink.clone());
+    client.connect(settings(&gateway, "secret")).await.unwrap();
+    client.send_chat("hi").unwrap();
+
+    let events = sink
+        .wait_until(|events| events.iter().any(is_terminal))
+        .await;
+    client.disconnect();
+
+    let deltas: Vec<_> = events
+        .iter()
+        .filter_map(|event| match event {
+            BotEvent::AssistantDelta { text } => Some(text.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(deltas.len(), 3);
+    assert_eq!(deltas.last(), Some(&"Hello from the mock"));
+    assert!(events.ends_with(&[
+        BotEvent::Assistant {
+            text: "Hello from the mock".into()
+        },
+        BotEvent::AssistantDone,
+    ]));
+}
+
+#[tokio::test]
+async fn rejects_wrong_token() {
+    let gateway = start(MockGatewayConfig {
+        token: Some("secret".into()),
+        ..MockGatewayConfig::default()
+    })
+    .await;
+
+    let client = GatewayClient::new(Arc::new(RecordingSink::default()));
+    let result = client.connect(settings(&gateway, "wrong")).await;
+    client.disconnect();
+
+    assert_eq!(result, Err("invalid token or password".into()));
+}
+
+#[tokio::test]
+async fn accepts_password_auth() {
+    let gateway = start(MockGatewayConfig {
+        password: Some("hunter2".into()),
+        ..MockGatewayConfig::default()
+    })
+    .await;
+
+    let client = GatewayClient::new(Arc::new(RecordingSink::default()));
+    let creds = Credentials {
+        password: "hunter2".into(),
+        ..settings(&gateway, "")
+    };
+    let result = client.connect(creds).await;
+    client.disconnect();
+
+    assert_eq!(result, Ok(()));
+}
+
+#[tokio::test]
+async fn injected_chat_error_is_reported() {
+    let gateway = start(MockGatewayConfig {
+        fault: Some(Fault::ChatError("model overloaded".into())),
+        ..MockGatewayConfig::default()
+    })
+    .await;
+
+    let sink = Arc::new(RecordingSink::default());
+    let client = GatewayClient::new(sink.clone());
+    client.connect(settings(&gateway, "")).await.unwrap();
+    client.send_chat("hi").unwrap();
+
+    let events = sink
+        .wait_until(|events| events.iter().any(is_terminal))
+        .await;
+    client.disconnect();
+
+    assert_eq!(
+        events.last(),
+        Some(&BotEvent::Error {
+            text: "model overloaded".into()
+        })
+    );
+}
+
+#[tokio::test]
+async fn rejected_send_is_reported() {
+    let gateway = start(MockGatewayConfig {
+        fault: Some(Fault::RejectSend("session busy".into())),
+        ..MockGatewayConfig::default()
+    })
+    .await;
+
+    let sink = Arc::new(RecordingSink::default());
+    let client = GatewayClient::new(sink.clone());
+    client.connect(settings(&gateway, "")).await.unwrap();
+    client.send_chat("hi").unwrap();
+
+    let events = sink
+        .wait_until(|events| events.iter().any(is_terminal))
+        .await;
+    client.disconnect();
+
+    assert_eq!(
+        events,
+        vec![BotEvent::Error {
+            text: "session busy".into()
+        }]
+    );
+}
+
+#[tokio::test]
+async fn disconnect_mid_stream_drops_to_idle() {
+    let gateway = start(MockGatewayConfig {
+        fault: Some(Fault::DisconnectAfter(1)),
+        ..MockGatewayConfig::default()
+    })
+    .await;
+
+    let sink = Arc::new(RecordingSink::default());
+    let client = GatewayClient::new(sink.clone());
+    client.connect(settings(&gateway, "")).await.unwrap();
+    client.send_chat("hi").unwrap();
+
+    assert!(sink.wait_for_state(ConnectionState::Idle).await);
+    client.disconnect();
+
+    let events = sink.events.lock().unwrap().clone();
+    assert_eq!(events.len(), 1);
+    assert!(matches!(events[0], BotEvent::AssistantDelta { .. }));
+}
+
+#[tokio::test]
+async fn slow_handshake_still_connects() {
+    let gateway = start(MockGatewayConfig {
+        handshake_delay: Duration::from_millis(300),
+        ..MockGatewayConfig::default()
+    })
+    .await;
+
+    let sink = Arc::new(RecordingSink::default());
+    let client = GatewayClient::new(sink.clone());
+    let result = client.connect(settings(&gateway, "")).await;
+    client.disconnect();
+
+    assert_eq!(result, Ok(()));
+    assert!(sink
+        .states
+        .lock()
+        .unwrap()
+        .contains(&ConnectionState::Connected));
+}
+
+#[tokio::test]
+async fn abort_stops_streaming_reply() {
+    let gateway = start(MockGatewayConfig {
+        replies: vec!["a long answer that takes a while to stream".into()],
+        chunks: 20,
+        ..MockGatewayConfig::default()
+    })
+    .await;
+
+    let sink = Arc::new(RecordingSink::default());
+    let client = GatewayClient::new(sink.clone());
+    client.connect(settings(&gateway, "")).await.unwrap();
+    client.send_chat("hi").unwrap();
+    sink.wait_until(|events| !events.is_empty()).await;
+    client.abort_chat().unwrap();
+
+    let events = sink
+        .wait_until(|events| events.iter().any(is_terminal))
+        .await;
+    client.disconnect();
+
+    assert_eq!(events.last(), Some(&BotEvent::Aborted));
+    assert!(!events.contains(&BotEvent::AssistantDone));
+}
@@ -12,24 +12,73 @@ import { ConnectForm } from "./components/ConnectForm";
 import { ResponsePanel } from "./components/ResponsePanel";
 import { SettingsForm } from "./components/SettingsForm";
 import type { PanelMode } from "./constants/panel";
+import {
+  resolveRunPrompt,
+  type RunPromptPayload,
+} from "./features/panel/run-prompt";
 import { resolveSubmitAction } from "./features/panel/submit-resolver";
 import {
   DEFAULT_INPUT_PLACEHOLDER,
   type SelectionContext,
 } from "./features/panel/types";
 import { useSelectionPrefill } from "./features/panel/useSelectionPrefill";
+import { useShortcutActions } from "./features/panel/useShortcutActions";
 import { useCommandInput } from "./hooks/useCommandInput";
 import { useWsClient } from "./hooks/useWsClient";
 import { usePanelLifecycle } from "./hooks/usePanelLifecycle";
 import { usePanelResize } from "./hooks/usePanelResize";
 import { safeTrim } from "./lib/commands";
+import {
+  formatRecentHistory,
+  formatSearchResults,
+  listHistory,
+  searchHistory,
+  type ChatContext,
+  type HistorySettings,
+} from "./lib/history";
+import { jobResponse, type JobSnapshot, type NotchSettings } from "./lib/jobs";
 import { clearMediaCache } from "./lib/media-cache";
+import { writeBackResponse, type WriteMode } from "./lib/panel-window";
+import {
+  listDisplays,
+  resetPanelLayout,
+  type DisplayInfo,
+  type PanelSettings,
+} from "./lib/placement";
+import {
+  activateProfile,
+  formatProfileList,
+  listProfiles,
+} from "./lib/profiles";
+import {
+  normalizeRedactionSettings,
+  redactionNotice,
+  type RedactionSettings,
+} from "./lib/redaction";
+import {
+  SELECTION_STRATEGY_LABELS,
+  describeSourceApp,
+  type SelectionSettings,
+} from "./lib/selection";
 import {
   DEFAULT_SETTINGS,
+  describeConfigIssue,
+  fromCredentialsPayload,
+  loadConfigIssues,
   loadSettings,
   saveSettings,
   type AppSettings,
+  type KeychainCredentials,
 } from "./lib/settings";
+import {
+  describeShortcutReport,
+  loadShortcutIssues,
+  makeShortcutAction,
+  updateShortcuts,
+  type ShortcutBinding,
+} from "./lib/shortcuts";
+
+const HISTORY_RESULTS = 10;
 
 function resolvePanelMode(params: {
   showHints: boolean;
@@ -52,6 +101,8 @@ function resolvePanelMode(params: {
 export function App() {
   const [settings, setSettings] = useState<AppSettings>(DEFAULT_SETTINGS);
   const [settingsLoaded, setSettingsLoaded] = useState(false);
+  const [configIssues, setConfigIssues] = useState<string[]>([]);
+  const [shortcutIssues, setShortcutIssues] = useState<string[]>([]);
   const [inputPlaceholder, setInputPlaceholder] = useState(
     DEFAULT_INPUT_PLACEHOLDER,
   );
@@ -65,12 +116,29 @@ export function App() {
 
   const [showSettingsForm, setShowSettingsForm] = useState(false);
   const [settingsShortcuts, setSettingsShortcuts] = useState<
-    [string, string, string]
+    ShortcutBinding[]
   >(DEFAULT_SETTINGS.shortcuts);
+  const [settingsHistory, setSettingsHistory] = useState<HistorySettings>(
+    DEFAULT_SETTINGS.history,
+  );
+  const [settingsSelection, setSettingsSelection] = useState<SelectionSettings>(
+    DEFAULT_SETTINGS.selection,
+  );
+  const [settingsRedaction, setSettingsRedaction] = useState<RedactionSettings>(
+    DEFAULT_SETTINGS.redaction,
+  );
+  const [settingsPanel, setSettingsPanel] = useState<PanelSettings>(
+    DEFAULT_SETTINGS.panel,
+  );
+  const [settingsNotch, setSettingsNotch] = useState<NotchSettings>(
+    DEFAULT_SETTINGS.notch,
+  );
+  const [displays, setDisplays] = useState<DisplayInfo[]>([]);
 
   const [backgroundMode, setBackgroundMode] = useState(false);
   const backgroundModeRef = useRef(false);
   const preserveNextOpenRef = useRef(false);
+  const writingBackRef = useRef(false);
 
   const inputRef = useRef<HTMLInputElement>(null);
   const responsePanelRef = useRef<HTMLElement>(null);
@@ -108,6 +176,10 @@ export function App() {
       setSettings(loaded);
       setSettingsLoaded(true);
     });
+    void loadConfigIssues().then((issues) => {
+      setConfigIssues(issues.map(describeConfigIssue));
+    });
+    void loadShortcutIssues().then(setShortcutIssues);
   }, []);
 
   const resetResponseState = useCallback(() => {
@@ -130,16 +202,37 @@ export function App() {
     clearMediaCache();
   }, [resetResponseState, setHintIndex, setInput]);
 
+  const showJob = useCallback(
+    (job: JobSnapshot) => {
+      const response = jobResponse(job);
+      setActiveQuery(job.label);
+      setAssistantText(response.assistantText);
+      setStreamingText(response.streamingText);
+      streamingTextRef.current = response.streamingText;
+      setIsThinking(response.thinking);
+      isThinkingRef.current = response.thinking;
+    },
+    [
+      setAssistantText,
+      setStreamingText,
+      setIsThinking,
+      isThinkingRef,
+      streamingTextRef,
+    ],
+  );
+
   usePanelLifecycle({
     client,
     settings,
     settingsLoaded,
     preserveNextOpenRef,
+    writingBackRef,
     backgroundModeRef,
     isThinkingRef,
     streamingTextRef,
     inputRef,
     clearConversation,
+    showJob,
     setBackgroundMode,
   });
 
@@ -158,6 +251,86 @@ export function App() {
     setSelectionContext,
   });
 
+  const sendPrompt = useCallback(
+    (outgoingPrompt: string, queryLabel: string, context: ChatContext) => {
+      setActiveQuery(queryLabel);
+      setAssistantText("");
+      setStreamingText("");
+      streamingTextRef.current = "";
+      setIsThinking(true);
+      isThinkingRef.current = true;
+      setShowConnectForm(false);
+      setSelectionContext(null);
+
+      if (!settingsLoaded) {
+        setAssistantText("Loading settings...");
+        setIsThinking(false);
+        isThinkingRef.current = false;
+        return;
+      }
+
+      if (!client.connected) {
+        setAssistantText(
+          connectionState === "connecting"
+            ? "Connecting to gateway..."
+            : "No connection to gateway.",
+        );
+        setIsThinking(false);
+        isThinkingRef.current = false;
+        return;
+      }
+
+      try {
+        client.sendChatMessage(outgoingPrompt, settings, context);
+      } catch (error) {
+        setAssistantText(`Send failed: ${String(error)}`);
+        setIsThinking(false);
+        isThinkingRef.current = false;
+      }
+    },
+    [
+      client,
+      connectionState,
+      isThinkingRef,
+      setAssistantText,
+      setIsThinking,
+      setStreamingText,
+      settings,
+      settingsLoaded,
+      streamingTextRef,
+    ],
+  );
+
+  const handleProfileActivated = useCallback(
+    (creds: KeychainCredentials) => {
+      const nextSettings = fromCredentialsPayload(creds);
+      setSettings(nextSettings);
+      client.connect(nextSettings);
+    },
+    [client],
+  );
+
+  const handleRunPrompt = useCallback(
+    (payload: RunPromptPayload) => {
+      const request = resolveRunPrompt(payload);
+      if (!request) {
+        return;
+      }
+
+      setShowSettingsForm(false);
+      setInput("");
+      setInputPlaceholder(DEFAULT_INPUT_PLACEHOLDER);
+      sendPrompt(request.outgoingPrompt, request.queryLabel, request.context);
+    },
+    [sendPrompt, setInput],
+  );
+
+  useShortcutActions({
+    preserveNextOpenRef,
+    onProfileActivated: handleProfileActivated,
+    onRunPrompt: handleRunPrompt,
+  });
+
   // Which panel mode?
   const panelMode = resolvePanelMode({
     showHints,
@@ -199,6 +372,40 @@ export function App() {
     }
   };
 
+  const runProfileCommand = async (name: string | null) => {
+    try {
+      if (name === null) {
+        setAssistantText(formatProfileList(await listProfiles()));
+        return;
+      }
+
+      const nextSettings = await activateProfile(name);
+      setSettings(nextSettings);
+      client.connect(nextSettings);
+      setAssistantText(
+        `Switched to profile "${name}" (${nextSettings.gatewayUrl})`,
+      );
+    } catch (error) {
+      setAssistantText(
+        `Profile error: ${error instanceof Error ? error.message : String(error)}`,
+      );
+    }
+  };
+
+  const runHistoryCommand = async (query: string) => {
+    try {
+      setAssistantText(
+        query
+          ? formatSearchResults(query, await searchHistory(query, HISTORY_RESULTS))
+          : formatRecentHistory(await listHistory(HISTORY_RESULTS)),
+      );
+    } catch (error) {
+      setAssistantText(
+        `History error: ${error instanceof Error ? error.message : String(error)}`,
+      );
+    }
+  };
+
   const handleSubmit = (event: FormEvent) => {
     event.preventDefault();
 
@@ -209,6 +416,7 @@ export function App() {
       gatewayUrl: settings.gatewayUrl,
       token: settings.token,
       connectionState,
+      configIssues: [...configIssues, ...shortcutIssues],
     });
 
     if (action.kind === "noop") {
@@ -229,6 +437,12 @@ export function App() {
 
     if (action.kind === "open_settings") {
       setSettingsShortcuts([...settings.shortcuts]);
+      setSettingsHistory(settings.history);
+      setSettingsSelection(settings.selection);
+      setSettingsRedaction(settings.redaction);
+      setSettingsPanel(settings.panel);
+      setSettingsNotch(settings.notch);
+      void listDisplays().then(setDisplays);
       setShowSettingsForm(true);
       setShowConnectForm(false);
       resetResponseState();
@@ -244,67 +458,99 @@ export function App() {
       return;
     }
 
-    setActiveQuery(action.queryLabel);
-    setAssistantText("");
-    setStreamingText("");
-    streamingTextRef.current = "";
-    setIsThinking(true);
-    isThinkingRef.current = true;
-    setShowConnectForm(false);
-    setSelectionContext(null);
-
-    if (!settingsLoaded) {
-      setAssistantText("Loading settings...");
+    if (action.kind === "list_profiles" || action.kind === "switch_profile") {
+      const name = action.kind === "switch_profile" ? action.name : null;
+      setActiveQuery(name === null ? "/profile" : `/profile ${name}`);
+      setAssistantText("");
+      setStreamingText("");
       setIsThinking(false);
-      isThinkingRef.current = false;
+      setShowConnectForm(false);
+      void runProfileCommand(name);
       return;
     }
 
-    if (!client.connected) {
-      setAssistantText(
-        connectionState === "connecting"
-          ? "Connecting to gateway..."
-          : "No connection to gateway.",
-      );
+    if (action.kind === "search_history") {
+      setActiveQuery(action.query ? `/history ${action.query}` : "/history");
+      setAssistantText("");
+      setStreamingText("");
       setIsThinking(false);
-      isThinkingRef.current = false;
+      setShowConnectForm(false);
+      void runHistoryCommand(action.query);
       return;
     }
 
-    try {
-      client.sendChatMessage(action.outgoingPrompt, settings);
-    } catch (error) {
-      setAssistantText(`Send failed: ${String(error)}`);
-      setIsThinking(false);
-      isThinkingRef.current = false;
-    }
+    sendPrompt(action.outgoingPrompt, action.queryLabel, action.context);
   };
 
   const handleShortcutChange = useCallback(
-    (index: number, value: string) => {
-      setSettingsShortcuts((prev) => {
-        const next: [string, string, string] = [...prev];
-        next[index] = value;
-        return next;
-      });
+    (index: number, binding: ShortcutBinding) => {
+      setSettingsShortcuts((prev) =>
+        prev.map((current, i) => (i === index ? binding : current)),
+      );
     },
     [],
   );
 
+  const handleShortcutAdd = useCallback(() => {
+    setSettingsShortcuts((prev) => [
+      ...prev,
+      { accelerator: "", action: makeShortcutAction("toggle_panel") },
+    ]);
+  }, []);
+
+  const handleShortcutRemove = useCallback((index: number) => {
+    setSettingsShortcuts((prev) => prev.filter((_, i) => i !== index));
+  }, []);
+
+  // Rust saves the reset size right away; keep the other unsaved edits.
+  const handleLayoutReset = useCallback(async () => {
+    const reset = await resetPanelLayout();
+    if (reset) {
+      setSettingsPanel((prev) => ({ ...prev, expanded: reset.expanded }));
+      setSettings((prev) => ({
+        ...prev,
+        panel: { ...prev.panel, expanded: reset.expanded },
+      }));
+    }
+  }, []);
+
   const handleSaveSettings = async (event: FormEvent) => {
     event.preventDefault();
     setShowSettingsForm(false);
 
+    // Rows whose hotkey was never recorded are dropped.
+    const shortcuts = settingsShortcuts.filter((b) => b.accelerator);
+
+    // Saved before the shortcuts, which Rust only stores once registered.
+    const saved = {
+      ...settings,
+      history: settingsHistory,
+      selection: settingsSelection,
+      redaction: normalizeRedactionSettings(settingsRedaction),
+      panel: settingsPanel,
+      notch: settingsNotch,
+    };
+    await saveSettings(saved);
+    setSettings(saved);
+    // Saving re-reads the settings file, which may have turned up problems.
+    void loadConfigIssues().then((issues) => {
+      setConfigIssues(issues.map(describeConfigIssue));
+    });
+
     try {
-      const { invoke } = await import("@tauri-apps/api/core");
-      await invoke("update_shortcuts", { shortcuts: settingsShortcuts });
+      const report = await updateShortcuts(shortcuts);
+      const problems = describeShortcutReport(report);
+      setShortcutIssues(problems);
 
-      const nextSettings: AppSettings = {
-        ...settings,
-        shortcuts: settingsShortcuts,
-      };
-      await saveSettings(nextSettings);
-      setSettings(nextSettings);
+      if (report.applied) {
+        setSettings({ ...saved, shortcuts });
+      }
+      if (problems.length > 0) {
+        setActiveQuery("/settings");
+        setAssistantText(
+          `${report.applied ? "Shortcuts saved with problems" : "Shortcuts not saved"}:\n${problems.map((problem) => `- ${problem}`).join("\n")}`,
+        );
+      }
     } catch (error) {
       setActiveQuery("/settings");
       setAssistantText(
@@ -320,6 +566,28 @@ export function App() {
     }
   }, [streamingText, assistantText]);
 
+  const handleWriteBack = useCallback(
+    (mode: WriteMode) => {
+      const text = safeTrim(streamingText) || safeTrim(assistantText);
+      if (!text) {
+        return;
+      }
+      // Rust hides the panel while it writes and shows it again if writing
+      // fails, so the answer has to survive both.
+      writingBackRef.current = true;
+      writeBackResponse(text, mode)
+        .catch((error: unknown) => {
+          setInputPlaceholder(
+            `Write back failed: ${error instanceof Error ? error.message : String(error)}`,
+          );
+        })
+        .finally(() => {
+          writingBackRef.current = false;
+        });
+    },
+    [streamingText, assistantText],
+  );
+
   const handleInputChange = useCallback(
     (value: string) => {
       if (inputPlaceholder !== DEFAULT_INPUT_PLACEHOLDER) {
@@ -353,8 +621,20 @@ export function App() {
     inputRef.current?.focus();
   }, []);
 
+  const selectionSource = selectionContext?.source
+    ? ` · ${SELECTION_STRATEGY_LABELS[selectionContext.source].toLowerCase()}`
+    : "";
+  const selectionRedaction = redactionNotice(selectionContext?.redacted);
+  const selectionLength = selectionContext?.originalChars
+    ? `${selectionContext.chars} of ${selectionContext.originalChars}`
+    : `${selectionContext?.chars}`;
+  const selectionApp = selectionContext?.app
+    ? ` · from ${describeSourceApp(selectionContext.app)}`
+    : "";
   const selectionBadge = selectionContext
-    ? `selected: ${selectionContext.chars} chars`
+    ? `selected: ${selectionLength} chars${selectionApp}${selectionSource}${
+        selectionRedaction ? ` · ${selectionRedaction}` : ""
+      }`
     : undefined;
 
   return (
@@ -390,6 +670,20 @@ export function App() {
         <SettingsForm
           shortcuts={settingsShortcuts}
           onShortcutChange={handleShortcutChange}
+          onShortcutAdd={handleShortcutAdd}
+          onShortcutRemove={handleShortcutRemove}
+          history={settingsHistory}
+          onHistoryChange={setSettingsHistory}
+          selection={settingsSelection}
+          onSelectionChange={setSettingsSelection}
+          redaction={settingsRedaction}
+          onRedactionChange={setSettingsRedaction}
+          panel={settingsPanel}
+          onPanelChange={setSettingsPanel}
+          displays={displays}
+          onLayoutReset={() => void handleLayoutReset()}
+          notch={settingsNotch}
+          onNotchChange={setSettingsNotch}
           onSubmit={handleSaveSettings}
         />
       ) : (
@@ -400,6 +694,7 @@ export function App() {
           isStreaming={!!safeTrim(streamingText)}
           isThinking={isThinking}
           onCopy={handleCopy}
+          onWriteBack={handleWriteBack}
           client={client}
         />
       )}
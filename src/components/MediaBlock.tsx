@@ -1,12 +1,12 @@
 import { useCallback, useState } from "react";
-import type { WsClient } from "../lib/ws-client";
+import type { GatewayClient } from "../lib/ws-client";
 import { getMediaUrl, getMediaCacheEntry } from "../lib/media-cache";
 import { mediaKind, fileNameFromPath } from "../lib/media-types";
 
 interface MediaBlockProps {
   filePath: string;
   mimeType: string;
-  client: WsClient;
+  client: GatewayClient;
 }
 
 export function MediaBlock({ filePath, mimeType, client }: MediaBlockProps) {
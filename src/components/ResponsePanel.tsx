@@ -2,7 +2,8 @@ import { forwardRef, useCallback, useMemo } from "react";
 import Markdown from "react-markdown";
 import remarkGfm from "remark-gfm";
 import rehypeHighlight from "rehype-highlight";
-import type { WsClient } from "../lib/ws-client";
+import type { WriteMode } from "../lib/panel-window";
+import type { GatewayClient } from "../lib/ws-client";
 import { parseContent } from "../lib/parse-content";
 import { MediaBlock, InlineImage } from "./MediaBlock";
 
@@ -12,7 +13,8 @@ type ResponsePanelProps = {
   isStreaming: boolean;
   isThinking: boolean;
   onCopy: () => void;
-  client: WsClient;
+  onWriteBack: (mode: WriteMode) => void;
+  client: GatewayClient;
 };
 
 function CodeBlock({
@@ -88,7 +90,10 @@ function MarkdownBlock({ children }: { children: string }) {
 }
 
 export const ResponsePanel = forwardRef<HTMLElement, ResponsePanelProps>(
-  function ResponsePanel({ activeQuery, response, isStreaming, isThinking, onCopy, client }, ref) {
+  function ResponsePanel(
+    { activeQuery, response, isStreaming, isThinking, onCopy, onWriteBack, client },
+    ref,
+  ) {
     if (!activeQuery) {
       return null;
     }
@@ -100,6 +105,7 @@ export const ResponsePanel = forwardRef<HTMLElement, ResponsePanelProps>(
       [response, isStreaming],
     );
     const canCopy = !!response && !isThinking;
+    const canWriteBack = canCopy && !isStreaming;
 
     return (
       <section className="dropdown-panel" ref={ref}>
@@ -124,6 +130,47 @@ export const ResponsePanel = forwardRef<HTMLElement, ResponsePanelProps>(
             </svg>
           </button>
         )}
+        {canWriteBack && (
+          <>
+            <button
+              className="copy-btn write-back-btn insert-btn"
+              onClick={() => onWriteBack("insert")}
+              title="Insert after the selection"
+            >
+              <svg
+                width="14"
+                height="14"
+                viewBox="0 0 24 24"
+                fill="none"
+                stroke="currentColor"
+                strokeWidth="1.8"
+                strokeLinecap="round"
+                strokeLinejoin="round"
+              >
+                <path d="M12 5v14M5 12h14" />
+              </svg>
+            </button>
+            <button
+              className="copy-btn write-back-btn replace-btn"
+              onClick={() => onWriteBack("replace")}
+              title="Replace the selection"
+            >
+              <svg
+                width="14"
+                height="14"
+                viewBox="0 0 24 24"
+                fill="none"
+                stroke="currentColor"
+                strokeWidth="1.8"
+                strokeLinecap="round"
+                strokeLinejoin="round"
+              >
+                <path d="M9 10l-5 5 5 5" />
+                <path d="M20 4v7a4 4 0 0 1-4 4H4" />
+              </svg>
+            </button>
+          </>
+        )}
         <div className="answer-body">
           {response ? (
             segments ? (
@@ -1,50 +1,577 @@
 import type { FormEvent, KeyboardEvent } from "react";
+import type { HistorySettings } from "../lib/history";
+import type { NotchSettings } from "../lib/jobs";
+import {
+  DOCK_EDGES,
+  DOCK_EDGE_LABELS,
+  LAYOUT_PRESETS,
+  LAYOUT_PRESET_LABELS,
+  MIN_PANEL_SIZE,
+  PLACEMENT_POLICIES,
+  PLACEMENT_POLICY_LABELS,
+  displayOptions,
+  parsePanelDimension,
+  type DisplayInfo,
+  type DockEdge,
+  type LayoutPreset,
+  type PanelSettings,
+  type PlacementPolicy,
+} from "../lib/placement";
+import type { RedactionSettings } from "../lib/redaction";
+import {
+  SELECTION_STRATEGY_LABELS,
+  TRUNCATION_MODES,
+  TRUNCATION_MODE_LABELS,
+  moveStrategyUp,
+  strategyRows,
+  toggleStrategy,
+  type SelectionSettings,
+  type TruncationMode,
+} from "../lib/selection";
 import { keyEventToShortcut } from "../lib/shortcut-utils";
+import {
+  SHORTCUT_ACTION_LABELS,
+  SHORTCUT_ACTION_TYPES,
+  makeShortcutAction,
+  shortcutActionArgument,
+  type ShortcutBinding,
+  type ShortcutActionType,
+} from "../lib/shortcuts";
 
 type SettingsFormProps = {
-  shortcuts: [string, string, string];
-  onShortcutChange: (index: number, value: string) => void;
+  shortcuts: ShortcutBinding[];
+  onShortcutChange: (index: number, binding: ShortcutBinding) => void;
+  onShortcutAdd: () => void;
+  onShortcutRemove: (index: number) => void;
+  history: HistorySettings;
+  onHistoryChange: (history: HistorySettings) => void;
+  selection: SelectionSettings;
+  onSelectionChange: (selection: SelectionSettings) => void;
+  redaction: RedactionSettings;
+  onRedactionChange: (redaction: RedactionSettings) => void;
+  panel: PanelSettings;
+  onPanelChange: (panel: PanelSettings) => void;
+  displays: DisplayInfo[];
+  onLayoutReset: () => void;
+  notch: NotchSettings;
+  onNotchChange: (notch: NotchSettings) => void;
   onSubmit: (event: FormEvent) => void;
 };
 
-const LABELS = ["Shortcut 1", "Shortcut 2", "Shortcut 3"] as const;
+const ARGUMENT_PLACEHOLDERS: Partial<Record<ShortcutActionType, string>> = {
+  open_profile: "Profile name",
+  run_prompt: "Prompt text",
+};
+
+/** Empty or non-positive input means "no limit". */
+function parseLimit(value: string): number | null {
+  const limit = Number.parseInt(value, 10);
+  return Number.isFinite(limit) && limit > 0 ? limit : null;
+}
+
+/** Non-negative whole number, keeping `fallback` for unusable input. */
+function parseCount(value: string, fallback: number): number {
+  const count = Number.parseInt(value, 10);
+  return Number.isFinite(count) && count >= 0 ? count : fallback;
+}
+
+function replaceAt<T>(items: T[], index: number, item: T): T[] {
+  return items.map((current, i) => (i === index ? item : current));
+}
 
 function handleKeyDown(
   e: KeyboardEvent<HTMLInputElement>,
   index: number,
-  onChange: (index: number, value: string) => void,
+  binding: ShortcutBinding,
+  onChange: (index: number, binding: ShortcutBinding) => void,
 ) {
   e.preventDefault();
   const shortcut = keyEventToShortcut(e.nativeEvent);
   if (shortcut) {
-    onChange(index, shortcut);
+    onChange(index, { ...binding, accelerator: shortcut });
   }
 }
 
 export function SettingsForm({
   shortcuts,
   onShortcutChange,
+  onShortcutAdd,
+  onShortcutRemove,
+  history,
+  onHistoryChange,
+  selection,
+  onSelectionChange,
+  redaction,
+  onRedactionChange,
+  panel,
+  onPanelChange,
+  displays,
+  onLayoutReset,
+  notch,
+  onNotchChange,
   onSubmit,
 }: SettingsFormProps) {
   return (
     <section className="dropdown-panel">
       <form className="connect-form" onSubmit={onSubmit}>
         <p className="settings-description">
-          Press a key combination in each field to record a new hotkey.
+          Press a key combination in a field to record a new hotkey.
+        </p>
+        {shortcuts.map((binding, i) => {
+          const placeholder = ARGUMENT_PLACEHOLDERS[binding.action.type];
+          const argument = shortcutActionArgument(binding.action);
+          return (
+            <div className="connect-field shortcut-field" key={i}>
+              <select
+                className="connect-label shortcut-action"
+                value={binding.action.type}
+                onChange={(e) =>
+                  onShortcutChange(i, {
+                    ...binding,
+                    action: makeShortcutAction(
+                      e.target.value as ShortcutActionType,
+                      argument,
+                    ),
+                  })
+                }
+              >
+                {SHORTCUT_ACTION_TYPES.map((type) => (
+                  <option key={type} value={type}>
+                    {SHORTCUT_ACTION_LABELS[type]}
+                  </option>
+                ))}
+              </select>
+              {placeholder ? (
+                <input
+                  className="connect-input shortcut-argument"
+                  value={argument}
+                  placeholder={placeholder}
+                  onChange={(e) =>
+                    onShortcutChange(i, {
+                      ...binding,
+                      action: makeShortcutAction(
+                        binding.action.type,
+                        e.target.value,
+                      ),
+                    })
+                  }
+                />
+              ) : null}
+              <input
+                className="connect-input"
+                value={binding.accelerator}
+                placeholder="Press keys"
+                readOnly
+                onKeyDown={(e) => handleKeyDown(e, i, binding, onShortcutChange)}
+                autoFocus={i === 0}
+              />
+              <button
+                type="button"
+                className="shortcut-remove"
+                aria-label="Remove shortcut"
+                onClick={() => onShortcutRemove(i)}
+              >
+                ×
+              </button>
+            </div>
+          );
+        })}
+        <div className="connect-field history-field">
+          <label className="connect-label history-toggle">
+            <input
+              type="checkbox"
+              checked={history.enabled}
+              onChange={(e) =>
+                onHistoryChange({ ...history, enabled: e.target.checked })
+              }
+            />
+            History
+          </label>
+          <input
+            className="connect-input"
+            type="number"
+            min={1}
+            value={history.maxAgeDays ?? ""}
+            placeholder="Keep days (all)"
+            disabled={!history.enabled}
+            onChange={(e) =>
+              onHistoryChange({ ...history, maxAgeDays: parseLimit(e.target.value) })
+            }
+          />
+          <input
+            className="connect-input"
+            type="number"
+            min={1}
+            value={history.maxEntries ?? ""}
+            placeholder="Keep entries (all)"
+            disabled={!history.enabled}
+            onChange={(e) =>
+              onHistoryChange({ ...history, maxEntries: parseLimit(e.target.value) })
+            }
+          />
+        </div>
+        <div className="connect-field history-field">
+          <select
+            className="connect-label shortcut-action"
+            value={panel.placement}
+            title="Open the panel on"
+            onChange={(e) =>
+              onPanelChange({ ...panel, placement: e.target.value as PlacementPolicy })
+            }
+          >
+            {PLACEMENT_POLICIES.map((policy) => (
+              <option key={policy} value={policy}>
+                {PLACEMENT_POLICY_LABELS[policy]}
+              </option>
+            ))}
+          </select>
+          {panel.placement === "display" ? (
+            <select
+              className="connect-input"
+              value={panel.display}
+              title="Display"
+              onChange={(e) => onPanelChange({ ...panel, display: e.target.value })}
+            >
+              {displayOptions(displays, panel.display).map((option) => (
+                <option key={option.value} value={option.value}>
+                  {option.label}
+                </option>
+              ))}
+            </select>
+          ) : null}
+        </div>
+        <div className="connect-field history-field">
+          <select
+            className="connect-label shortcut-action"
+            value={panel.preset}
+            title="Panel layout"
+            onChange={(e) =>
+              onPanelChange({ ...panel, preset: e.target.value as LayoutPreset })
+            }
+          >
+            {LAYOUT_PRESETS.map((preset) => (
+              <option key={preset} value={preset}>
+                {LAYOUT_PRESET_LABELS[preset]}
+              </option>
+            ))}
+          </select>
+          {panel.preset === "sidebar" ? (
+            <select
+              className="connect-input"
+              value={panel.dock}
+              title="Dock to"
+              onChange={(e) =>
+                onPanelChange({ ...panel, dock: e.target.value as DockEdge })
+              }
+            >
+              {DOCK_EDGES.map((edge) => (
+                <option key={edge} value={edge}>
+                  {DOCK_EDGE_LABELS[edge]}
+                </option>
+              ))}
+            </select>
+          ) : null}
+        </div>
+        <div className="connect-field history-field">
+          <span className="connect-label">Response size</span>
+          {/* Lets smaller values through while typing; Rust clamps on use. */}
+          <input
+            className="connect-input"
+            type="number"
+            min={MIN_PANEL_SIZE.width}
+            value={panel.expanded.width}
+            title="Width in points"
+            onChange={(e) =>
+              onPanelChange({
+                ...panel,
+                expanded: {
+                  ...panel.expanded,
+                  width: parsePanelDimension(e.target.value, 1, panel.expanded.width),
+                },
+              })
+            }
+          />
+          <input
+            className="connect-input"
+            type="number"
+            min={MIN_PANEL_SIZE.height}
+            value={panel.expanded.height}
+            title="Largest height in points"
+            onChange={(e) =>
+              onPanelChange({
+                ...panel,
+                expanded: {
+                  ...panel.expanded,
+                  height: parsePanelDimension(e.target.value, 1, panel.expanded.height),
+                },
+              })
+            }
+          />
+          <button
+            type="button"
+            className="connect-button shortcut-add"
+            title="Forget where the panel was dragged and its response size"
+            onClick={onLayoutReset}
+          >
+            Reset layout
+          </button>
+        </div>
+        <div className="connect-field history-field">
+          <span className="connect-label">Notch</span>
+          <input
+            className="connect-input"
+            type="number"
+            min={1}
+            value={notch.autoHideSecs ?? ""}
+            placeholder="Keep until clicked"
+            title="Seconds the notch stays after the last answer; errors always stay"
+            onChange={(e) =>
+              onNotchChange({ autoHideSecs: parseLimit(e.target.value) })
+            }
+          />
+        </div>
+        <p className="settings-description">
+          Selection capture tries the checked sources top to bottom.
+        </p>
+        {strategyRows(selection.strategies).map(({ strategy, enabled }, i) => (
+          <div className="connect-field selection-field" key={strategy}>
+            <label className="connect-label history-toggle">
+              <input
+                type="checkbox"
+                checked={enabled}
+                onChange={(e) =>
+                  onSelectionChange({
+                    ...selection,
+                    strategies: toggleStrategy(
+                      selection.strategies,
+                      strategy,
+                      e.target.checked,
+                    ),
+                  })
+                }
+              />
+              {SELECTION_STRATEGY_LABELS[strategy]}
+            </label>
+            {enabled && i > 0 ? (
+              <button
+                type="button"
+                className="shortcut-remove"
+                aria-label="Try earlier"
+                onClick={() =>
+                  onSelectionChange({
+                    ...selection,
+                    strategies: moveStrategyUp(selection.strategies, strategy),
+                  })
+                }
+              >
+                ↑
+              </button>
+            ) : null}
+          </div>
+        ))}
+        <div className="connect-field history-field">
+          <input
+            className="connect-input"
+            type="number"
+            min={0}
+            value={selection.copyWaitMs}
+            title="Copy wait (ms)"
+            onChange={(e) =>
+              onSelectionChange({
+                ...selection,
+                copyWaitMs: parseCount(e.target.value, selection.copyWaitMs),
+              })
+            }
+          />
+          <input
+            className="connect-input"
+            type="number"
+            min={1}
+            value={selection.copyPollMs}
+            title="Copy poll interval (ms)"
+            onChange={(e) =>
+              onSelectionChange({
+                ...selection,
+                copyPollMs: parseCount(e.target.value, selection.copyPollMs),
+              })
+            }
+          />
+          <input
+            className="connect-input"
+            type="number"
+            min={1}
+            value={selection.clipboardHistory}
+            title="Clipboard history entries"
+            onChange={(e) =>
+              onSelectionChange({
+                ...selection,
+                clipboardHistory: parseCount(
+                  e.target.value,
+                  selection.clipboardHistory,
+                ),
+              })
+            }
+          />
+        </div>
+        <div className="connect-field history-field">
+          <select
+            className="connect-label shortcut-action"
+            value={selection.truncation}
+            title="Long selections"
+            onChange={(e) =>
+              onSelectionChange({
+                ...selection,
+                truncation: e.target.value as TruncationMode,
+              })
+            }
+          >
+            {TRUNCATION_MODES.map((mode) => (
+              <option key={mode} value={mode}>
+                {TRUNCATION_MODE_LABELS[mode]}
+              </option>
+            ))}
+          </select>
+          <input
+            className="connect-input"
+            type="number"
+            min={200}
+            value={selection.maxChars}
+            title="Selection limit (chars)"
+            onChange={(e) =>
+              onSelectionChange({
+                ...selection,
+                maxChars: parseCount(e.target.value, selection.maxChars),
+              })
+            }
+          />
+          <label className="connect-label history-toggle">
+            <input
+              type="checkbox"
+              checked={selection.truncateAtLines}
+              onChange={(e) =>
+                onSelectionChange({ ...selection, truncateAtLines: e.target.checked })
+              }
+            />
+            At lines
+          </label>
+        </div>
+        <p className="settings-description">
+          Prompts filled in for selections from an app (name or bundle id).
         </p>
-        {LABELS.map((label, i) => (
-          <div className="connect-field" key={label}>
-            <label className="connect-label">{label}</label>
+        {selection.appPrompts.map((rule, i) => (
+          <div className="connect-field shortcut-field" key={i}>
+            <input
+              className="connect-input shortcut-argument"
+              value={rule.app}
+              placeholder="App"
+              onChange={(e) =>
+                onSelectionChange({
+                  ...selection,
+                  appPrompts: replaceAt(selection.appPrompts, i, {
+                    ...rule,
+                    app: e.target.value,
+                  }),
+                })
+              }
+            />
             <input
               className="connect-input"
-              value={shortcuts[i]}
-              readOnly
-              onKeyDown={(e) => handleKeyDown(e, i, onShortcutChange)}
-              autoFocus={i === 0}
+              value={rule.prompt}
+              placeholder="Prompt text"
+              onChange={(e) =>
+                onSelectionChange({
+                  ...selection,
+                  appPrompts: replaceAt(selection.appPrompts, i, {
+                    ...rule,
+                    prompt: e.target.value,
+                  }),
+                })
+              }
             />
+            <button
+              type="button"
+              className="shortcut-remove"
+              aria-label="Remove app prompt"
+              onClick={() =>
+                onSelectionChange({
+                  ...selection,
+                  appPrompts: selection.appPrompts.filter((_, j) => j !== i),
+                })
+              }
+            >
+              ×
+            </button>
           </div>
         ))}
         <div className="connect-actions">
+          <button
+            type="button"
+            className="connect-button shortcut-add"
+            onClick={() =>
+              onSelectionChange({
+                ...selection,
+                appPrompts: [...selection.appPrompts, { app: "", prompt: "" }],
+              })
+            }
+          >
+            Add app prompt
+          </button>
+        </div>
+        <p className="settings-description">
+          Secrets in selected text are replaced before it is sent.
+        </p>
+        <div className="connect-field history-field">
+          <label className="connect-label history-toggle">
+            <input
+              type="checkbox"
+              checked={redaction.enabled}
+              onChange={(e) =>
+                onRedactionChange({ ...redaction, enabled: e.target.checked })
+              }
+            />
+            Redact
+          </label>
+          <label className="connect-label history-toggle">
+            <input
+              type="checkbox"
+              checked={redaction.highEntropy}
+              disabled={!redaction.enabled}
+              onChange={(e) =>
+                onRedactionChange({ ...redaction, highEntropy: e.target.checked })
+              }
+            />
+            Random-looking
+          </label>
+          <label className="connect-label history-toggle">
+            <input
+              type="checkbox"
+              checked={redaction.redactPrompts}
+              disabled={!redaction.enabled}
+              onChange={(e) =>
+                onRedactionChange({ ...redaction, redactPrompts: e.target.checked })
+              }
+            />
+            Typed prompts
+          </label>
+        </div>
+        <textarea
+          className="connect-input redaction-patterns"
+          value={redaction.patterns.join("\n")}
+          placeholder="Extra patterns, one regex per line"
+          disabled={!redaction.enabled}
+          spellCheck={false}
+          onChange={(e) =>
+            onRedactionChange({ ...redaction, patterns: e.target.value.split("\n") })
+          }
+        />
+        <div className="connect-actions">
+          <button
+            type="button"
+            className="connect-button shortcut-add"
+            onClick={onShortcutAdd}
+          >
+            Add
+          </button>
           <button type="submit" className="connect-button">
             Save
           </button>
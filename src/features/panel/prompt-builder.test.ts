@@ -24,6 +24,33 @@ describe("buildOutgoingPrompt", () => {
     ).toBe("Hello world");
   });
 
+  it("tells the agent when the selection was truncated", () => {
+    const selectionContext = { text: "tail of log", chars: 11, originalChars: 9000 };
+    expect(buildOutgoingPrompt({ selectionContext, userInstruction: "Why?" })).toBe(
+      "Selected text (11 of 9000 chars, truncated to fit):\ntail of log\n\nInstruction:\nWhy?",
+    );
+    expect(buildOutgoingPrompt({ selectionContext, userInstruction: "" })).toBe(
+      "Selected text (11 of 9000 chars, truncated to fit):\ntail of log",
+    );
+    expect(buildQueryLabel({ selectionContext, userInstruction: "" })).toBe(
+      "[selected: 11 of 9000 chars]",
+    );
+  });
+
+  it("names the app the selection came from", () => {
+    const selectionContext = {
+      text: "error[E0308]",
+      chars: 12,
+      app: { name: "Code", documentPath: "/src/main.rs" },
+    };
+    expect(buildOutgoingPrompt({ selectionContext, userInstruction: "Fix" })).toBe(
+      "Selected text (12 chars, from Code / main.rs):\nerror[E0308]\n\nInstruction:\nFix",
+    );
+    expect(buildOutgoingPrompt({ selectionContext, userInstruction: "" })).toBe(
+      "Selected text (12 chars, from Code / main.rs):\nerror[E0308]",
+    );
+  });
+
   it("combines selection and instruction into a single prompt", () => {
     expect(
       buildOutgoingPrompt({
@@ -61,4 +88,15 @@ describe("buildStatusMessage", () => {
       }),
     ).toContain("Token: ***");
   });
+
+  it("appends the settings load report", () => {
+    const message = buildStatusMessage({
+      gatewayUrl: "ws://localhost:8765",
+      connectionState: "idle",
+      token: "",
+      configIssues: ["Settings file could not be read"],
+    });
+
+    expect(message).toContain("Settings load report:\n- Settings file could not be read");
+  });
 });
@@ -1,4 +1,5 @@
 import { safeTrim } from "../../lib/commands";
+import { describeSourceApp } from "../../lib/selection";
 import type { ConnectionState } from "../../lib/ws-client";
 import type { SelectionContext } from "./types";
 
@@ -7,22 +8,37 @@ type PromptBuildParams = {
   userInstruction: string;
 };
 
+/** "12000 chars", or "12000 of 48210 chars" for a cut selection. */
+function describeLength(selectionContext: SelectionContext): string {
+  const { chars, originalChars } = selectionContext;
+  return originalChars ? `${chars} of ${originalChars} chars` : `${chars} chars`;
+}
+
 export function buildOutgoingPrompt(params: PromptBuildParams): string {
   const { selectionContext, userInstruction } = params;
   const instruction = safeTrim(userInstruction);
   const selectedText = selectionContext?.text ?? "";
-  const selectedChars = selectionContext?.chars ?? 0;
   const hasSelection = selectedText.length > 0;
 
-  if (!hasSelection) {
+  if (!selectionContext || !hasSelection) {
     return instruction;
   }
 
+  // The agent is told when it only sees part of the selection, and where
+  // the selection came from.
+  const { originalChars, app } = selectionContext;
+  const details = [
+    describeLength(selectionContext),
+    ...(originalChars ? ["truncated to fit"] : []),
+    ...(app ? [`from ${describeSourceApp(app)}`] : []),
+  ];
+  const header = `Selected text (${details.join(", ")}):`;
+
   if (!instruction) {
-    return selectedText;
+    return originalChars || app ? `${header}\n${selectedText}` : selectedText;
   }
 
-  return `Selected text (${selectedChars} chars):\n${selectedText}\n\nInstruction:\n${instruction}`;
+  return `${header}\n${selectedText}\n\nInstruction:\n${instruction}`;
 }
 
 export function buildQueryLabel(params: PromptBuildParams): string {
@@ -34,7 +50,7 @@ export function buildQueryLabel(params: PromptBuildParams): string {
     return instruction;
   }
 
-  const selectionLabel = `[selected: ${selectionContext.chars} chars]`;
+  const selectionLabel = `[selected: ${describeLength(selectionContext)}]`;
   return instruction ? `${selectionLabel} ${instruction}` : selectionLabel;
 }
 
@@ -42,6 +58,12 @@ export function buildStatusMessage(params: {
   gatewayUrl: string;
   connectionState: ConnectionState;
   token: string;
+  configIssues?: string[];
 }): string {
-  return `Gateway: ${params.gatewayUrl}\nStatus: ${params.connectionState}\nToken: ${params.token ? "***" : "(none)"}`;
+  const status = `Gateway: ${params.gatewayUrl}\nStatus: ${params.connectionState}\nToken: ${params.token ? "***" : "(none)"}`;
+  const issues = params.configIssues ?? [];
+  if (issues.length === 0) {
+    return status;
+  }
+  return `${status}\n\nSettings load report:\n${issues.map((issue) => `- ${issue}`).join("\n")}`;
 }
@@ -0,0 +1,45 @@
+import { describe, expect, it } from "vitest";
+import { resolveRunPrompt } from "./run-prompt";
+
+describe("resolveRunPrompt", () => {
+  it("applies the saved prompt to the captured selection", () => {
+    expect(
+      resolveRunPrompt({
+        prompt: "Translate to English",
+        selection: { text: "Bonjour", hasText: true },
+      }),
+    ).toEqual({
+      outgoingPrompt:
+        "Selected text (7 chars):\nBonjour\n\nInstruction:\nTranslate to English",
+      queryLabel: "[selected: 7 chars] Translate to English",
+      context: { prompt: "Translate to English", selection: "Bonjour" },
+    });
+  });
+
+  it("mentions the source app", () => {
+    const request = resolveRunPrompt({
+      prompt: "Explain",
+      selection: { text: "panic!", hasText: true, app: { name: "Terminal" } },
+    });
+    expect(request?.outgoingPrompt).toBe(
+      "Selected text (6 chars, from Terminal):\npanic!\n\nInstruction:\nExplain",
+    );
+  });
+
+  it("sends the prompt alone when nothing was selected", () => {
+    expect(
+      resolveRunPrompt({
+        prompt: "What's new?",
+        selection: { text: "", hasText: false, error: "No selection" },
+      }),
+    ).toEqual({
+      outgoingPrompt: "What's new?",
+      queryLabel: "What's new?",
+      context: { prompt: "What's new?", selection: null },
+    });
+  });
+
+  it("ignores empty prompts without a selection", () => {
+    expect(resolveRunPrompt({ prompt: "  " })).toBeNull();
+  });
+});
@@ -0,0 +1,54 @@
+import { safeTrim } from "../../lib/commands";
+import type { ChatContext } from "../../lib/history";
+import { buildOutgoingPrompt, buildQueryLabel } from "./prompt-builder";
+import type { SelectionPrefillPayload } from "./types";
+
+/** Payload of the Rust "panel-run-prompt" event. */
+export type RunPromptPayload = {
+  prompt?: string;
+  selection?: SelectionPrefillPayload;
+};
+
+export type RunPromptRequest = {
+  outgoingPrompt: string;
+  queryLabel: string;
+  context: ChatContext;
+};
+
+/**
+ * Prompt to send for a "run prompt" shortcut. The captured selection is
+ * attached as context regardless of its length; a failed capture just
+ * sends the saved prompt on its own.
+ */
+export function resolveRunPrompt(
+  payload: RunPromptPayload,
+): RunPromptRequest | null {
+  const userInstruction = safeTrim(payload.prompt ?? "");
+  const text = safeTrim(payload.selection?.text ?? "");
+  const originalChars = payload.selection?.truncated
+    ? payload.selection.originalChars
+    : undefined;
+  const app = payload.selection?.app;
+  const selectionContext = text
+    ? {
+        text,
+        chars: text.length,
+        ...(originalChars ? { originalChars } : {}),
+        ...(app ? { app } : {}),
+      }
+    : null;
+
+  const outgoingPrompt = buildOutgoingPrompt({
+    selectionContext,
+    userInstruction,
+  });
+  if (!outgoingPrompt) {
+    return null;
+  }
+
+  return {
+    outgoingPrompt,
+    queryLabel: buildQueryLabel({ selectionContext, userInstruction }),
+    context: { prompt: userInstruction, selection: selectionContext?.text ?? null },
+  };
+}
@@ -20,11 +20,24 @@ export function resolveSelectionPrefill(
 
   if (text.length > 0 || payload.hasText) {
     const chars = text.length;
-    if (chars > SELECTION_COMPACT_THRESHOLD) {
+    const redacted = payload.redacted ?? 0;
+    const appPrompt = safeTrim(payload.appPrompt ?? "");
+    // Redacted text always goes to the badge so the warning stays visible,
+    // and an app prompt needs the input for itself.
+    if (chars > SELECTION_COMPACT_THRESHOLD || redacted > 0 || appPrompt) {
       return {
-        input: "",
+        input: appPrompt,
         placeholder: DEFAULT_INPUT_PLACEHOLDER,
-        selectionContext: { text, chars },
+        selectionContext: {
+          text,
+          chars,
+          ...(payload.source ? { source: payload.source } : {}),
+          ...(redacted > 0 ? { redacted } : {}),
+          ...(payload.truncated && payload.originalChars
+            ? { originalChars: payload.originalChars }
+            : {}),
+          ...(payload.app ? { app: payload.app } : {}),
+        },
       };
     }
 
@@ -11,6 +11,50 @@ describe("resolveSelectionPrefill", () => {
     expect(result.selectionContext).toEqual({ text: longSelection, chars: 60 });
   });
 
+  it("remembers which strategy captured the text", () => {
+    const text = "b".repeat(60);
+    const result = resolveSelectionPrefill({ text, hasText: true, source: "clipboard" });
+
+    expect(result.selectionContext).toEqual({ text, chars: 60, source: "clipboard" });
+  });
+
+  it("keeps redacted selections in the badge", () => {
+    const text = "token [REDACTED GitHub token]";
+    const result = resolveSelectionPrefill({ text, hasText: true, redacted: 1 });
+
+    expect(result.input).toBe("");
+    expect(result.selectionContext).toEqual({ text, chars: text.length, redacted: 1 });
+  });
+
+  it("remembers the length of a truncated selection", () => {
+    const text = "c".repeat(200);
+    const result = resolveSelectionPrefill({
+      text,
+      hasText: true,
+      truncated: true,
+      originalChars: 5000,
+    });
+
+    expect(result.selectionContext).toEqual({ text, chars: 200, originalChars: 5000 });
+  });
+
+  it("prefills the app prompt and keeps the selection in the badge", () => {
+    const app = { name: "Terminal", appId: "com.apple.Terminal" };
+    const result = resolveSelectionPrefill({
+      text: "ls: x: No such file",
+      hasText: true,
+      app,
+      appPrompt: "Explain this error",
+    });
+
+    expect(result.input).toBe("Explain this error");
+    expect(result.selectionContext).toEqual({
+      text: "ls: x: No such file",
+      chars: 19,
+      app,
+    });
+  });
+
   it("puts short selected text directly in input", () => {
     const result = resolveSelectionPrefill({ text: "short text", hasText: true });
 
@@ -55,9 +99,42 @@ describe("resolveSubmitAction", () => {
     if (action.kind === "send_prompt") {
       expect(action.outgoingPrompt).toContain("Selected source");
       expect(action.outgoingPrompt).toContain("Instruction:\nsummarize this");
+      expect(action.context).toEqual({
+        prompt: "summarize this",
+        selection: "Selected source",
+      });
     }
   });
 
+  it("routes /profile with and without a name", () => {
+    const base = {
+      selectionContext: null,
+      gatewayUrl: "ws://localhost:8765",
+      token: "",
+      connectionState: "connected" as const,
+    };
+
+    expect(resolveSubmitAction({ ...base, rawInput: "/profile" })).toEqual({
+      kind: "list_profiles",
+    });
+    expect(resolveSubmitAction({ ...base, rawInput: "/profile prod" })).toEqual({
+      kind: "switch_profile",
+      name: "prod",
+    });
+  });
+
+  it("routes /history even while text is selected", () => {
+    expect(
+      resolveSubmitAction({
+        rawInput: "/history ssh keygen",
+        selectionContext: { text: "Selected source", chars: 15 },
+        gatewayUrl: "ws://localhost:8765",
+        token: "",
+        connectionState: "idle",
+      }),
+    ).toEqual({ kind: "search_history", query: "ssh keygen" });
+  });
+
   it("returns noop for empty submit without selection", () => {
     expect(
       resolveSubmitAction({
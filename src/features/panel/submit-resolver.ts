@@ -1,4 +1,5 @@
 import { parsePanelCommand, safeTrim } from "../../lib/commands";
+import type { ChatContext } from "../../lib/history";
 import type { ConnectionState } from "../../lib/ws-client";
 import {
   buildOutgoingPrompt,
@@ -13,6 +14,7 @@ type ResolveSubmitParams = {
   gatewayUrl: string;
   token: string;
   connectionState: ConnectionState;
+  configIssues?: string[];
 };
 
 export type SubmitAction =
@@ -20,7 +22,15 @@ export type SubmitAction =
   | { kind: "open_connect" }
   | { kind: "open_settings" }
   | { kind: "show_status"; query: string; message: string }
-  | { kind: "send_prompt"; outgoingPrompt: string; queryLabel: string };
+  | { kind: "list_profiles" }
+  | { kind: "switch_profile"; name: string }
+  | { kind: "search_history"; query: string }
+  | {
+      kind: "send_prompt";
+      outgoingPrompt: string;
+      queryLabel: string;
+      context: ChatContext;
+    };
 
 export function resolveSubmitAction(params: ResolveSubmitParams): SubmitAction {
   const rawInput = safeTrim(params.rawInput);
@@ -46,10 +56,21 @@ export function resolveSubmitAction(params: ResolveSubmitParams): SubmitAction {
         gatewayUrl: params.gatewayUrl,
         connectionState: params.connectionState,
         token: params.token,
+        configIssues: params.configIssues,
       }),
     };
   }
 
+  if (command?.kind === "profile") {
+    return command.name
+      ? { kind: "switch_profile", name: command.name }
+      : { kind: "list_profiles" };
+  }
+
+  if (command?.kind === "history") {
+    return { kind: "search_history", query: command.query };
+  }
+
   const isSelectionOnly = !command && !!params.selectionContext;
   const promptCommand = command?.kind === "prompt" ? command : null;
   if (!isSelectionOnly && !promptCommand) {
@@ -72,5 +93,9 @@ export function resolveSubmitAction(params: ResolveSubmitParams): SubmitAction {
       selectionContext: params.selectionContext,
       userInstruction,
     }),
+    context: {
+      prompt: userInstruction,
+      selection: params.selectionContext?.text ?? null,
+    },
   };
 }
@@ -1,3 +1,5 @@
+import type { SelectionStrategy, SourceApp } from "../../lib/selection";
+
 export const DEFAULT_INPUT_PLACEHOLDER = "Ask MacClaw...";
 export const EMPTY_SELECTION_PLACEHOLDER = "No selected text found";
 export const SELECTION_COMPACT_THRESHOLD = 50;
@@ -6,9 +8,27 @@ export type SelectionPrefillPayload = {
   text?: string;
   hasText?: boolean;
   error?: string | null;
+  source?: SelectionStrategy | null;
+  /** Secrets the Rust side replaced with placeholders. */
+  redacted?: number;
+  /** `text` was cut down to the configured limit. */
+  truncated?: boolean;
+  originalChars?: number;
+  /** App in front when the shortcut fired. */
+  app?: SourceApp | null;
+  /** Configured prompt for that app. */
+  appPrompt?: string | null;
 };
 
 export type SelectionContext = {
   text: string;
   chars: number;
+  /** Capture strategy that found the text, when known. */
+  source?: SelectionStrategy;
+  /** Secrets replaced with placeholders, when any. */
+  redacted?: number;
+  /** Length before truncation; set only when the text was cut. */
+  originalChars?: number;
+  /** App the text was selected in, when known. */
+  app?: SourceApp;
 };
@@ -0,0 +1,64 @@
+import { useEffect, type MutableRefObject } from "react";
+import type { KeychainCredentials } from "../../lib/settings";
+import type { RunPromptPayload } from "./run-prompt";
+
+type UseShortcutActionsParams = {
+  preserveNextOpenRef: MutableRefObject<boolean>;
+  onProfileActivated: (creds: KeychainCredentials) => void;
+  onRunPrompt: (payload: RunPromptPayload) => void;
+};
+
+/** Listens for shortcut actions that Rust forwards to the panel. */
+export function useShortcutActions(params: UseShortcutActionsParams): void {
+  const { preserveNextOpenRef, onProfileActivated, onRunPrompt } = params;
+
+  useEffect(() => {
+    let aborted = false;
+    let preserveTimer: ReturnType<typeof setTimeout> | null = null;
+    const unlisteners: (() => void)[] = [];
+
+    // Rust presents the panel before emitting, so keep the "panel-show"
+    // handler from clearing the response we are about to start.
+    const preservePanelOpenState = () => {
+      preserveNextOpenRef.current = true;
+      if (preserveTimer) {
+        clearTimeout(preserveTimer);
+      }
+      preserveTimer = setTimeout(() => {
+        preserveNextOpenRef.current = false;
+        preserveTimer = null;
+      }, 700);
+    };
+
+    import("@tauri-apps/api/event")
+      .then(({ listen }) =>
+        Promise.all([
+          listen<KeychainCredentials>("profile-activated", (event) => {
+            onProfileActivated(event.payload ?? {});
+          }),
+          listen<RunPromptPayload>("panel-run-prompt", (event) => {
+            preservePanelOpenState();
+            onRunPrompt(event.payload ?? {});
+          }),
+        ]),
+      )
+      .then((fns) => {
+        if (aborted) {
+          fns.forEach((fn) => fn());
+        } else {
+          unlisteners.push(...fns);
+        }
+      })
+      .catch(() => {
+        // Expected outside Tauri runtime.
+      });
+
+    return () => {
+      aborted = true;
+      unlisteners.forEach((fn) => fn());
+      if (preserveTimer) {
+        clearTimeout(preserveTimer);
+      }
+    };
+  }, [onProfileActivated, onRunPrompt, preserveNextOpenRef]);
+}
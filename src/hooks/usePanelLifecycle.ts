@@ -1,22 +1,28 @@
-import { useEffect, type MutableRefObject, type RefObject } from "react";
-import type { WsClient } from "../lib/ws-client";
+import { useEffect, useRef, type MutableRefObject, type RefObject } from "react";
+import type { GatewayClient } from "../lib/ws-client";
+import { countActive, type JobSnapshot, type JobSummary } from "../lib/jobs";
 import {
-  emitNotchState,
+  endsBackgroundMode,
   hidePanelWindow,
   showNotch,
+  type PanelStateChange,
 } from "../lib/panel-window";
 import type { AppSettings } from "../lib/settings";
 
 type PanelLifecycleDeps = {
-  client: WsClient;
+  client: GatewayClient;
   settings: AppSettings;
   settingsLoaded: boolean;
   preserveNextOpenRef: MutableRefObject<boolean>;
+  /** Set while an answer is written back into another app. */
+  writingBackRef: MutableRefObject<boolean>;
   backgroundModeRef: MutableRefObject<boolean>;
   isThinkingRef: MutableRefObject<boolean>;
   streamingTextRef: MutableRefObject<string>;
   inputRef: RefObject<HTMLInputElement | null>;
   clearConversation: () => void;
+  /** Show a response brought back from the notch. */
+  showJob: (job: JobSnapshot) => void;
   setBackgroundMode: (v: boolean) => void;
 };
 
@@ -26,14 +32,19 @@ export function usePanelLifecycle(deps: PanelLifecycleDeps): void {
     settings,
     settingsLoaded,
     preserveNextOpenRef,
+    writingBackRef,
     backgroundModeRef,
     isThinkingRef,
     streamingTextRef,
     inputRef,
     clearConversation,
+    showJob,
     setBackgroundMode,
   } = deps;
 
+  // Prompts still streaming, including ones the panel does not show.
+  const runningJobsRef = useRef(0);
+
   useEffect(() => {
     if (!settingsLoaded) {
       return;
@@ -50,10 +61,16 @@ export function usePanelLifecycle(deps: PanelLifecycleDeps): void {
     let justRestored = false;
     let restoreTimer: ReturnType<typeof setTimeout> | null = null;
 
+    // Whether hiding the panel should leave a notch behind.
+    const hasActiveResponse = () =>
+      isThinkingRef.current ||
+      streamingTextRef.current.length > 0 ||
+      runningJobsRef.current > 0;
+
     // Called every time the panel becomes visible (hotkey show).
     // Clears stale UI, reconnects if the WebSocket died while hidden.
     const onPanelShow = () => {
-      if (preserveNextOpenRef.current) {
+      if (preserveNextOpenRef.current || writingBackRef.current) {
         inputRef.current?.focus();
         return;
       }
@@ -77,7 +94,10 @@ export function usePanelLifecycle(deps: PanelLifecycleDeps): void {
     };
 
     // Notch restore: panel reopens with preserved response.
-    const onNotchRestore = () => {
+    const onNotchRestore = (event: { payload: JobSnapshot | null }) => {
+      if (event.payload) {
+        showJob(event.payload);
+      }
       justRestored = true;
       backgroundModeRef.current = false;
       setBackgroundMode(false);
@@ -95,22 +115,32 @@ export function usePanelLifecycle(deps: PanelLifecycleDeps): void {
     // we handle background mode first, then hide from JS. This avoids
     // the race where orderOut: suppresses DOM blur and IPC delivery.
     const onPanelBlur = () => {
-      if (backgroundModeRef.current) {
+      // Rust already hid the panel; keep the answer in case writing fails.
+      if (backgroundModeRef.current || writingBackRef.current) {
         return;
       }
-      const hasActiveResponse =
-        isThinkingRef.current || streamingTextRef.current.length > 0;
-      if (hasActiveResponse) {
+      if (hasActiveResponse()) {
         backgroundModeRef.current = true;
         setBackgroundMode(true);
         void showNotch();
-        void emitNotchState("streaming");
       } else {
         clearConversation();
       }
       void hidePanelWindow();
     };
 
+    // Rust owns the panel state; background mode ends when it says so.
+    const onPanelState = (event: { payload: PanelStateChange }) => {
+      if (backgroundModeRef.current && endsBackgroundMode(event.payload)) {
+        backgroundModeRef.current = false;
+        setBackgroundMode(false);
+      }
+    };
+
+    const onJobsChanged = (event: { payload: JobSummary[] }) => {
+      runningJobsRef.current = countActive(event.payload);
+    };
+
     // Tauri event listeners are registered asynchronously. Use an aborted
     // flag so that if the effect is cleaned up before the promises resolve,
     // the listeners are immediately unregistered instead of leaking.
@@ -120,11 +150,13 @@ export function usePanelLifecycle(deps: PanelLifecycleDeps): void {
       .then(({ listen }) =>
         Promise.all([
           listen("panel-show", onPanelShow),
-          listen("notch-restore", onNotchRestore),
+          listen<JobSnapshot | null>("notch-restore", onNotchRestore),
           // Rust emits "panel-dismiss" right before window.hide().
           // This is more reliable than DOM "blur" which may not fire
           // after macOS orderOut:.
           listen("panel-dismiss", onPanelBlur),
+          listen<PanelStateChange>("panel-state", onPanelState),
+          listen<JobSummary[]>("jobs-changed", onJobsChanged),
         ]),
       )
       .then((fns) => {
@@ -155,14 +187,10 @@ export function usePanelLifecycle(deps: PanelLifecycleDeps): void {
         return;
       }
 
-      const hasActiveResponse =
-        isThinkingRef.current || streamingTextRef.current.length > 0;
-
-      if (hasActiveResponse) {
+      if (hasActiveResponse()) {
         backgroundModeRef.current = true;
         setBackgroundMode(true);
         void showNotch();
-        void emitNotchState("streaming");
         void hidePanelWindow();
         return;
       }
@@ -191,6 +219,8 @@ export function usePanelLifecycle(deps: PanelLifecycleDeps): void {
     setBackgroundMode,
     settings,
     settingsLoaded,
+    showJob,
     streamingTextRef,
+    writingBackRef,
   ]);
 }
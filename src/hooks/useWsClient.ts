@@ -1,9 +1,14 @@
 import { useMemo, useRef, useState, type MutableRefObject } from "react";
-import { WsClient, type ConnectionState } from "../lib/ws-client";
-import { emitNotchState } from "../lib/panel-window";
+import {
+  WsClient,
+  type ConnectionState,
+  type GatewayClient,
+  type GatewayHandlers,
+} from "../lib/ws-client";
+import { isTauriRuntime, NativeGatewayClient } from "../lib/native-gateway";
 
 type UseWsClientResult = {
-  client: WsClient;
+  client: GatewayClient;
   connectionState: ConnectionState;
   assistantText: string;
   setAssistantText: (text: string) => void;
@@ -26,27 +31,25 @@ export function useWsClient(
 
   const isThinkingRef = useRef(false);
   const streamingTextRef = useRef("");
-  const clientRef = useRef<WsClient | null>(null);
+  const clientRef = useRef<GatewayClient | null>(null);
 
   const client = useMemo(() => {
     if (clientRef.current) {
       return clientRef.current;
     }
 
-    const instance = new WsClient({
+    const handlers: GatewayHandlers = {
       onState: (state) => {
         setConnectionState(state);
-        // BUG 2: If WS drops while background streaming, transition notch to "ready"
-        // instead of leaving it stuck in "streaming" forever.
+        // BUG 2: If WS drops while background streaming, stop thinking; Rust
+        // marks the job failed so the notch is not stuck in "streaming".
         if (
           (state === "idle" || state === "error") &&
           backgroundModeRef.current &&
           (isThinkingRef.current || streamingTextRef.current.length > 0)
         ) {
-          const preview = streamingTextRef.current || "Connection lost";
           isThinkingRef.current = false;
           setIsThinking(false);
-          void emitNotchState("ready", preview);
         }
       },
       onEvent: (event) => {
@@ -61,9 +64,6 @@ export function useWsClient(
         if (event.kind === "assistant_done") {
           isThinkingRef.current = false;
           setIsThinking(false);
-          // BUG 3: Don't emit notch "ready" here — the "assistant" event that
-          // follows will emit it with the final text. Emitting in both places
-          // caused a double flash.
           return;
         }
 
@@ -73,10 +73,15 @@ export function useWsClient(
           setAssistantText(event.text);
           isThinkingRef.current = false;
           setIsThinking(false);
+          return;
+        }
 
-          if (backgroundModeRef.current) {
-            void emitNotchState("ready", event.text);
-          }
+        if (event.kind === "aborted") {
+          setAssistantText(streamingTextRef.current);
+          streamingTextRef.current = "";
+          setStreamingText("");
+          isThinkingRef.current = false;
+          setIsThinking(false);
           return;
         }
 
@@ -86,14 +91,15 @@ export function useWsClient(
           setAssistantText(`Error: ${event.text}`);
           isThinkingRef.current = false;
           setIsThinking(false);
-
-          if (backgroundModeRef.current) {
-            void emitNotchState("ready", `Error: ${event.text}`);
-          }
         }
       },
-    });
+    };
 
+    // Inside Tauri the Rust backend owns the socket; the browser dev build
+    // falls back to the in-page WebSocket client.
+    const instance = isTauriRuntime()
+      ? new NativeGatewayClient(handlers)
+      : new WsClient(handlers);
     clientRef.current = instance;
     return instance;
   }, []);
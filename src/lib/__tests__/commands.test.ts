@@ -24,3 +24,33 @@ describe("parsePanelCommand", () => {
     expect(parsePanelCommand("hello")).toEqual({ kind: "prompt", text: "hello" });
   });
 });
+
+describe("/profile", () => {
+  it("parses a profile name", () => {
+    expect(parsePanelCommand("/profile  Staging ")).toEqual({
+      kind: "profile",
+      name: "Staging",
+    });
+  });
+
+  it("parses bare /profile as a list request", () => {
+    expect(parsePanelCommand("/profile")).toEqual({ kind: "profile", name: "" });
+  });
+
+  it("does not swallow prompts starting with /profile", () => {
+    expect(parsePanelCommand("/profiles")).toEqual({
+      kind: "prompt",
+      text: "/profiles",
+    });
+  });
+});
+
+describe("/history", () => {
+  it("parses a search query", () => {
+    expect(parsePanelCommand("/history  tar flags ")).toEqual({
+      kind: "history",
+      query: "tar flags",
+    });
+    expect(parsePanelCommand("/history")).toEqual({ kind: "history", query: "" });
+  });
+});
@@ -0,0 +1,86 @@
+import { describe, expect, it } from "vitest";
+import {
+  DEFAULT_HISTORY_SETTINGS,
+  MATCH_END,
+  MATCH_START,
+  formatRecentHistory,
+  formatSearchResults,
+  highlightSnippet,
+  normalizeHistorySettings,
+  type HistoryEntry,
+} from "../history";
+
+describe("normalizeHistorySettings", () => {
+  it("falls back to defaults for missing values", () => {
+    expect(normalizeHistorySettings(undefined)).toEqual(DEFAULT_HISTORY_SETTINGS);
+    expect(normalizeHistorySettings({ maxAgeDays: "7" })).toEqual(
+      DEFAULT_HISTORY_SETTINGS,
+    );
+  });
+
+  it("treats null and non-positive limits as unlimited", () => {
+    expect(
+      normalizeHistorySettings({ enabled: false, maxAgeDays: null, maxEntries: 0 }),
+    ).toEqual({ enabled: false, maxAgeDays: null, maxEntries: null });
+  });
+
+  it("rounds fractional limits down", () => {
+    expect(
+      normalizeHistorySettings({ enabled: true, maxAgeDays: 7.9, maxEntries: 250 }),
+    ).toEqual({ enabled: true, maxAgeDays: 7, maxEntries: 250 });
+  });
+});
+
+function entry(overrides: Partial<HistoryEntry>): HistoryEntry {
+  return {
+    id: 1,
+    prompt: "tar flags",
+    selection: null,
+    agentId: "",
+    sessionKey: "main",
+    createdAt: new Date(2026, 9, 10, 12).getTime(),
+    completedAt: null,
+    response: "",
+    status: "done",
+    error: null,
+    ...overrides,
+  };
+}
+
+const mark = (word: string) => `${MATCH_START}${word}${MATCH_END}`;
+
+describe("history formatting", () => {
+  it("bolds matches and flattens snippets", () => {
+    expect(highlightSnippet(`…use ${mark("tar")}\n -xzf ${mark("file")}`)).toBe(
+      "…use **tar** -xzf **file**",
+    );
+  });
+
+  it("lists search hits with their date", () => {
+    expect(
+      formatSearchResults("tar", [
+        { entry: entry({}), score: 2, snippet: `${mark("tar")} flags` },
+        { entry: entry({ id: 2, prompt: "" }), score: 1, snippet: "x" },
+      ]).split("\n\n"),
+    ).toEqual([
+      "**1.** tar flags · 2026-10-10\n> **tar** flags",
+      "**2.** (selection only) · 2026-10-10\n> x",
+    ]);
+    expect(formatSearchResults("zzz", [])).toBe('Nothing in history matches "zzz".');
+  });
+
+  it("previews recent answers and errors", () => {
+    const text = formatRecentHistory([
+      entry({ response: "a".repeat(130) }),
+      entry({ id: 2, status: "error", error: "Agent error." }),
+      entry({ id: 3, status: "pending" }),
+    ]);
+    expect(text.split("\n\n")).toEqual([
+      `**1.** tar flags · 2026-10-10\n> ${"a".repeat(120)}…`,
+      "**2.** tar flags · 2026-10-10\n> Agent error.",
+      "**3.** tar flags · 2026-10-10",
+      "Search with `/history <words>`",
+    ]);
+    expect(formatRecentHistory([])).toBe("No history yet.");
+  });
+});
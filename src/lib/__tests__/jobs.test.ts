@@ -0,0 +1,103 @@
+import { describe, expect, it } from "vitest";
+import {
+  countActive,
+  DEFAULT_NOTCH_SETTINGS,
+  followJob,
+  jobCounter,
+  jobMeta,
+  jobResponse,
+  nextJobIndex,
+  normalizeNotchSettings,
+  notchPreview,
+  type JobSummary,
+} from "../jobs";
+
+function job(
+  id: number,
+  state: JobSummary["state"],
+  preview: string | null = null,
+): JobSummary {
+  return {
+    id,
+    label: `Prompt ${id}`,
+    state,
+    startedAt: 0,
+    textLen: 0,
+    tokens: null,
+    elapsedMs: 0,
+    preview,
+  };
+}
+
+describe("notch cycling", () => {
+  it("counts only when several jobs share the notch", () => {
+    expect(jobCounter(0, 1)).toBe("");
+    expect(jobCounter(1, 3)).toBe("2/3");
+  });
+
+  it("wraps around and survives an empty list", () => {
+    expect(nextJobIndex(0, 2)).toBe(1);
+    expect(nextJobIndex(1, 2)).toBe(0);
+    expect(nextJobIndex(3, 0)).toBe(0);
+  });
+
+  it("keeps showing the same job when the list changes", () => {
+    const jobs = [job(2, "streaming"), job(3, "tool_running")];
+    expect(followJob(jobs, 3)).toBe(1);
+    expect(followJob(jobs, 1)).toBe(0);
+    expect(followJob([], undefined)).toBe(0);
+    expect(countActive([job(1, "done"), ...jobs])).toBe(2);
+  });
+});
+
+describe("notchPreview", () => {
+  it("strips markdown and marks errors", () => {
+    expect(notchPreview(job(1, "done", "## **Summary** of [it](x)"))).toBe(
+      "Summary of itx",
+    );
+    expect(notchPreview(job(1, "error", "Agent error."))).toBe("Error: Agent error.");
+    expect(notchPreview(job(1, "aborted", ""))).toBe("Stopped");
+    expect(notchPreview(job(1, "aborted", "Half"))).toBe("Stopped: Half");
+  });
+});
+
+describe("jobMeta", () => {
+  it("shows tokens when reported and the time taken", () => {
+    expect(jobMeta({ ...job(1, "done"), elapsedMs: 8400 })).toBe("8s");
+    expect(jobMeta({ ...job(1, "done"), tokens: 412, elapsedMs: 65_000 })).toBe(
+      "412 tokens · 1m 5s",
+    );
+  });
+});
+
+describe("normalizeNotchSettings", () => {
+  it("keeps whole seconds and null, falls back otherwise", () => {
+    expect(normalizeNotchSettings(undefined)).toEqual(DEFAULT_NOTCH_SETTINGS);
+    expect(normalizeNotchSettings({ autoHideSecs: null }).autoHideSecs).toBeNull();
+    expect(normalizeNotchSettings({ autoHideSecs: 2.7 })).toEqual({ autoHideSecs: 2 });
+    expect(normalizeNotchSettings({ autoHideSecs: 0 })).toEqual(DEFAULT_NOTCH_SETTINGS);
+  });
+});
+
+describe("jobResponse", () => {
+  it("restores the response in the state the job is in", () => {
+    const snapshot = { ...job(1, "streaming"), text: "" };
+    expect(jobResponse(snapshot)).toEqual({
+      assistantText: "",
+      streamingText: "",
+      thinking: true,
+    });
+    expect(jobResponse({ ...snapshot, text: "Half" }).streamingText).toBe("Half");
+    expect(jobResponse({ ...snapshot, state: "done", text: "All" }).assistantText).toBe(
+      "All",
+    );
+    expect(jobResponse({ ...snapshot, state: "tool_running" }).thinking).toBe(true);
+    const aborted = jobResponse({ ...snapshot, state: "aborted", text: "Ha" });
+    expect(aborted.assistantText).toBe("Ha");
+    expect(jobResponse({ ...snapshot, state: "error", text: "Lost" })).toEqual({
+      assistantText: "Error: Lost",
+      streamingText: "",
+      thinking: false,
+    });
+  });
+});
@@ -0,0 +1,20 @@
+import { describe, expect, it } from "vitest";
+import { isTauriRuntime, NativeGatewayClient } from "../native-gateway";
+
+describe("isTauriRuntime", () => {
+  it("is false outside the Tauri webview", () => {
+    expect(isTauriRuntime()).toBe(false);
+  });
+});
+
+describe("NativeGatewayClient", () => {
+  it("refuses to send before the backend reports connected", () => {
+    const client = new NativeGatewayClient({
+      onState: () => {},
+      onEvent: () => {},
+    });
+
+    expect(client.connected).toBe(false);
+    expect(() => client.sendChatMessage("hi")).toThrow("Not connected");
+  });
+});
@@ -0,0 +1,14 @@
+import { describe, expect, it } from "vitest";
+import { endsBackgroundMode } from "../panel-window";
+
+describe("endsBackgroundMode", () => {
+  it("ends when Rust drops the notch", () => {
+    expect(endsBackgroundMode({ from: "background", to: "hidden" })).toBe(true);
+    expect(endsBackgroundMode({ from: "background", to: "compact" })).toBe(true);
+  });
+
+  it("keeps the response through a restore and outside the notch", () => {
+    expect(endsBackgroundMode({ from: "background", to: "restoring" })).toBe(false);
+    expect(endsBackgroundMode({ from: "expanded", to: "hidden" })).toBe(false);
+  });
+});
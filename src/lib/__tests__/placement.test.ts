@@ -0,0 +1,82 @@
+import { describe, expect, it } from "vitest";
+import {
+  DEFAULT_PANEL_SETTINGS,
+  displayOptions,
+  normalizePanelSettings,
+  parsePanelDimension,
+  type DisplayInfo,
+} from "../placement";
+
+const builtIn: DisplayInfo = {
+  name: "Built-in",
+  frame: { x: 0, y: 0, width: 3024, height: 1964 },
+  scale: 2,
+  primary: true,
+};
+
+const external: DisplayInfo = {
+  name: "DELL U2720Q",
+  frame: { x: 3024, y: 0, width: 2560, height: 1440 },
+  scale: 1,
+  primary: false,
+};
+
+describe("normalizePanelSettings", () => {
+  it("keeps known policies and falls back otherwise", () => {
+    expect(normalizePanelSettings(undefined)).toEqual(DEFAULT_PANEL_SETTINGS);
+    expect(
+      normalizePanelSettings({ placement: "focused_window", display: "LG" }),
+    ).toEqual({
+      ...DEFAULT_PANEL_SETTINGS,
+      placement: "focused_window",
+      display: "LG",
+    });
+    expect(normalizePanelSettings({ placement: "middle", display: 2 })).toEqual(
+      DEFAULT_PANEL_SETTINGS,
+    );
+  });
+
+  it("keeps the expanded size at least the window minimum", () => {
+    expect(
+      normalizePanelSettings({ expanded: { width: 320, height: 600.4 } }).expanded,
+    ).toEqual({ width: 640, height: 600 });
+    expect(normalizePanelSettings({ expanded: { width: "wide" } }).expanded).toEqual(
+      DEFAULT_PANEL_SETTINGS.expanded,
+    );
+  });
+});
+
+describe("layout presets", () => {
+  it("keeps known presets and dock edges", () => {
+    expect(normalizePanelSettings({ preset: "wide", dock: "left" })).toMatchObject({
+      preset: "wide",
+      dock: "left",
+    });
+    expect(normalizePanelSettings({ preset: "floating", dock: "top" })).toMatchObject({
+      preset: "compact",
+      dock: "right",
+    });
+  });
+});
+
+describe("parsePanelDimension", () => {
+  it("reads typed sizes and keeps the old one for junk", () => {
+    expect(parsePanelDimension("800", 640, 750)).toBe(800);
+    expect(parsePanelDimension("100", 640, 750)).toBe(640);
+    expect(parsePanelDimension("", 640, 750)).toBe(750);
+  });
+});
+
+describe("displayOptions", () => {
+  it("lists connected displays and keeps a missing saved one", () => {
+    expect(displayOptions([builtIn, external], "DELL U2720Q")).toEqual([
+      { value: "Built-in", label: "Built-in (3024×1964, main)" },
+      { value: "DELL U2720Q", label: "DELL U2720Q (2560×1440)" },
+    ]);
+    expect(displayOptions([builtIn], "DELL U2720Q")[0]).toEqual({
+      value: "DELL U2720Q",
+      label: "DELL U2720Q (not connected)",
+    });
+    expect(displayOptions([], "")).toEqual([{ value: "", label: "Choose a display" }]);
+  });
+});
@@ -0,0 +1,23 @@
+import { describe, expect, it } from "vitest";
+import { activateProfile, formatProfileList } from "../profiles";
+
+describe("formatProfileList", () => {
+  it("marks the active profile", () => {
+    const text = formatProfileList([
+      { name: "Local", gatewayUrl: "ws://127.0.0.1:19819", agentId: "", active: true },
+      { name: "Prod", gatewayUrl: "wss://gw.example/ws", agentId: "ops", active: false },
+    ]);
+
+    expect(text).toContain("* Local — ws://127.0.0.1:19819");
+    expect(text).toContain("  Prod — wss://gw.example/ws");
+    expect(text).toContain("/profile <name>");
+  });
+});
+
+describe("activateProfile", () => {
+  it("rejects outside the desktop app", async () => {
+    await expect(activateProfile("Prod")).rejects.toThrow(
+      "Profiles are available only in the desktop app",
+    );
+  });
+});
@@ -0,0 +1,40 @@
+import { describe, expect, it } from "vitest";
+import {
+  DEFAULT_REDACTION_SETTINGS,
+  normalizeRedactionSettings,
+  redactionNotice,
+} from "../redaction";
+
+describe("normalizeRedactionSettings", () => {
+  it("falls back to defaults for missing values", () => {
+    expect(normalizeRedactionSettings(undefined)).toEqual(DEFAULT_REDACTION_SETTINGS);
+    expect(normalizeRedactionSettings({ enabled: "no" })).toEqual(
+      DEFAULT_REDACTION_SETTINGS,
+    );
+  });
+
+  it("keeps flags and drops blank patterns", () => {
+    expect(
+      normalizeRedactionSettings({
+        enabled: false,
+        redactPrompts: true,
+        highEntropy: false,
+        patterns: ["password=(\\S+)", " ", 3],
+      }),
+    ).toEqual({
+      enabled: false,
+      redactPrompts: true,
+      highEntropy: false,
+      patterns: ["password=(\\S+)"],
+    });
+  });
+});
+
+describe("redactionNotice", () => {
+  it("words the badge warning", () => {
+    expect(redactionNotice(undefined)).toBe("");
+    expect(redactionNotice(0)).toBe("");
+    expect(redactionNotice(1)).toBe("1 secret hidden");
+    expect(redactionNotice(3)).toBe("3 secrets hidden");
+  });
+});
@@ -0,0 +1,97 @@
+import { describe, expect, it } from "vitest";
+import {
+  DEFAULT_SELECTION_SETTINGS,
+  describeSourceApp,
+  moveStrategyUp,
+  normalizeSelectionSettings,
+  strategyRows,
+  toggleStrategy,
+} from "../selection";
+
+describe("normalizeSelectionSettings", () => {
+  it("falls back to defaults for missing values", () => {
+    expect(normalizeSelectionSettings(undefined)).toEqual(DEFAULT_SELECTION_SETTINGS);
+    expect(normalizeSelectionSettings({ copyWaitMs: -1 })).toEqual(
+      DEFAULT_SELECTION_SETTINGS,
+    );
+  });
+
+  it("drops unknown and repeated strategies", () => {
+    expect(
+      normalizeSelectionSettings({
+        strategies: ["clipboard", "telepathy", "clipboard", "primary_selection"],
+        copyWaitMs: 800,
+        copyPollMs: 20,
+        clipboardHistory: 3,
+      }),
+    ).toEqual({
+      ...DEFAULT_SELECTION_SETTINGS,
+      strategies: ["clipboard", "primary_selection"],
+      copyWaitMs: 800,
+      copyPollMs: 20,
+      clipboardHistory: 3,
+    });
+  });
+
+  it("keeps truncation settings and rejects unknown modes", () => {
+    expect(
+      normalizeSelectionSettings({
+        maxChars: 4000,
+        truncation: "tail",
+        truncateAtLines: false,
+      }),
+    ).toMatchObject({ maxChars: 4000, truncation: "tail", truncateAtLines: false });
+    expect(normalizeSelectionSettings({ truncation: "middle" }).truncation).toBe(
+      "head_tail",
+    );
+  });
+
+  it("keeps well-formed app prompts", () => {
+    expect(
+      normalizeSelectionSettings({
+        appPrompts: [
+          { app: "Safari", prompt: "Summarize this page", extra: true },
+          { app: "Terminal" },
+          "Notes",
+        ],
+      }).appPrompts,
+    ).toEqual([{ app: "Safari", prompt: "Summarize this page" }]);
+  });
+});
+
+describe("describeSourceApp", () => {
+  it("adds the file, page or window to the app name", () => {
+    expect(
+      describeSourceApp({
+        name: "Code",
+        documentPath: "/Users/me/src/main.rs",
+        url: "https://example.com",
+      }),
+    ).toBe("Code / main.rs");
+    expect(describeSourceApp({ name: "Safari", url: "https://example.com/a" })).toBe(
+      "Safari / https://example.com/a",
+    );
+    expect(describeSourceApp({ name: "Terminal", windowTitle: "zsh — 80×24" })).toBe(
+      "Terminal / zsh — 80×24",
+    );
+    expect(describeSourceApp({ name: "Notes", windowTitle: "Notes" })).toBe("Notes");
+  });
+});
+
+describe("strategy editing", () => {
+  it("lists enabled strategies first, in order", () => {
+    expect(strategyRows(["clipboard", "accessibility"]).slice(0, 3)).toEqual([
+      { strategy: "clipboard", enabled: true },
+      { strategy: "accessibility", enabled: true },
+      { strategy: "simulated_copy", enabled: false },
+    ]);
+  });
+
+  it("toggles and reorders", () => {
+    const enabled = toggleStrategy(["accessibility"], "clipboard", true);
+    expect(enabled).toEqual(["accessibility", "clipboard"]);
+    expect(moveStrategyUp(enabled, "clipboard")).toEqual(["clipboard", "accessibility"]);
+    expect(moveStrategyUp(enabled, "accessibility")).toEqual(enabled);
+    expect(toggleStrategy(enabled, "accessibility", false)).toEqual(["clipboard"]);
+  });
+});
@@ -14,7 +14,35 @@ describe("normalizeSettings", () => {
         password: "pass",
         agentId: "a1",
         sessionKey: "s1",
-        shortcuts: ["Alt+A", "Alt+B", "Alt+C"],
+        shortcuts: [
+          { accelerator: "Alt+A", action: { type: "toggle_panel" } },
+          { accelerator: "Alt+P", action: { type: "run_prompt", prompt: "Fix" } },
+        ],
+        history: { enabled: false, maxAgeDays: null, maxEntries: 20 },
+        selection: {
+          strategies: ["clipboard"],
+          copyWaitMs: 900,
+          copyPollMs: 50,
+          clipboardHistory: 2,
+          maxChars: 3000,
+          truncation: "tail",
+          truncateAtLines: false,
+          appPrompts: [{ app: "Terminal", prompt: "Explain this error" }],
+        },
+        redaction: {
+          enabled: true,
+          redactPrompts: true,
+          highEntropy: false,
+          patterns: ["token=(\\w+)"],
+        },
+        panel: {
+          placement: "display",
+          display: "DELL U2720Q",
+          expanded: { width: 900, height: 600 },
+          preset: "sidebar",
+          dock: "left",
+        },
+        notch: { autoHideSecs: null },
       }),
     ).toEqual({
       gatewayUrl: "ws://example/ws",
@@ -22,7 +50,35 @@ describe("normalizeSettings", () => {
       password: "pass",
       agentId: "a1",
       sessionKey: "s1",
-      shortcuts: ["Alt+A", "Alt+B", "Alt+C"],
+      shortcuts: [
+        { accelerator: "Alt+A", action: { type: "toggle_panel" } },
+        { accelerator: "Alt+P", action: { type: "run_prompt", prompt: "Fix" } },
+      ],
+      history: { enabled: false, maxAgeDays: null, maxEntries: 20 },
+      selection: {
+        strategies: ["clipboard"],
+        copyWaitMs: 900,
+        copyPollMs: 50,
+        clipboardHistory: 2,
+        maxChars: 3000,
+        truncation: "tail",
+        truncateAtLines: false,
+        appPrompts: [{ app: "Terminal", prompt: "Explain this error" }],
+      },
+      redaction: {
+        enabled: true,
+        redactPrompts: true,
+        highEntropy: false,
+        patterns: ["token=(\\w+)"],
+      },
+      panel: {
+        placement: "display",
+        display: "DELL U2720Q",
+        expanded: { width: 900, height: 600 },
+        preset: "sidebar",
+        dock: "left",
+      },
+      notch: { autoHideSecs: null },
     });
   });
 
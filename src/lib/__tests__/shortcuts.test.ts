@@ -0,0 +1,113 @@
+import { describe, expect, it } from "vitest";
+import {
+  DEFAULT_SHORTCUT_BINDINGS,
+  SELECTION_SHORTCUT,
+  describeShortcutReport,
+  makeShortcutAction,
+  normalizeShortcutBindings,
+  shortcutActionArgument,
+} from "../shortcuts";
+
+describe("normalizeShortcutBindings", () => {
+  it("falls back to defaults for missing or empty values", () => {
+    expect(normalizeShortcutBindings(undefined)).toEqual(
+      DEFAULT_SHORTCUT_BINDINGS,
+    );
+    expect(normalizeShortcutBindings([])).toEqual(DEFAULT_SHORTCUT_BINDINGS);
+    expect(normalizeShortcutBindings("Alt+A")).toEqual(
+      DEFAULT_SHORTCUT_BINDINGS,
+    );
+  });
+
+  it("keeps any number of valid bindings", () => {
+    const bindings = [
+      { accelerator: "Alt+A", action: { type: "toggle_panel" } },
+      { accelerator: "Alt+P", action: { type: "open_profile", profile: "Prod" } },
+      { accelerator: "Alt+R", action: { type: "run_prompt", prompt: "Fix" } },
+      { accelerator: "Alt+X", action: { type: "abort_streaming" } },
+      { accelerator: "Alt+N", action: { type: "restore_notch" } },
+    ];
+    expect(normalizeShortcutBindings(bindings)).toEqual(bindings);
+  });
+
+  it("drops malformed bindings", () => {
+    expect(
+      normalizeShortcutBindings([
+        { accelerator: "", action: { type: "toggle_panel" } },
+        { accelerator: "Alt+B", action: { type: "launch_rocket" } },
+        { accelerator: "Alt+C", action: { type: "open_profile" } },
+        { accelerator: "Alt+D", action: { type: "capture_selection" } },
+      ]),
+    ).toEqual([{ accelerator: "Alt+D", action: { type: "capture_selection" } }]);
+  });
+
+  it("upgrades legacy accelerator lists", () => {
+    expect(normalizeShortcutBindings(["Alt+A", "Alt+B", "Alt+C"])).toEqual([
+      { accelerator: "Alt+A", action: { type: "toggle_panel" } },
+      { accelerator: "Alt+B", action: { type: "toggle_panel" } },
+      { accelerator: "Alt+C", action: { type: "toggle_panel" } },
+      { accelerator: SELECTION_SHORTCUT, action: { type: "capture_selection" } },
+    ]);
+  });
+});
+
+describe("shortcut actions", () => {
+  it("carries the argument between action types", () => {
+    const action = makeShortcutAction("run_prompt", "Summarize");
+    expect(action).toEqual({ type: "run_prompt", prompt: "Summarize" });
+    expect(shortcutActionArgument(action)).toBe("Summarize");
+    expect(
+      makeShortcutAction("open_profile", shortcutActionArgument(action)),
+    ).toEqual({ type: "open_profile", profile: "Summarize" });
+    expect(makeShortcutAction("toggle_panel", "ignored")).toEqual({
+      type: "toggle_panel",
+    });
+  });
+});
+
+describe("describeShortcutReport", () => {
+  it("lists only bindings with problems", () => {
+    expect(
+      describeShortcutReport({
+        bindings: [
+          { accelerator: "Alt+A", action: { type: "toggle_panel" }, problem: null },
+          {
+            accelerator: "Alt+Q",
+            action: { type: "abort_streaming" },
+            problem: { kind: "registration", message: "taken" },
+          },
+          {
+            accelerator: "alt+a",
+            action: { type: "capture_selection" },
+            problem: { kind: "duplicate", index: 0 },
+          },
+        ],
+        applied: true,
+        rolledBack: false,
+      }),
+    ).toEqual([
+      "Alt+Q → Abort response: could not be registered (taken)",
+      "alt+a → Capture selection: same keys as Alt+A",
+    ]);
+  });
+
+  it("leads with the refusal reason", () => {
+    expect(
+      describeShortcutReport({
+        bindings: [
+          {
+            accelerator: "Alt+Foo",
+            action: { type: "toggle_panel" },
+            problem: { kind: "parse", message: "unknown key Foo" },
+          },
+        ],
+        applied: false,
+        rolledBack: false,
+        error: "No working toggle panel shortcut; keeping the current shortcuts",
+      }),
+    ).toEqual([
+      "No working toggle panel shortcut; keeping the current shortcuts",
+      "Alt+Foo → Toggle panel: invalid shortcut (unknown key Foo)",
+    ]);
+  });
+});
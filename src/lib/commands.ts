@@ -2,6 +2,8 @@ export type PanelCommand =
   | { kind: "connect" }
   | { kind: "settings" }
   | { kind: "status" }
+  | { kind: "profile"; name: string }
+  | { kind: "history"; query: string }
   | { kind: "prompt"; text: string };
 
 export type CommandDefinition = {
@@ -13,6 +15,8 @@ export const AVAILABLE_COMMANDS: CommandDefinition[] = [
   { name: "/connect", description: "Configure gateway connection" },
   { name: "/settings", description: "Configure global hotkeys" },
   { name: "/status", description: "Show connection status" },
+  { name: "/profile", description: "List or switch gateway profiles" },
+  { name: "/history", description: "Search past prompts and answers" },
 ];
 
 export function safeTrim(value: string): string {
@@ -45,5 +49,13 @@ export function parsePanelCommand(input: string): PanelCommand | null {
     return { kind: "status" };
   }
 
+  if (text === "/profile" || text.startsWith("/profile ")) {
+    return { kind: "profile", name: safeTrim(text.slice("/profile".length)) };
+  }
+
+  if (text === "/history" || text.startsWith("/history ")) {
+    return { kind: "history", query: safeTrim(text.slice("/history".length)) };
+  }
+
   return { kind: "prompt", text };
 }
@@ -0,0 +1,175 @@
+import { isTauriRuntime } from "./native-gateway";
+
+/** Mirrors the Rust `HistorySettings` struct. */
+export type HistorySettings = {
+  enabled: boolean;
+  /** Entries older than this are deleted; `null` keeps them forever. */
+  maxAgeDays: number | null;
+  /** Only the newest entries are kept; `null` keeps all of them. */
+  maxEntries: number | null;
+};
+
+export const DEFAULT_HISTORY_SETTINGS: HistorySettings = {
+  enabled: true,
+  maxAgeDays: 90,
+  maxEntries: 1000,
+};
+
+/** Mirrors the Rust `ChatContext` struct: what the user typed and selected. */
+export type ChatContext = {
+  prompt: string;
+  selection: string | null;
+};
+
+/** Mirrors the Rust `HistoryEntry` struct. Timestamps are Unix ms. */
+export type HistoryEntry = {
+  id: number;
+  prompt: string;
+  selection: string | null;
+  agentId: string;
+  sessionKey: string;
+  createdAt: number;
+  completedAt: number | null;
+  response: string;
+  status: "pending" | "done" | "error";
+  error: string | null;
+};
+
+/** Markers around matched words in `SearchHit.snippet`, as in Rust. */
+export const MATCH_START = "\u0002";
+export const MATCH_END = "\u0003";
+
+/** Mirrors the Rust `SearchHit` struct. */
+export type SearchHit = {
+  entry: HistoryEntry;
+  /** BM25 relevance; higher is better. */
+  score: number;
+  snippet: string;
+};
+
+function asLimit(value: unknown, fallback: number | null): number | null {
+  if (value === null) {
+    return null;
+  }
+  if (typeof value !== "number" || !Number.isFinite(value)) {
+    return fallback;
+  }
+  // Zero or less means "no limit", like an empty field in the settings form.
+  return value >= 1 ? Math.floor(value) : null;
+}
+
+export function normalizeHistorySettings(value: unknown): HistorySettings {
+  if (!value || typeof value !== "object") {
+    return DEFAULT_HISTORY_SETTINGS;
+  }
+
+  const source = value as Record<string, unknown>;
+  return {
+    enabled:
+      typeof source.enabled === "boolean"
+        ? source.enabled
+        : DEFAULT_HISTORY_SETTINGS.enabled,
+    maxAgeDays: asLimit(source.maxAgeDays, DEFAULT_HISTORY_SETTINGS.maxAgeDays),
+    maxEntries: asLimit(source.maxEntries, DEFAULT_HISTORY_SETTINGS.maxEntries),
+  };
+}
+
+const BROWSER_MODE_ERROR = "History is available only in the desktop app";
+
+async function invokeHistory<T>(
+  command: string,
+  args?: Record<string, unknown>,
+): Promise<T> {
+  if (!isTauriRuntime()) {
+    throw new Error(BROWSER_MODE_ERROR);
+  }
+
+  const { invoke } = await import("@tauri-apps/api/core");
+  try {
+    return await invoke<T>(command, args);
+  } catch (error) {
+    throw error instanceof Error ? error : new Error(String(error));
+  }
+}
+
+/** Newest entries first; `before` pages back from an entry id. */
+export function listHistory(
+  limit?: number,
+  before?: number,
+): Promise<HistoryEntry[]> {
+  return invokeHistory<HistoryEntry[]>("history_list", {
+    limit: limit ?? null,
+    before: before ?? null,
+  });
+}
+
+/** Full-text search over prompts, selections and answers, best match first. */
+export function searchHistory(
+  query: string,
+  limit?: number,
+): Promise<SearchHit[]> {
+  return invokeHistory<SearchHit[]>("history_search", {
+    query,
+    limit: limit ?? null,
+  });
+}
+
+export function getHistoryEntry(id: number): Promise<HistoryEntry | null> {
+  return invokeHistory<HistoryEntry | null>("history_get", { id });
+}
+
+export function deleteHistoryEntry(id: number): Promise<boolean> {
+  return invokeHistory<boolean>("history_delete", { id });
+}
+
+export function clearHistory(): Promise<void> {
+  return invokeHistory<void>("history_clear");
+}
+
+function formatDay(timestamp: number): string {
+  const date = new Date(timestamp);
+  const pad = (value: number) => String(value).padStart(2, "0");
+  return `${date.getFullYear()}-${pad(date.getMonth() + 1)}-${pad(date.getDate())}`;
+}
+
+function oneLine(text: string): string {
+  return text.replace(/\s+/g, " ").trim();
+}
+
+function entryHeading(entry: HistoryEntry, index: number): string {
+  const prompt = oneLine(entry.prompt) || "(selection only)";
+  return `**${index + 1}.** ${prompt} · ${formatDay(entry.createdAt)}`;
+}
+
+/** Snippet on one line with the matched words in bold. */
+export function highlightSnippet(snippet: string): string {
+  return oneLine(snippet).split(MATCH_START).join("**").split(MATCH_END).join("**");
+}
+
+/** Markdown for the `/history <query>` answer. */
+export function formatSearchResults(query: string, hits: SearchHit[]): string {
+  if (hits.length === 0) {
+    return `Nothing in history matches "${query}".`;
+  }
+  return hits
+    .map(
+      (hit, i) => `${entryHeading(hit.entry, i)}\n> ${highlightSnippet(hit.snippet)}`,
+    )
+    .join("\n\n");
+}
+
+const PREVIEW_CHARS = 120;
+
+/** Markdown for bare `/history`: the most recent exchanges. */
+export function formatRecentHistory(entries: HistoryEntry[]): string {
+  if (entries.length === 0) {
+    return "No history yet.";
+  }
+  const lines = entries.map((entry, i) => {
+    const answer = oneLine(entry.error ?? entry.response);
+    const preview =
+      answer.length > PREVIEW_CHARS ? `${answer.slice(0, PREVIEW_CHARS)}…` : answer;
+    return preview ? `${entryHeading(entry, i)}\n> ${preview}` : entryHeading(entry, i);
+  });
+  return [...lines, "Search with `/history <words>`"].join("\n\n");
+}
@@ -0,0 +1,128 @@
+/** Mirrors the Rust `JobState` enum. */
+export type JobState = "streaming" | "tool_running" | "done" | "error" | "aborted";
+
+/** Mirrors the Rust `JobSummary` struct: a prompt as listed in the notch. */
+export type JobSummary = {
+  id: number;
+  label: string;
+  state: JobState;
+  /** Unix ms. */
+  startedAt: number;
+  /** Characters of response received so far. */
+  textLen: number;
+  /** Tokens used, when reported. */
+  tokens: number | null;
+  /** From the prompt until it finished, or until this summary was made. */
+  elapsedMs: number;
+  /** First line of the response once it finished. */
+  preview: string | null;
+};
+
+/** Mirrors the Rust `JobSnapshot` struct, sent with "notch-restore". */
+export type JobSnapshot = JobSummary & { text: string };
+
+/** Mirrors the Rust `NotchSettings` struct. */
+export type NotchSettings = {
+  /** Seconds the notch stays once every answer arrived; `null` keeps it. */
+  autoHideSecs: number | null;
+};
+
+export const DEFAULT_NOTCH_SETTINGS: NotchSettings = { autoHideSecs: 4 };
+
+export function normalizeNotchSettings(value: unknown): NotchSettings {
+  if (!value || typeof value !== "object") {
+    return DEFAULT_NOTCH_SETTINGS;
+  }
+
+  const secs = (value as Record<string, unknown>).autoHideSecs;
+  if (secs === null) {
+    return { autoHideSecs: null };
+  }
+  return {
+    autoHideSecs:
+      typeof secs === "number" && Number.isFinite(secs) && secs >= 1
+        ? Math.floor(secs)
+        : DEFAULT_NOTCH_SETTINGS.autoHideSecs,
+  };
+}
+
+/** How long the notch shows each job when there are several. */
+export const JOB_CYCLE_MS = 3000;
+
+/** Still waiting for the answer. */
+export function isActive(state: JobState): boolean {
+  return state === "streaming" || state === "tool_running";
+}
+
+export function countActive(jobs: JobSummary[]): number {
+  return jobs.filter((job) => isActive(job.state)).length;
+}
+
+/** "2/3" while several jobs share the notch, empty for a single one. */
+export function jobCounter(index: number, total: number): string {
+  return total > 1 ? `${index + 1}/${total}` : "";
+}
+
+export function nextJobIndex(index: number, total: number): number {
+  return total > 0 ? (index + 1) % total : 0;
+}
+
+/** Where job `id` sits in a new list, so the notch keeps showing it. */
+export function followJob(jobs: JobSummary[], id: number | undefined): number {
+  return Math.max(
+    jobs.findIndex((job) => job.id === id),
+    0,
+  );
+}
+
+/** The notch line for a finished job, without markdown. */
+export function notchPreview(job: JobSummary): string {
+  const clean = (job.preview ?? "").replace(/[#*_`~>\[\]()!]/g, "").trim();
+  switch (job.state) {
+    case "error":
+      return `Error: ${clean}`;
+    case "aborted":
+      return clean ? `Stopped: ${clean}` : "Stopped";
+    default:
+      return clean;
+  }
+}
+
+/** Tokens and time taken, e.g. "412 tokens · 1m 5s". */
+export function jobMeta(job: JobSummary): string {
+  const secs = Math.round(job.elapsedMs / 1000);
+  const elapsed = secs < 60 ? `${secs}s` : `${Math.floor(secs / 60)}m ${secs % 60}s`;
+  return job.tokens === null ? elapsed : `${job.tokens} tokens · ${elapsed}`;
+}
+
+/** What the panel shows for a job brought back from the notch. */
+export function jobResponse(job: JobSnapshot): {
+  assistantText: string;
+  streamingText: string;
+  thinking: boolean;
+} {
+  switch (job.state) {
+    case "streaming":
+    case "tool_running":
+      return { assistantText: "", streamingText: job.text, thinking: !job.text };
+    case "done":
+    case "aborted":
+      return { assistantText: job.text, streamingText: "", thinking: false };
+    case "error":
+      return {
+        assistantText: `Error: ${job.text}`,
+        streamingText: "",
+        thinking: false,
+      };
+  }
+}
+
+export async function listJobs(): Promise<JobSummary[]> {
+  try {
+    const { invoke } = await import("@tauri-apps/api/core");
+    return await invoke<JobSummary[]>("list_jobs");
+  } catch {
+    // Browser mode.
+    return [];
+  }
+}
@@ -1,4 +1,4 @@
-import type { WsClient } from "./ws-client";
+import type { GatewayClient } from "./ws-client";
 
 type CacheEntry =
   | { state: "loading" }
@@ -13,7 +13,7 @@ const cache = new Map<string, CacheEntry>();
  * component can re-render.
  */
 export function getMediaUrl(
-  client: WsClient,
+  client: GatewayClient,
   filePath: string,
   mimeType: string,
   onLoaded: () => void,
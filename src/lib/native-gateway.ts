@@ -0,0 +1,117 @@
+import type { ChatContext } from "./history";
+import { toCredentialsPayload, type AppSettings } from "./settings";
+import type {
+  BotEvent,
+  ConnectionState,
+  GatewayClient,
+  GatewayHandlers,
+} from "./ws-client";
+
+type GatewayStatus = {
+  state: ConnectionState;
+  note?: string | null;
+};
+
+export function isTauriRuntime(): boolean {
+  return typeof window !== "undefined" && "__TAURI_INTERNALS__" in window;
+}
+
+/**
+ * Gateway client backed by the Rust `gateway` module. The WebSocket lives in
+ * the Tauri process, so streaming continues while the panel is hidden. The
+ * panel only receives events for the prompt it shows; Rust tracks the rest
+ * as jobs listed in the notch.
+ */
+export class NativeGatewayClient implements GatewayClient {
+  private handlers: GatewayHandlers;
+  private state: ConnectionState = "idle";
+
+  constructor(handlers: GatewayHandlers) {
+    this.handlers = handlers;
+
+    import("@tauri-apps/api/event")
+      .then(({ listen }) =>
+        Promise.all([
+          listen<GatewayStatus>("gateway-state", (event) => {
+            this.setState(event.payload.state, event.payload.note ?? undefined);
+          }),
+          listen<BotEvent>("gateway-event", (event) => {
+            this.handlers.onEvent(event.payload);
+          }),
+        ]),
+      )
+      .catch(() => {
+        // Expected outside Tauri runtime.
+      });
+
+    void this.invoke<GatewayStatus>("gateway_status")
+      .then((status) => this.setState(status.state, status.note ?? undefined))
+      .catch(() => {
+        // Status is refreshed by the next "gateway-state" event.
+      });
+  }
+
+  get connected(): boolean {
+    return this.state === "connected";
+  }
+
+  get active(): boolean {
+    return this.state === "connecting" || this.state === "connected";
+  }
+
+  connect(settings: AppSettings): void {
+    void this.connectAndVerify(settings).catch(() => {
+      // State is reported through "gateway-state"; Rust keeps reconnecting.
+    });
+  }
+
+  connectAndVerify(settings: AppSettings): Promise<string> {
+    this.setState("connecting");
+    return this.invoke<string>("gateway_connect", {
+      settings: toCredentialsPayload(settings),
+    });
+  }
+
+  disconnect(): void {
+    void this.invoke("gateway_disconnect").catch(() => {
+      // Nothing to disconnect.
+    });
+  }
+
+  sendChatMessage(text: string, _settings?: AppSettings, context?: ChatContext): void {
+    if (!this.connected) {
+      throw new Error("Not connected");
+    }
+
+    void this.invoke("gateway_send_chat", {
+      message: text,
+      context: context ?? null,
+    }).catch((error) => {
+      this.handlers.onEvent({ kind: "error", text: String(error) });
+    });
+  }
+
+  request<T = unknown>(
+    method: string,
+    params?: Record<string, unknown>,
+  ): Promise<T> {
+    return this.invoke<T>("gateway_request", { method, params: params ?? null });
+  }
+
+  private setState(state: ConnectionState, note?: string): void {
+    this.state = state;
+    this.handlers.onState(state, note);
+  }
+
+  private async invoke<T>(
+    command: string,
+    args?: Record<string, unknown>,
+  ): Promise<T> {
+    const { invoke } = await import("@tauri-apps/api/core");
+    try {
+      return await invoke<T>(command, args);
+    } catch (error) {
+      throw error instanceof Error ? error : new Error(String(error));
+    }
+  }
+}
@@ -1,18 +1,31 @@
 import type { PanelMode } from "../constants/panel";
 import {
+  PANEL_HEIGHT,
   PANEL_INPUT_HEIGHT,
-  PANEL_MAX_HEIGHT,
   PANEL_RESPONSE_CHROME,
-  PANEL_SIZE,
-  PANEL_WIDTH,
 } from "../constants/panel";
 
-/** Resize window height only — Rust handles position on show. */
-async function setHeight(height: number): Promise<void> {
-  const { getCurrentWindow, LogicalSize } = await import(
-    "@tauri-apps/api/window"
-  );
-  await getCurrentWindow().setSize(new LogicalSize(PANEL_WIDTH, height));
+/** Mirrors the Rust `PanelState` enum. */
+export type PanelState = "hidden" | "compact" | "expanded" | "background" | "restoring";
+
+/** Payload of the Rust "panel-state" event. */
+export type PanelStateChange = { from: PanelState; to: PanelState };
+
+/**
+ * Whether Rust took the response away from the notch without restoring it,
+ * e.g. a selection shortcut claimed the panel.
+ */
+export function endsBackgroundMode(change: PanelStateChange): boolean {
+  return change.from === "background" && change.to !== "restoring";
+}
+
+/**
+ * Tell Rust the panel shows a view `height` points tall, a response when
+ * `expanded`. Rust sizes and places the window for the layout preset.
+ */
+async function setExpanded(expanded: boolean, height: number): Promise<void> {
+  const { invoke } = await import("@tauri-apps/api/core");
+  await invoke("set_panel_expanded", { expanded, height });
 }
 
 export async function hidePanelWindow(): Promise<void> {
@@ -24,13 +37,25 @@ export async function hidePanelWindow(): Promise<void> {
   }
 }
 
+/** Mirrors the Rust `WriteMode` enum. */
+export type WriteMode = "replace" | "insert";
+
+/**
+ * Paste `text` into the app the selection came from, over the selection or
+ * after it. Rejects with the Rust error message.
+ */
+export async function writeBackResponse(text: string, mode: WriteMode): Promise<void> {
+  const { invoke } = await import("@tauri-apps/api/core");
+  await invoke("write_back_response", { text, mode });
+}
+
 export async function resizePanelWindow(mode: PanelMode): Promise<void> {
   if (mode === "response") {
     return;
   }
 
   try {
-    await setHeight(PANEL_SIZE[mode].height);
+    await setExpanded(false, PANEL_HEIGHT[mode]);
   } catch {
     // Browser mode.
   }
@@ -39,16 +64,13 @@ export async function resizePanelWindow(mode: PanelMode): Promise<void> {
 export async function resizePanelToContent(
   contentHeight: number,
 ): Promise<void> {
-  const total = Math.min(
-    Math.max(
-      PANEL_INPUT_HEIGHT + contentHeight + PANEL_RESPONSE_CHROME,
-      PANEL_INPUT_HEIGHT + 48,
-    ),
-    PANEL_MAX_HEIGHT,
+  const total = Math.max(
+    PANEL_INPUT_HEIGHT + contentHeight + PANEL_RESPONSE_CHROME,
+    PANEL_INPUT_HEIGHT + 48,
   );
 
   try {
-    await setHeight(total);
+    await setExpanded(true, total);
   } catch {
     // Browser mode.
   }
@@ -71,15 +93,3 @@ export async function hideNotch(): Promise<void> {
     // Browser mode.
   }
 }
-
-export async function emitNotchState(
-  state: "streaming" | "ready",
-  preview?: string,
-): Promise<void> {
-  try {
-    const { emit } = await import("@tauri-apps/api/event");
-    await emit("notch-state", { state, preview });
-  } catch {
-    // Browser mode.
-  }
-}
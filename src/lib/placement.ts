@@ -0,0 +1,174 @@
+/** Mirrors the Rust `PlacementPolicy` enum. */
+export type PlacementPolicy = "cursor" | "focused_window" | "display" | "last_used";
+
+/** Mirrors the Rust `LayoutPreset` enum. */
+export type LayoutPreset = "compact" | "wide" | "sidebar";
+
+/** Mirrors the Rust `DockEdge` enum. */
+export type DockEdge = "left" | "right";
+
+/** Mirrors the Rust `PanelSize` struct, in logical points. */
+export type PanelSize = { width: number; height: number };
+
+/** Mirrors the Rust `PanelSettings` struct. */
+export type PanelSettings = {
+  placement: PlacementPolicy;
+  /** Display name used by the "display" policy. */
+  display: string;
+  /** Largest size the panel grows to for a response. */
+  expanded: PanelSize;
+  preset: LayoutPreset;
+  /** Side of the screen the sidebar preset docks to. */
+  dock: DockEdge;
+};
+
+/** Mirrors the Rust `Monitor` struct; geometry is in physical pixels. */
+export type DisplayInfo = {
+  name: string;
+  frame: { x: number; y: number; width: number; height: number };
+  scale: number;
+  primary: boolean;
+};
+
+export const PLACEMENT_POLICY_LABELS: Record<PlacementPolicy, string> = {
+  cursor: "Display with the mouse",
+  focused_window: "Display with the focused window",
+  display: "Fixed display",
+  last_used: "Last used display",
+};
+
+export const PLACEMENT_POLICIES = Object.keys(
+  PLACEMENT_POLICY_LABELS,
+) as PlacementPolicy[];
+
+export const LAYOUT_PRESET_LABELS: Record<LayoutPreset, string> = {
+  compact: "Compact strip",
+  wide: "Wide strip",
+  sidebar: "Sidebar",
+};
+
+export const LAYOUT_PRESETS = Object.keys(LAYOUT_PRESET_LABELS) as LayoutPreset[];
+
+export const DOCK_EDGE_LABELS: Record<DockEdge, string> = {
+  left: "Left edge",
+  right: "Right edge",
+};
+
+export const DOCK_EDGES = Object.keys(DOCK_EDGE_LABELS) as DockEdge[];
+
+export const DEFAULT_PANEL_SETTINGS: PanelSettings = {
+  placement: "cursor",
+  display: "",
+  expanded: { width: 750, height: 420 },
+  preset: "compact",
+  dock: "right",
+};
+
+/** `minWidth` / `minHeight` of the window; Rust clamps to these too. */
+export const MIN_PANEL_SIZE: PanelSize = { width: 640, height: 48 };
+
+function isPlacementPolicy(value: unknown): value is PlacementPolicy {
+  return PLACEMENT_POLICIES.includes(value as PlacementPolicy);
+}
+
+/** Whole points, at least the window minimum; `fallback` for unusable input. */
+export function parsePanelDimension(
+  value: unknown,
+  min: number,
+  fallback: number,
+): number {
+  const size = typeof value === "string" ? Number.parseInt(value, 10) : value;
+  return typeof size === "number" && Number.isFinite(size)
+    ? Math.max(Math.round(size), min)
+    : fallback;
+}
+
+function normalizePanelSize(value: unknown): PanelSize {
+  const fallback = DEFAULT_PANEL_SETTINGS.expanded;
+  if (!value || typeof value !== "object") {
+    return fallback;
+  }
+
+  const source = value as Record<string, unknown>;
+  return {
+    width: parsePanelDimension(source.width, MIN_PANEL_SIZE.width, fallback.width),
+    height: parsePanelDimension(source.height, MIN_PANEL_SIZE.height, fallback.height),
+  };
+}
+
+export function normalizePanelSettings(value: unknown): PanelSettings {
+  if (!value || typeof value !== "object") {
+    return DEFAULT_PANEL_SETTINGS;
+  }
+
+  const source = value as Record<string, unknown>;
+  return {
+    placement: isPlacementPolicy(source.placement)
+      ? source.placement
+      : DEFAULT_PANEL_SETTINGS.placement,
+    display:
+      typeof source.display === "string"
+        ? source.display
+        : DEFAULT_PANEL_SETTINGS.display,
+    expanded: normalizePanelSize(source.expanded),
+    preset: LAYOUT_PRESETS.includes(source.preset as LayoutPreset)
+      ? (source.preset as LayoutPreset)
+      : DEFAULT_PANEL_SETTINGS.preset,
+    dock: DOCK_EDGES.includes(source.dock as DockEdge)
+      ? (source.dock as DockEdge)
+      : DEFAULT_PANEL_SETTINGS.dock,
+  };
+}
+
+/** Option label for a display, e.g. "DELL U2720Q (2560×1440, main)". */
+export function describeDisplay(display: DisplayInfo): string {
+  const size = `${display.frame.width}×${display.frame.height}`;
+  const name = display.name || "Display";
+  return display.primary ? `${name} (${size}, main)` : `${name} (${size})`;
+}
+
+/**
+ * Choices for the fixed-display picker. A saved display that is not
+ * connected stays selectable so opening settings does not change it.
+ */
+export function displayOptions(
+  displays: DisplayInfo[],
+  selected: string,
+): { value: string; label: string }[] {
+  const options = displays.map((display) => ({
+    value: display.name,
+    label: describeDisplay(display),
+  }));
+  if (!displays.some((display) => display.name === selected)) {
+    options.unshift({
+      value: selected,
+      label: selected ? `${selected} (not connected)` : "Choose a display",
+    });
+  }
+  return options;
+}
+
+/** Connected displays; empty outside the desktop app. */
+export async function listDisplays(): Promise<DisplayInfo[]> {
+  try {
+    const { invoke } = await import("@tauri-apps/api/core");
+    return await invoke<DisplayInfo[]>("list_displays");
+  } catch {
+    // Browser mode.
+    return [];
+  }
+}
+
+/**
+ * Forget where the panel was dragged and its expanded size. Resolves to the
+ * panel settings Rust saved, or null outside the desktop app.
+ */
+export async function resetPanelLayout(): Promise<PanelSettings | null> {
+  try {
+    const { invoke } = await import("@tauri-apps/api/core");
+    return normalizePanelSettings(await invoke<PanelSettings>("reset_panel_layout"));
+  } catch {
+    // Browser mode.
+    return null;
+  }
+}
@@ -0,0 +1,55 @@
+import { isTauriRuntime } from "./native-gateway";
+import {
+  fromCredentialsPayload,
+  type AppSettings,
+  type KeychainCredentials,
+} from "./settings";
+
+/** Mirrors the Rust `ProfileSummary` struct. */
+export type ProfileSummary = {
+  name: string;
+  gatewayUrl: string;
+  agentId: string;
+  active: boolean;
+};
+
+const BROWSER_MODE_ERROR = "Profiles are available only in the desktop app";
+
+async function invokeProfiles<T>(
+  command: string,
+  args?: Record<string, unknown>,
+): Promise<T> {
+  if (!isTauriRuntime()) {
+    throw new Error(BROWSER_MODE_ERROR);
+  }
+
+  const { invoke } = await import("@tauri-apps/api/core");
+  try {
+    return await invoke<T>(command, args);
+  } catch (error) {
+    throw error instanceof Error ? error : new Error(String(error));
+  }
+}
+
+export function listProfiles(): Promise<ProfileSummary[]> {
+  return invokeProfiles<ProfileSummary[]>("list_profiles");
+}
+
+/** Activate a profile and return its settings for reconnecting. */
+export async function activateProfile(name: string): Promise<AppSettings> {
+  const creds = await invokeProfiles<KeychainCredentials>("activate_profile", {
+    name,
+  });
+  return fromCredentialsPayload(creds);
+}
+
+export function formatProfileList(profiles: ProfileSummary[]): string {
+  if (profiles.length === 0) {
+    return "No profiles.";
+  }
+  const lines = profiles.map(
+    (profile) =>
+      `${profile.active ? "* " : "  "}${profile.name} — ${profile.gatewayUrl}`,
+  );
+  return [...lines, "", "Switch with /profile <name>"].join("\n");
+}
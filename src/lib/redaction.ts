@@ -0,0 +1,50 @@
+/** Mirrors the Rust `RedactionSettings` struct. */
+export type RedactionSettings = {
+  enabled: boolean;
+  /** Also redact typed prompts, not just captured selections. */
+  redactPrompts: boolean;
+  highEntropy: boolean;
+  /** Extra regular expressions (Rust `regex` syntax), one per entry. */
+  patterns: string[];
+};
+
+export const DEFAULT_REDACTION_SETTINGS: RedactionSettings = {
+  enabled: true,
+  redactPrompts: false,
+  highEntropy: true,
+  patterns: [],
+};
+
+function asFlag(value: unknown, fallback: boolean): boolean {
+  return typeof value === "boolean" ? value : fallback;
+}
+
+export function normalizeRedactionSettings(value: unknown): RedactionSettings {
+  if (!value || typeof value !== "object") {
+    return DEFAULT_REDACTION_SETTINGS;
+  }
+
+  const source = value as Record<string, unknown>;
+  return {
+    enabled: asFlag(source.enabled, DEFAULT_REDACTION_SETTINGS.enabled),
+    redactPrompts: asFlag(
+      source.redactPrompts,
+      DEFAULT_REDACTION_SETTINGS.redactPrompts,
+    ),
+    highEntropy: asFlag(source.highEntropy, DEFAULT_REDACTION_SETTINGS.highEntropy),
+    patterns: Array.isArray(source.patterns)
+      ? source.patterns.filter(
+          (pattern): pattern is string =>
+            typeof pattern === "string" && pattern.trim().length > 0,
+        )
+      : DEFAULT_REDACTION_SETTINGS.patterns,
+  };
+}
+
+/** Badge suffix warning that secrets were replaced. */
+export function redactionNotice(count: number | undefined): string {
+  if (!count) {
+    return "";
+  }
+  return count === 1 ? "1 secret hidden" : `${count} secrets hidden`;
+}
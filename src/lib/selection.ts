@@ -0,0 +1,173 @@
+/** Mirrors the Rust `SelectionStrategy` enum. */
+export type SelectionStrategy =
+  | "accessibility"
+  | "simulated_copy"
+  | "clipboard"
+  | "primary_selection"
+  | "clipboard_history";
+
+/** Mirrors the Rust `TruncationMode` enum. */
+export type TruncationMode = "head" | "tail" | "head_tail";
+
+/** Mirrors the Rust `SourceApp` struct: where a selection came from. */
+export type SourceApp = {
+  name: string;
+  /** Bundle identifier on macOS, `WM_CLASS` instance on X11. */
+  appId?: string | null;
+  windowTitle?: string | null;
+  documentPath?: string | null;
+  url?: string | null;
+};
+
+/** Mirrors the Rust `AppPrompt` struct. */
+export type AppPrompt = {
+  /** App name or identifier, compared case-insensitively. */
+  app: string;
+  prompt: string;
+};
+
+/** Mirrors the Rust `SelectionSettings` struct. */
+export type SelectionSettings = {
+  /** Tried in order; the first one that finds text wins. */
+  strategies: SelectionStrategy[];
+  copyWaitMs: number;
+  copyPollMs: number;
+  clipboardHistory: number;
+  /** Longer selections are cut down to this many characters. */
+  maxChars: number;
+  truncation: TruncationMode;
+  truncateAtLines: boolean;
+  /** Prompts prefilled for selections from specific apps. */
+  appPrompts: AppPrompt[];
+};
+
+export const SELECTION_STRATEGY_LABELS: Record<SelectionStrategy, string> = {
+  accessibility: "Accessibility",
+  simulated_copy: "Simulated copy",
+  clipboard: "Clipboard",
+  primary_selection: "Primary selection",
+  clipboard_history: "Clipboard history",
+};
+
+export const SELECTION_STRATEGIES = Object.keys(
+  SELECTION_STRATEGY_LABELS,
+) as SelectionStrategy[];
+
+export const TRUNCATION_MODE_LABELS: Record<TruncationMode, string> = {
+  head: "Keep start",
+  tail: "Keep end",
+  head_tail: "Keep start and end",
+};
+
+export const TRUNCATION_MODES = Object.keys(TRUNCATION_MODE_LABELS) as TruncationMode[];
+
+/** macOS defaults; Rust sends its platform defaults with the settings. */
+export const DEFAULT_SELECTION_SETTINGS: SelectionSettings = {
+  strategies: ["accessibility", "simulated_copy"],
+  copyWaitMs: 420,
+  copyPollMs: 35,
+  clipboardHistory: 5,
+  maxChars: 12_000,
+  truncation: "head_tail",
+  truncateAtLines: true,
+  appPrompts: [],
+};
+
+function asCount(value: unknown, fallback: number): number {
+  return typeof value === "number" && Number.isFinite(value) && value >= 0
+    ? Math.floor(value)
+    : fallback;
+}
+
+export function isSelectionStrategy(value: unknown): value is SelectionStrategy {
+  return SELECTION_STRATEGIES.includes(value as SelectionStrategy);
+}
+
+function isAppPrompt(value: unknown): value is AppPrompt {
+  if (!value || typeof value !== "object") {
+    return false;
+  }
+  const { app, prompt } = value as Record<string, unknown>;
+  return typeof app === "string" && typeof prompt === "string";
+}
+
+function isTruncationMode(value: unknown): value is TruncationMode {
+  return TRUNCATION_MODES.includes(value as TruncationMode);
+}
+
+export function normalizeSelectionSettings(value: unknown): SelectionSettings {
+  if (!value || typeof value !== "object") {
+    return DEFAULT_SELECTION_SETTINGS;
+  }
+
+  const source = value as Record<string, unknown>;
+  const strategies = Array.isArray(source.strategies)
+    ? [...new Set(source.strategies.filter(isSelectionStrategy))]
+    : DEFAULT_SELECTION_SETTINGS.strategies;
+  return {
+    strategies,
+    copyWaitMs: asCount(source.copyWaitMs, DEFAULT_SELECTION_SETTINGS.copyWaitMs),
+    copyPollMs: asCount(source.copyPollMs, DEFAULT_SELECTION_SETTINGS.copyPollMs),
+    clipboardHistory: asCount(
+      source.clipboardHistory,
+      DEFAULT_SELECTION_SETTINGS.clipboardHistory,
+    ),
+    maxChars: asCount(source.maxChars, DEFAULT_SELECTION_SETTINGS.maxChars),
+    truncation: isTruncationMode(source.truncation)
+      ? source.truncation
+      : DEFAULT_SELECTION_SETTINGS.truncation,
+    truncateAtLines:
+      typeof source.truncateAtLines === "boolean"
+        ? source.truncateAtLines
+        : DEFAULT_SELECTION_SETTINGS.truncateAtLines,
+    appPrompts: Array.isArray(source.appPrompts)
+      ? source.appPrompts
+          .filter(isAppPrompt)
+          .map(({ app, prompt }) => ({ app, prompt }))
+      : DEFAULT_SELECTION_SETTINGS.appPrompts,
+  };
+}
+
+/** "Terminal / main.rs": the app plus the file, page or window it showed. */
+export function describeSourceApp(app: SourceApp): string {
+  const detail = app.documentPath
+    ? app.documentPath.split("/").filter(Boolean).pop()
+    : app.url || app.windowTitle;
+  return detail && detail !== app.name ? `${app.name} / ${detail}` : app.name;
+}
+
+/** Enabled strategies in chain order, then the disabled ones. */
+export function strategyRows(
+  strategies: SelectionStrategy[],
+): { strategy: SelectionStrategy; enabled: boolean }[] {
+  return [
+    ...strategies.map((strategy) => ({ strategy, enabled: true })),
+    ...SELECTION_STRATEGIES.filter((s) => !strategies.includes(s)).map(
+      (strategy) => ({ strategy, enabled: false }),
+    ),
+  ];
+}
+
+/** Enable (appended last) or disable a strategy. */
+export function toggleStrategy(
+  strategies: SelectionStrategy[],
+  strategy: SelectionStrategy,
+  enabled: boolean,
+): SelectionStrategy[] {
+  const rest = strategies.filter((s) => s !== strategy);
+  return enabled ? [...rest, strategy] : rest;
+}
+
+/** Move an enabled strategy one step earlier in the chain. */
+export function moveStrategyUp(
+  strategies: SelectionStrategy[],
+  strategy: SelectionStrategy,
+): SelectionStrategy[] {
+  const index = strategies.indexOf(strategy);
+  if (index <= 0) {
+    return strategies;
+  }
+  const next = [...strategies];
+  [next[index - 1], next[index]] = [next[index], next[index - 1]];
+  return next;
+}
@@ -1,10 +1,46 @@
+import {
+  DEFAULT_HISTORY_SETTINGS,
+  normalizeHistorySettings,
+  type HistorySettings,
+} from "./history";
+import {
+  DEFAULT_NOTCH_SETTINGS,
+  normalizeNotchSettings,
+  type NotchSettings,
+} from "./jobs";
+import {
+  DEFAULT_PANEL_SETTINGS,
+  normalizePanelSettings,
+  type PanelSettings,
+} from "./placement";
+import {
+  DEFAULT_REDACTION_SETTINGS,
+  normalizeRedactionSettings,
+  type RedactionSettings,
+} from "./redaction";
+import {
+  DEFAULT_SELECTION_SETTINGS,
+  normalizeSelectionSettings,
+  type SelectionSettings,
+} from "./selection";
+import {
+  DEFAULT_SHORTCUT_BINDINGS,
+  normalizeShortcutBindings,
+  type ShortcutBinding,
+} from "./shortcuts";
+
 export type AppSettings = {
   gatewayUrl: string;
   token: string;
   password: string;
   agentId: string;
   sessionKey: string;
-  shortcuts: [string, string, string];
+  shortcuts: ShortcutBinding[];
+  history: HistorySettings;
+  selection: SelectionSettings;
+  redaction: RedactionSettings;
+  panel: PanelSettings;
+  notch: NotchSettings;
 };
 
 export const DEFAULT_SETTINGS: AppSettings = {
@@ -13,7 +49,12 @@ export const DEFAULT_SETTINGS: AppSettings = {
   password: "",
   agentId: "",
   sessionKey: "main",
-  shortcuts: ["CmdOrCtrl+Shift+Space", "CmdOrCtrl+Shift+K", "Alt+Space"],
+  shortcuts: DEFAULT_SHORTCUT_BINDINGS,
+  history: DEFAULT_HISTORY_SETTINGS,
+  selection: DEFAULT_SELECTION_SETTINGS,
+  redaction: DEFAULT_REDACTION_SETTINGS,
+  panel: DEFAULT_PANEL_SETTINGS,
+  notch: DEFAULT_NOTCH_SETTINGS,
 };
 
 const LOCAL_FALLBACK_KEY = "macclaw.panel.settings";
@@ -22,36 +63,27 @@ type LocalFallbackSettings = {
   gatewayUrl?: string;
   agentId?: string;
   sessionKey?: string;
-  shortcuts?: [string, string, string];
+  shortcuts?: unknown;
 };
 
-type KeychainCredentials = {
+export type KeychainCredentials = {
   gatewayUrl?: string;
   token?: string;
   password?: string;
   agentId?: string;
   sessionKey?: string;
-  shortcuts?: string[] | null;
+  shortcuts?: ShortcutBinding[] | null;
+  history?: HistorySettings;
+  selection?: SelectionSettings;
+  redaction?: RedactionSettings;
+  panel?: PanelSettings;
+  notch?: NotchSettings;
 };
 
 function asText(value: unknown, fallback = ""): string {
   return typeof value === "string" ? value : fallback;
 }
 
-function asShortcuts(
-  value: unknown,
-  fallback: [string, string, string],
-): [string, string, string] {
-  if (
-    Array.isArray(value) &&
-    value.length === 3 &&
-    value.every((v) => typeof v === "string" && v.length > 0)
-  ) {
-    return value as [string, string, string];
-  }
-  return fallback;
-}
-
 export function normalizeSettings(input?: Partial<AppSettings>): AppSettings {
   const source = input ?? {};
 
@@ -61,7 +93,15 @@ export function normalizeSettings(input?: Partial<AppSettings>): AppSettings {
     password: asText(source.password, DEFAULT_SETTINGS.password),
     agentId: asText(source.agentId, DEFAULT_SETTINGS.agentId),
     sessionKey: asText(source.sessionKey, DEFAULT_SETTINGS.sessionKey),
-    shortcuts: asShortcuts(source.shortcuts, DEFAULT_SETTINGS.shortcuts),
+    shortcuts: normalizeShortcutBindings(
+      source.shortcuts,
+      DEFAULT_SETTINGS.shortcuts,
+    ),
+    history: normalizeHistorySettings(source.history),
+    selection: normalizeSelectionSettings(source.selection),
+    redaction: normalizeRedactionSettings(source.redaction),
+    panel: normalizePanelSettings(source.panel),
+    notch: normalizeNotchSettings(source.notch),
   };
 }
 
@@ -81,7 +121,7 @@ function loadFromLocalStorage(): AppSettings {
       gatewayUrl: parsed.gatewayUrl,
       agentId: parsed.agentId,
       sessionKey: parsed.sessionKey,
-      shortcuts: parsed.shortcuts,
+      shortcuts: parsed.shortcuts as ShortcutBinding[] | undefined,
       token: "",
       password: "",
     });
@@ -112,36 +152,76 @@ export async function loadSettings(): Promise<AppSettings> {
   try {
     const { invoke } = await import("@tauri-apps/api/core");
     const creds = await invoke<KeychainCredentials>("load_credentials");
-
-    return normalizeSettings({
-      gatewayUrl: creds.gatewayUrl,
-      token: creds.token,
-      password: creds.password,
-      agentId: creds.agentId,
-      sessionKey: creds.sessionKey,
-      shortcuts: (creds.shortcuts ?? undefined) as
-        | [string, string, string]
-        | undefined,
-    });
+    return fromCredentialsPayload(creds);
   } catch {
     return loadFromLocalStorage();
   }
 }
 
+/** Settings from a Rust `Credentials` value. */
+export function fromCredentialsPayload(creds: KeychainCredentials): AppSettings {
+  return normalizeSettings({
+    gatewayUrl: creds.gatewayUrl,
+    token: creds.token,
+    password: creds.password,
+    agentId: creds.agentId,
+    sessionKey: creds.sessionKey,
+    shortcuts: creds.shortcuts ?? undefined,
+    history: creds.history,
+    selection: creds.selection,
+    redaction: creds.redaction,
+    panel: creds.panel,
+    notch: creds.notch,
+  });
+}
+
+/** Mirrors the Rust `ConfigIssue` struct. */
+export type ConfigIssue = {
+  kind: "unreadable" | "migrated" | "secrets";
+  message: string;
+  backupPath?: string | null;
+};
+
+/** Problems the Rust side hit while loading the settings file. */
+export async function loadConfigIssues(): Promise<ConfigIssue[]> {
+  try {
+    const { invoke } = await import("@tauri-apps/api/core");
+    return await invoke<ConfigIssue[]>("config_load_report");
+  } catch {
+    return [];
+  }
+}
+
+export function describeConfigIssue(issue: ConfigIssue): string {
+  return issue.backupPath
+    ? `${issue.message} (backup: ${issue.backupPath})`
+    : issue.message;
+}
+
+/** Shape expected by the Rust `Credentials` struct. */
+export function toCredentialsPayload(settings: AppSettings): KeychainCredentials {
+  return {
+    gatewayUrl: settings.gatewayUrl,
+    token: settings.token,
+    password: settings.password,
+    agentId: settings.agentId,
+    sessionKey: settings.sessionKey,
+    shortcuts: settings.shortcuts,
+    history: settings.history,
+    selection: settings.selection,
+    redaction: settings.redaction,
+    panel: settings.panel,
+    notch: settings.notch,
+  };
+}
+
 export async function saveSettings(settings: AppSettings): Promise<void> {
   const normalized = normalizeSettings(settings);
 
   try {
     const { invoke } = await import("@tauri-apps/api/core");
     await invoke("save_credentials", {
-      creds: {
-        gatewayUrl: normalized.gatewayUrl,
-        token: normalized.token,
-        password: normalized.password,
-        agentId: normalized.agentId,
-        sessionKey: normalized.sessionKey,
-        shortcuts: normalized.shortcuts,
-      },
+      creds: toCredentialsPayload(normalized),
     });
     return;
   } catch {
@@ -0,0 +1,189 @@
+/** Mirrors the Rust `ShortcutAction` enum (tagged by `type`). */
+export type ShortcutAction =
+  | { type: "toggle_panel" }
+  | { type: "capture_selection" }
+  | { type: "restore_notch" }
+  | { type: "open_profile"; profile: string }
+  | { type: "run_prompt"; prompt: string }
+  | { type: "abort_streaming" };
+
+export type ShortcutActionType = ShortcutAction["type"];
+
+/** Mirrors the Rust `ShortcutBinding` struct. */
+export type ShortcutBinding = {
+  accelerator: string;
+  action: ShortcutAction;
+};
+
+export const SELECTION_SHORTCUT = "CmdOrCtrl+Shift+L";
+
+export const DEFAULT_SHORTCUT_BINDINGS: ShortcutBinding[] = [
+  { accelerator: "CmdOrCtrl+Shift+Space", action: { type: "toggle_panel" } },
+  { accelerator: "CmdOrCtrl+Shift+K", action: { type: "toggle_panel" } },
+  { accelerator: "Alt+Space", action: { type: "toggle_panel" } },
+  { accelerator: SELECTION_SHORTCUT, action: { type: "capture_selection" } },
+];
+
+export const SHORTCUT_ACTION_LABELS: Record<ShortcutActionType, string> = {
+  toggle_panel: "Toggle panel",
+  capture_selection: "Capture selection",
+  restore_notch: "Restore from notch",
+  open_profile: "Open profile",
+  run_prompt: "Run prompt",
+  abort_streaming: "Abort response",
+};
+
+export const SHORTCUT_ACTION_TYPES = Object.keys(
+  SHORTCUT_ACTION_LABELS,
+) as ShortcutActionType[];
+
+/** Action of the given type, keeping the text argument where it applies. */
+export function makeShortcutAction(
+  type: ShortcutActionType,
+  argument = "",
+): ShortcutAction {
+  switch (type) {
+    case "open_profile":
+      return { type, profile: argument };
+    case "run_prompt":
+      return { type, prompt: argument };
+    default:
+      return { type };
+  }
+}
+
+/** Profile name or prompt text of an action, "" for actions without one. */
+export function shortcutActionArgument(action: ShortcutAction): string {
+  switch (action.type) {
+    case "open_profile":
+      return action.profile;
+    case "run_prompt":
+      return action.prompt;
+    default:
+      return "";
+  }
+}
+
+function asAction(value: unknown): ShortcutAction | null {
+  if (!value || typeof value !== "object") {
+    return null;
+  }
+
+  const { type, profile, prompt } = value as Record<string, unknown>;
+  if (!SHORTCUT_ACTION_TYPES.includes(type as ShortcutActionType)) {
+    return null;
+  }
+  if (type === "open_profile") {
+    return typeof profile === "string" ? { type, profile } : null;
+  }
+  if (type === "run_prompt") {
+    return typeof prompt === "string" ? { type, prompt } : null;
+  }
+  return { type: type as ShortcutActionType } as ShortcutAction;
+}
+
+function asBinding(value: unknown): ShortcutBinding | null {
+  // Settings saved before actions existed hold bare toggle accelerators.
+  if (typeof value === "string") {
+    return value ? { accelerator: value, action: { type: "toggle_panel" } } : null;
+  }
+  if (!value || typeof value !== "object") {
+    return null;
+  }
+
+  const { accelerator, action } = value as Record<string, unknown>;
+  const parsedAction = asAction(action);
+  if (typeof accelerator !== "string" || !accelerator || !parsedAction) {
+    return null;
+  }
+  return { accelerator, action: parsedAction };
+}
+
+/**
+ * Bindings from stored settings. Unusable entries are dropped; a list of
+ * legacy accelerator strings also gets the selection shortcut that used to
+ * be built in.
+ */
+export function normalizeShortcutBindings(
+  value: unknown,
+  fallback: ShortcutBinding[] = DEFAULT_SHORTCUT_BINDINGS,
+): ShortcutBinding[] {
+  if (!Array.isArray(value)) {
+    return fallback;
+  }
+
+  const bindings = value
+    .map(asBinding)
+    .filter((binding): binding is ShortcutBinding => binding !== null);
+  if (bindings.length === 0) {
+    return fallback;
+  }
+
+  const legacy = value.every((entry) => typeof entry === "string");
+  if (legacy && !bindings.some((b) => b.accelerator === SELECTION_SHORTCUT)) {
+    bindings.push({
+      accelerator: SELECTION_SHORTCUT,
+      action: { type: "capture_selection" },
+    });
+  }
+  return bindings;
+}
+
+/** Mirrors the Rust `BindingProblem` enum (tagged by `kind`). */
+export type BindingProblem =
+  | { kind: "parse"; message: string }
+  | { kind: "duplicate"; index: number }
+  | { kind: "registration"; message: string };
+
+/** Mirrors the Rust `ShortcutReport` struct. */
+export type ShortcutReport = {
+  bindings: (ShortcutBinding & { problem?: BindingProblem | null })[];
+  applied: boolean;
+  rolledBack: boolean;
+  error?: string | null;
+};
+
+function describeProblem(
+  problem: BindingProblem,
+  bindings: ShortcutReport["bindings"],
+): string {
+  switch (problem.kind) {
+    case "parse":
+      return `invalid shortcut (${problem.message})`;
+    case "duplicate":
+      return `same keys as ${bindings[problem.index]?.accelerator ?? "another binding"}`;
+    case "registration":
+      return `could not be registered (${problem.message})`;
+  }
+}
+
+/** One line per binding that is not active, plus the overall error. */
+export function describeShortcutReport(report: ShortcutReport): string[] {
+  const lines = report.bindings.flatMap((binding) =>
+    binding.problem
+      ? [
+          `${binding.accelerator || "(empty)"} → ${SHORTCUT_ACTION_LABELS[binding.action.type]}: ${describeProblem(binding.problem, report.bindings)}`,
+        ]
+      : [],
+  );
+  return report.error ? [report.error, ...lines] : lines;
+}
+
+/** Register bindings with the OS; Rust saves them when they were applied. */
+export async function updateShortcuts(
+  bindings: ShortcutBinding[],
+): Promise<ShortcutReport> {
+  const { invoke } = await import("@tauri-apps/api/core");
+  return invoke<ShortcutReport>("update_shortcuts", { shortcuts: bindings });
+}
+
+/** Problems from the most recent registration (e.g. at startup). */
+export async function loadShortcutIssues(): Promise<string[]> {
+  try {
+    const { invoke } = await import("@tauri-apps/api/core");
+    const report = await invoke<ShortcutReport | null>("shortcut_report");
+    return report ? describeShortcutReport(report) : [];
+  } catch {
+    return [];
+  }
+}
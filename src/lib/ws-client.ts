@@ -1,4 +1,5 @@
 import { extractText, extractTextWithMedia, isJsonMap, parseFrame, type JsonMap } from "./extract-text";
+import type { ChatContext } from "./history";
 import type { AppSettings } from "./settings";
 
 export { extractText } from "./extract-text";
@@ -10,17 +11,31 @@ export type BotEvent =
   | { kind: "assistant_delta"; text: string }
   | { kind: "assistant_done" }
   | { kind: "error"; text: string }
+  /** The answer was stopped; the text streamed so far stays. */
+  | { kind: "aborted" }
   | { kind: "info"; text: string };
 
-type Handlers = {
+export type GatewayHandlers = {
   onState: (state: ConnectionState, note?: string) => void;
   onEvent: (event: BotEvent) => void;
 };
 
-export class WsClient {
+/** Surface shared by the webview WebSocket client and the native Rust client. */
+export interface GatewayClient {
+  readonly connected: boolean;
+  readonly active: boolean;
+  connect(settings: AppSettings): void;
+  connectAndVerify(settings: AppSettings, timeoutMs?: number): Promise<string>;
+  disconnect(): void;
+  /** `context` is recorded in the history instead of `text` (native only). */
+  sendChatMessage(text: string, settings: AppSettings, context?: ChatContext): void;
+  request<T = unknown>(method: string, params?: Record<string, unknown>): Promise<T>;
+}
+
+export class WsClient implements GatewayClient {
   private ws: WebSocket | null = null;
   private requestId = 1;
-  private handlers: Handlers;
+  private handlers: GatewayHandlers;
   private authenticated = false;
   private pendingResponses = new Map<
     string,
@@ -31,7 +46,7 @@ export class WsClient {
   private reconnectDelay = 1000;
   private intentionalDisconnect = false;
 
-  constructor(handlers: Handlers) {
+  constructor(handlers: GatewayHandlers) {
     this.handlers = handlers;
   }
 
@@ -362,16 +377,21 @@ export class WsClient {
 
         if (state === "final") {
           const messageText = extractTextWithMedia(payload.message);
-          this.handlers.onEvent({ kind: "assistant_done" });
           if (messageText) {
             this.handlers.onEvent({ kind: "assistant", text: messageText });
           }
+          this.handlers.onEvent({ kind: "assistant_done" });
+          return;
+        }
+
+        if (state === "aborted") {
+          this.handlers.onEvent({ kind: "aborted" });
           return;
         }
 
         const messageText = extractText(payload.message);
 
-        if (state === "error" || state === "aborted") {
+        if (state === "error") {
           const errText = typeof payload.errorMessage === "string"
             ? payload.errorMessage
             : messageText || "Agent error.";
@@ -1,28 +1,90 @@
 import { listen } from "@tauri-apps/api/event";
 import { invoke } from "@tauri-apps/api/core";
+import {
+  followJob,
+  isActive,
+  JOB_CYCLE_MS,
+  jobCounter,
+  jobMeta,
+  listJobs,
+  nextJobIndex,
+  notchPreview,
+  type JobSummary,
+} from "./lib/jobs";
 
-document.body.addEventListener("click", () => invoke("notch_clicked"));
+let jobs: JobSummary[] = [];
+let index = 0;
 
-listen("notch-state", (e) => {
+// Restore the job the notch is showing.
+document.body.addEventListener("click", () =>
+  invoke("notch_clicked", { job: jobs[index]?.id ?? null }),
+);
+
+function indicatorClass(job: JobSummary | undefined): string {
+  switch (job?.state) {
+    case undefined:
+    case "streaming":
+      return "indicator--streaming";
+    case "tool_running":
+      return "indicator--streaming indicator--tool";
+    case "error":
+      return "indicator--ready indicator--error";
+    default:
+      return "indicator--ready";
+  }
+}
+
+function render() {
   const el = document.getElementById("indicator");
+  const labelEl = document.getElementById("label");
   const previewEl = document.getElementById("preview");
+  const metaEl = document.getElementById("meta");
+  const countEl = document.getElementById("count");
   if (!el) return;
 
-  const payload = e.payload as { state: string; preview?: string };
-  el.className = "notch-wrap indicator--" + payload.state;
+  const job = jobs[index];
+  const running = !job || isActive(job.state);
+  el.className = "notch-wrap " + indicatorClass(job);
 
-  if (previewEl) {
-    if (payload.state === "ready" && payload.preview) {
-      // Strip markdown and take first line.
-      const clean = payload.preview
-        .replace(/[#*_`~>\[\]()!]/g, "")
-        .trim();
-      const firstLine = clean.split("\n").find((l) => l.trim().length > 0) ?? clean;
-      previewEl.textContent = firstLine.slice(0, 80);
-      previewEl.style.display = "block";
-    } else {
-      previewEl.textContent = "";
-      previewEl.style.display = "none";
+  if (labelEl) {
+    // Only needed to tell several prompts apart, or to name the tool step.
+    let label = job && jobs.length > 1 ? job.label : "";
+    if (job?.state === "tool_running") {
+      label = label ? `${label} · running a tool` : "Running a tool";
     }
+    labelEl.textContent = label;
+    labelEl.style.display = running && label ? "block" : "none";
+  }
+  if (previewEl) {
+    previewEl.textContent = job && !running ? notchPreview(job) : "";
+    previewEl.style.display = running ? "none" : "block";
   }
+  if (metaEl) {
+    metaEl.textContent = job && !running ? jobMeta(job) : "";
+    metaEl.style.display = running ? "none" : "block";
+  }
+  if (countEl) {
+    countEl.textContent = jobCounter(index, jobs.length);
+  }
+}
+
+function update(next: JobSummary[]) {
+  index = followJob(next, jobs[index]?.id);
+  jobs = next;
+  render();
+}
+
+listen<JobSummary[]>("jobs-changed", (e) => update(e.payload));
+// Jump to a job that changed state, so a finished answer shows at once.
+listen<JobSummary>("notch-state", (e) => {
+  index = followJob(jobs, e.payload.id);
+  render();
 });
+void listJobs().then(update);
+
+setInterval(() => {
+  if (jobs.length > 1) {
+    index = nextJobIndex(index, jobs.length);
+    render();
+  }
+}, JOB_CYCLE_MS);